@@ -0,0 +1,273 @@
+// Runs every script in tests/test_cases/benchmark through `Lox` with the output
+// discarded and reports wall time and heap allocations per script.
+//
+//   cargo bench --bench benchmarks -- [filter...] [--runs N] [--save-baseline NAME] [--baseline NAME] [--threshold PERCENT]
+//
+// `--save-baseline` stores the results under target/lox-bench, `--baseline` compares
+// against a stored run and exits with a failure when a script got slower than the
+// threshold (10% by default), so CI can gate on it. Scripts that use what this
+// interpreter does not support are skipped and listed apart.
+
+use rlox::interpreter::Output;
+use rlox::lox::{Lox, Status};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+const BENCHMARKS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/test_cases/benchmark");
+const BASELINES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/target/lox-bench");
+
+// the scripts that can't run here, and why
+const UNSUPPORTED: &[(&str, &str)] = &[("method_call", "inheritance")];
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Measurement {
+    name: String,
+    time: Duration,
+    allocations: usize,
+    bytes: usize,
+}
+
+impl Measurement {
+    fn parse(line: &str) -> Option<Measurement> {
+        let mut fields = line.split_whitespace();
+        Some(Measurement {
+            name: fields.next()?.to_string(),
+            time: Duration::from_nanos(fields.next()?.parse().ok()?),
+            allocations: fields.next()?.parse().ok()?,
+            bytes: fields.next()?.parse().ok()?,
+        })
+    }
+
+    fn serialize(&self) -> String {
+        format!("{} {} {} {}", self.name, self.time.as_nanos(), self.allocations, self.bytes)
+    }
+}
+
+struct Options {
+    filters: Vec<String>,
+    runs: usize,
+    save_baseline: Option<String>,
+    baseline: Option<String>,
+    threshold: f64,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: cargo bench --bench benchmarks -- [filter...] [--runs N] [--save-baseline NAME] [--baseline NAME] [--threshold PERCENT]"
+    );
+    exit(64);
+}
+
+fn options() -> Options {
+    let mut options = Options {
+        filters: Vec::new(),
+        runs: 1,
+        save_baseline: None,
+        baseline: None,
+        threshold: 10.0,
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            // passed along by `cargo bench`
+            "--bench" => {}
+            "--runs" => options.runs = args.next().and_then(|n| n.parse().ok()).unwrap_or_else(|| usage()),
+            "--save-baseline" => options.save_baseline = Some(args.next().unwrap_or_else(|| usage())),
+            "--baseline" => options.baseline = Some(args.next().unwrap_or_else(|| usage())),
+            "--threshold" => {
+                options.threshold = args.next().and_then(|n| n.parse().ok()).unwrap_or_else(|| usage())
+            }
+            flag if flag.starts_with("--") => usage(),
+            filter => options.filters.push(filter.to_string()),
+        }
+    }
+
+    if options.runs == 0 {
+        usage();
+    }
+
+    options
+}
+
+fn baseline_path(name: &str) -> PathBuf {
+    Path::new(BASELINES).join(format!("{}.baseline", name))
+}
+
+fn load_baseline(name: &str) -> HashMap<String, Measurement> {
+    let contents = fs::read_to_string(baseline_path(name)).unwrap_or_else(|err| {
+        eprintln!("Could not read baseline '{}': {}", name, err);
+        exit(1);
+    });
+
+    contents
+        .lines()
+        .filter_map(Measurement::parse)
+        .map(|measurement| (measurement.name.clone(), measurement))
+        .collect()
+}
+
+fn save_baseline(name: &str, measurements: &[Measurement]) {
+    let contents: String = measurements
+        .iter()
+        .map(|measurement| measurement.serialize() + "\n")
+        .collect();
+
+    fs::create_dir_all(BASELINES)
+        .and_then(|_| fs::write(baseline_path(name), contents))
+        .unwrap_or_else(|err| {
+            eprintln!("Could not save baseline '{}': {}", name, err);
+            exit(1);
+        });
+}
+
+// the fastest of `runs` executions, together with its allocations
+fn measure(name: &str, path: &Path, runs: usize) -> Result<Measurement, String> {
+    let mut best: Option<Measurement> = None;
+
+    for _ in 0..runs {
+        let mut lox = Lox::new();
+        lox.set_output(Output::Discard);
+
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+        let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+        let start = Instant::now();
+
+        match lox.run_file(path).map_err(|err| err.to_string())? {
+            Status::Success => {}
+            status => return Err(format!("{:?}", status)),
+        }
+
+        let measurement = Measurement {
+            name: name.to_string(),
+            time: start.elapsed(),
+            allocations: ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+            bytes: ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes,
+        };
+
+        if best.as_ref().is_none_or(|best| measurement.time < best.time) {
+            best = Some(measurement);
+        }
+    }
+
+    Ok(best.unwrap())
+}
+
+fn change(now: f64, before: f64) -> f64 {
+    if before == 0.0 {
+        0.0
+    } else {
+        (now - before) / before * 100.0
+    }
+}
+
+fn main() {
+    let options = options();
+    let baseline = options.baseline.as_deref().map(load_baseline);
+
+    let mut scripts: Vec<PathBuf> = fs::read_dir(BENCHMARKS)
+        .expect("benchmark directory")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "lox"))
+        .collect();
+    scripts.sort();
+
+    let mut measurements = Vec::new();
+    let mut regressions = Vec::new();
+    let mut failures = Vec::new();
+    let mut skipped = Vec::new();
+
+    println!("{:<20} {:>12} {:>14} {:>14}", "benchmark", "time", "allocations", "bytes");
+
+    for path in scripts.iter() {
+        let name = path.file_stem().unwrap().to_string_lossy().to_string();
+        if !options.filters.is_empty() && !options.filters.iter().any(|filter| name.contains(filter)) {
+            continue;
+        }
+        if let Some((_, reason)) = UNSUPPORTED.iter().find(|(unsupported, _)| *unsupported == name) {
+            println!("{:<20} skipped: uses {}", name, reason);
+            skipped.push(name);
+            continue;
+        }
+
+        let measurement = match measure(&name, path, options.runs) {
+            Ok(measurement) => measurement,
+            Err(err) => {
+                println!("{:<20} failed: {}", name, err);
+                failures.push(name);
+                continue;
+            }
+        };
+
+        print!(
+            "{:<20} {:>10.3}ms {:>14} {:>14}",
+            name,
+            measurement.time.as_secs_f64() * 1000.0,
+            measurement.allocations,
+            measurement.bytes
+        );
+
+        if let Some(before) = baseline.as_ref().and_then(|baseline| baseline.get(&name)) {
+            let time = change(measurement.time.as_secs_f64(), before.time.as_secs_f64());
+            let allocations = change(measurement.allocations as f64, before.allocations as f64);
+            print!("   time {:+.1}%, allocations {:+.1}%", time, allocations);
+
+            if time > options.threshold {
+                print!("   REGRESSED");
+                regressions.push(name.clone());
+            }
+        }
+        println!();
+
+        measurements.push(measurement);
+    }
+
+    if let Some(name) = options.save_baseline.as_deref() {
+        save_baseline(name, &measurements);
+        println!("saved baseline '{}'", name);
+    }
+
+    if !skipped.is_empty() {
+        println!("{} benchmark(s) skipped as unsupported: {}", skipped.len(), skipped.join(", "));
+    }
+    if !failures.is_empty() {
+        println!("{} benchmark(s) failed: {}", failures.len(), failures.join(", "));
+    }
+    if !regressions.is_empty() {
+        println!(
+            "{} benchmark(s) more than {}% slower than the baseline: {}",
+            regressions.len(),
+            options.threshold,
+            regressions.join(", ")
+        );
+    }
+
+    if !failures.is_empty() || !regressions.is_empty() {
+        exit(1);
+    }
+}
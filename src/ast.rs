@@ -1,5 +1,6 @@
-use crate::errors::Result;
-use crate::tokens::{Literal, Token};
+use crate::errors::{Error, Result};
+use crate::statement::{Pattern, Stmt, Visitor as StmtVisitor};
+use crate::tokens::{Literal, Token, TokenType::*};
 
 #[derive(Clone, Debug)]
 pub enum Expr {
@@ -34,7 +35,9 @@ pub enum Expr {
     Call {
         callee: Box<Expr>,
         paren: Token,
-        arguments: Vec<Expr>
+        arguments: Vec<Expr>,
+        // `b: 3`, they come after the positional arguments
+        named: Vec<(Token, Expr)>
     },
     Get {
         object: Box<Expr>,
@@ -44,6 +47,47 @@ pub enum Expr {
         object: Box<Expr>,
         name: Token,
         value: Box<Expr>
+    },
+    This {
+        keyword: Token
+    },
+    Interpolation {
+        parts: Vec<Expr>
+    },
+    Conditional {
+        condition: Box<Expr>,
+        then_branch: Box<Expr>,
+        else_branch: Box<Expr>
+    },
+    // `a += 1`, `++a` and `a++` on a variable or a field, the target is evaluated
+    // once. `operator` is the `+=` or `++` token, `value` is 1 for increments.
+    Update {
+        target: Box<Expr>,
+        operator: Token,
+        value: Box<Expr>,
+        postfix: bool
+    },
+    // `[1, 2]`, the bracket is the opening one
+    List {
+        bracket: Token,
+        elements: Vec<Expr>
+    },
+    // `{"a": 1}`, keys can be any value
+    Map {
+        brace: Token,
+        entries: Vec<(Expr, Expr)>
+    },
+    // `a[i]`, the bracket is the opening one
+    Index {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>
+    },
+    SetIndex {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+        value: Box<Expr>
     }
 }
 
@@ -58,6 +102,14 @@ pub trait Visitor<T> {
     fn visit_call_expr(&mut self, expr: &Expr) -> Result<T>;
     fn visit_get_expr(&mut self, expr: &Expr) -> Result<T>;
     fn visit_set_expr(&mut self, expr: &Expr) -> Result<T>;
+    fn visit_this_expr(&mut self, keyword: &Token) -> Result<T>;
+    fn visit_interpolation_expr(&mut self, parts: &[Expr]) -> Result<T>;
+    fn visit_conditional_expr(&mut self, expr: &Expr) -> Result<T>;
+    fn visit_update_expr(&mut self, expr: &Expr) -> Result<T>;
+    fn visit_list_expr(&mut self, expr: &Expr) -> Result<T>;
+    fn visit_map_expr(&mut self, expr: &Expr) -> Result<T>;
+    fn visit_index_expr(&mut self, expr: &Expr) -> Result<T>;
+    fn visit_set_index_expr(&mut self, expr: &Expr) -> Result<T>;
 
 }
 
@@ -72,63 +124,961 @@ impl Expr {
             Expr::Unary { operator, right } => visitor.visit_unary_expr(operator, right),
             Expr::Grouping { expression } => visitor.visit_grouping_expr(expression),
             Expr::Literal { value } => visitor.visit_literal_expr(value),
-            Expr::Variable { name } => visitor.visit_variable_expr(&name),
-            Expr::Assign { .. } => visitor.visit_variable_assignment_expr(&self),
+            Expr::Variable { name } => visitor.visit_variable_expr(name),
+            Expr::Assign { .. } => visitor.visit_variable_assignment_expr(self),
             Expr::Logical{..} => visitor.visit_logical_expr(self),
             Expr::Call { .. } => visitor.visit_call_expr(self),
-            Expr::Get { object, name } => visitor.visit_get_expr(self),
-            Expr::Set { object, name, value } => visitor.visit_set_expr(self)
-        }
-    }
-}
-
-//pub struct AstPrinter {}
-//
-//impl AstPrinter {
-//    pub fn print(&self, expr: &Expr) -> Result<String> {
-//        expr.accept(self)
-//    }
-//}
-//
-//fn parenthesize(
-//    visitor: &dyn Visitor<String>,
-//    name: &str,
-//    expressions: &Vec<&Expr>,
-//) -> Result<String> {
-//    let mut value = String::new();
-//    value.push('(');
-//    value.push_str(name);
-//    value = expressions.iter().fold(value, |mut accu, expr| {
-//        accu.push(' ');
-//        if let Ok(res) = &expr.accept(visitor) {
-//            accu.push_str(res);
-//        }
-//        accu
-//    });
-//    value.push(')');
-//    Ok(value)
-//}
-
-//impl Visitor<String> for AstPrinter {
-    //fn visit_binary_expr(&self, left: &Expr, operator: &Token, right: &Expr) -> Result<String> {
-        //parenthesize(self, &operator.lexeme, &vec![left, right])
-    //}
-    //fn visit_unary_expr(&self, operator: &Token, right: &Expr) -> Result<String> {
-        //parenthesize(self, &operator.lexeme, &vec![right])
-    //}
-    //fn visit_grouping_expr(&self, expr: &Expr) -> Result<String> {
-        //parenthesize(self, "group", &vec![expr])
-    //}
-
-    //fn visit_literal_expr(&self, value: &Literal) -> Result<String> {
-        //Ok(value.to_string())
-    //}
-
-    //fn visit_variable_expr(&self, name: &Token) -> Result<String> {
-        //Ok(name.lexeme.clone())
-    //}
-
-    //fn visit_variable_assignment_expr(&self, expr: &Expr) -> Result<String> {
-        //parenthesize(self, "+", &vec![expr])
-    //}
-//}
+            Expr::Get { .. } => visitor.visit_get_expr(self),
+            Expr::Set { .. } => visitor.visit_set_expr(self),
+            Expr::This { keyword } => visitor.visit_this_expr(keyword),
+            Expr::Interpolation { parts } => visitor.visit_interpolation_expr(parts),
+            Expr::Conditional { .. } => visitor.visit_conditional_expr(self),
+            Expr::Update { .. } => visitor.visit_update_expr(self),
+            Expr::List { .. } => visitor.visit_list_expr(self),
+            Expr::Map { .. } => visitor.visit_map_expr(self),
+            Expr::Index { .. } => visitor.visit_index_expr(self),
+            Expr::SetIndex { .. } => visitor.visit_set_index_expr(self),
+        }
+    }
+}
+
+// Prints the tree as S-expressions, `(* (- 123) (group 45.67))`, one statement per
+// line. Useful for checking what the parser made of something, like a `for` loop.
+pub struct AstPrinter;
+
+impl AstPrinter {
+    pub fn print(&mut self, expr: &Expr) -> Result<String> {
+        expr.accept(self)
+    }
+
+    pub fn print_statements(&mut self, statements: &[Stmt]) -> Result<String> {
+        let lines = statements
+            .iter()
+            .map(|statement| statement.accept(self))
+            .collect::<Result<Vec<String>>>()?;
+        Ok(lines.join("\n"))
+    }
+
+    fn parenthesize(&mut self, name: &str, expressions: &[&Expr]) -> Result<String> {
+        let mut value = format!("({}", name);
+        for expr in expressions {
+            value.push(' ');
+            value.push_str(&expr.accept(self)?);
+        }
+        value.push(')');
+        Ok(value)
+    }
+
+    fn statements(&mut self, statements: &[Stmt]) -> Result<String> {
+        let printed = statements
+            .iter()
+            .map(|statement| statement.accept(self))
+            .collect::<Result<Vec<String>>>()?;
+        Ok(printed.join(" "))
+    }
+}
+
+// `a`, or `(: a Number)` when it has a type annotation
+fn typed(name: &Token, annotation: Option<&Token>) -> String {
+    match annotation {
+        Some(annotation) => format!("(: {} {})", name.lexeme, annotation.lexeme),
+        None => name.lexeme.clone(),
+    }
+}
+
+fn print_literal(value: &Literal) -> String {
+    match value {
+        Literal::String(value) => format!("{:?}", value),
+        Literal::None => "nil".to_string(),
+        value => value.to_string(),
+    }
+}
+
+// `(Point x [first ...rest])`, alternatives as `(| 1 2)`
+fn print_pattern(pattern: &Pattern) -> String {
+    let list = |patterns: &[Pattern]| patterns.iter().map(print_pattern).collect::<Vec<String>>();
+    match pattern {
+        Pattern::Literal { value, .. } => print_literal(value),
+        Pattern::Wildcard => "_".to_string(),
+        Pattern::Binding { name } => name.lexeme.clone(),
+        Pattern::Or { alternatives } => format!("(| {})", list(alternatives).join(" ")),
+        Pattern::Class { name, fields } => {
+            let mut parts = vec![name.lexeme.clone()];
+            parts.extend(list(fields));
+            format!("({})", parts.join(" "))
+        }
+        Pattern::List { elements, rest } => {
+            let mut parts = list(elements);
+            parts.extend(rest.iter().map(|rest| format!("...{}", rest.lexeme)));
+            format!("[{}]", parts.join(" "))
+        }
+    }
+}
+
+impl Visitor<String> for AstPrinter {
+    fn visit_binary_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> Result<String> {
+        self.parenthesize(&operator.lexeme, &[left, right])
+    }
+
+    fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) -> Result<String> {
+        self.parenthesize(&operator.lexeme, &[right])
+    }
+
+    fn visit_grouping_expr(&mut self, expr: &Expr) -> Result<String> {
+        self.parenthesize("group", &[expr])
+    }
+
+    fn visit_literal_expr(&mut self, value: &Literal) -> Result<String> {
+        Ok(print_literal(value))
+    }
+
+    fn visit_variable_expr(&mut self, name: &Token) -> Result<String> {
+        Ok(name.lexeme.clone())
+    }
+
+    fn visit_variable_assignment_expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::Assign { name, value } => self.parenthesize(&format!("= {}", name.lexeme), &[value]),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_logical_expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::Logical { left, operator, right } => self.parenthesize(&operator.lexeme, &[left, right]),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_call_expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::Call { callee, arguments, named, .. } => {
+                let mut expressions = vec![callee.as_ref()];
+                expressions.extend(arguments.iter());
+                let mut value = self.parenthesize("call", &expressions)?;
+                value.pop();
+                for (name, argument) in named {
+                    value.push_str(&format!(" (: {} {})", name.lexeme, argument.accept(self)?));
+                }
+                value.push(')');
+                Ok(value)
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_get_expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::Get { object, name } => self.parenthesize(&format!(". {}", name.lexeme), &[object]),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_set_expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::Set { object, name, value } => {
+                self.parenthesize(&format!(".= {}", name.lexeme), &[object, value])
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_this_expr(&mut self, _keyword: &Token) -> Result<String> {
+        Ok("this".to_string())
+    }
+
+    fn visit_interpolation_expr(&mut self, parts: &[Expr]) -> Result<String> {
+        let parts: Vec<&Expr> = parts.iter().collect();
+        self.parenthesize("interpolate", &parts)
+    }
+
+    fn visit_conditional_expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::Conditional { condition, then_branch, else_branch } => {
+                self.parenthesize("?:", &[condition, then_branch, else_branch])
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_update_expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::Update { target, operator, postfix: true, .. } => {
+                self.parenthesize(&format!("post{}", operator.lexeme), &[target])
+            }
+            Expr::Update { target, operator, .. } if matches!(operator.token_type, PLUS_PLUS | MINUS_MINUS) => {
+                self.parenthesize(&operator.lexeme, &[target])
+            }
+            Expr::Update { target, operator, value, .. } => self.parenthesize(&operator.lexeme, &[target, value]),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_list_expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::List { elements, .. } => {
+                let elements: Vec<&Expr> = elements.iter().collect();
+                self.parenthesize("list", &elements)
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_map_expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::Map { entries, .. } => {
+                let mut value = "(map".to_string();
+                for (key, entry) in entries {
+                    value.push_str(&format!(" ({} {})", key.accept(self)?, entry.accept(self)?));
+                }
+                value.push(')');
+                Ok(value)
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_index_expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::Index { object, index, .. } => self.parenthesize("index", &[object, index]),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_set_index_expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::SetIndex { object, index, value, .. } => self.parenthesize("set-index", &[object, index, value]),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+}
+
+impl StmtVisitor<String> for AstPrinter {
+    fn visit_print_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::Print(expr) => self.parenthesize("print", &[expr]),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_expression_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::Expression(expr) => self.parenthesize(";", &[expr]),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_variable_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::Variable { name, annotation, initializer: Some(initializer) } => {
+                self.parenthesize(&format!("var {}", typed(name, annotation.as_ref())), &[initializer])
+            }
+            Stmt::Variable { name, annotation, initializer: None } => {
+                Ok(format!("(var {})", typed(name, annotation.as_ref())))
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_block_statement(&mut self, statements: &[Stmt]) -> Result<String> {
+        if statements.is_empty() {
+            return Ok("(block)".to_string());
+        }
+        Ok(format!("(block {})", self.statements(statements)?))
+    }
+
+    fn visit_if_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::If { condition, then_branch, else_branch } => {
+                let mut value = format!("(if {} {}", condition.accept(self)?, then_branch.accept(self)?);
+                if let Some(else_branch) = else_branch {
+                    value.push(' ');
+                    value.push_str(&else_branch.accept(self)?);
+                }
+                value.push(')');
+                Ok(value)
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_while_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::While { condition, body } => {
+                Ok(format!("(while {} {})", condition.accept(self)?, body.accept(self)?))
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_for_in_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::ForIn { name, iterable, body } => Ok(format!(
+                "(for-in {} {} {})",
+                name.lexeme,
+                iterable.accept(self)?,
+                body.accept(self)?
+            )),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_function_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::Function { name, params, param_types, defaults, rest, return_type, body, generator } => {
+                let mut printed = Vec::new();
+                for ((param, annotation), default) in params.iter().zip(param_types).zip(defaults) {
+                    let param = typed(param, annotation.as_ref());
+                    printed.push(match default {
+                        Some(default) => format!("(= {} {})", param, default.accept(self)?),
+                        None => param,
+                    });
+                }
+                printed.extend(rest.iter().map(|rest| format!("...{}", rest.lexeme)));
+                let params = printed;
+                let mut value = format!(
+                    "({} {} ({})",
+                    if *generator { "fun*" } else { "fun" },
+                    typed(name, return_type.as_ref()),
+                    params.join(" ")
+                );
+                if !body.is_empty() {
+                    value.push(' ');
+                    value.push_str(&self.statements(body)?);
+                }
+                value.push(')');
+                Ok(value)
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_return_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::Return { value: Some(value), .. } => self.parenthesize("return", &[value]),
+            Stmt::Return { value: None, .. } => Ok("(return)".to_string()),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_break_statement(&mut self, _keyword: &Token) -> Result<String> {
+        Ok("(break)".to_string())
+    }
+
+    fn visit_yield_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::Yield { value, .. } => self.parenthesize("yield", &[value]),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_throw_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::Throw { value, .. } => self.parenthesize("throw", &[value]),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_match_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::Match { value, arms, .. } => {
+                let mut printed = format!("(match {}", value.accept(self)?);
+                for arm in arms {
+                    printed.push_str(&format!(" (arm {}", print_pattern(&arm.pattern)));
+                    if let Some(guard) = &arm.guard {
+                        printed.push_str(&format!(" (if {})", guard.accept(self)?));
+                    }
+                    printed.push_str(&format!(" {})", arm.body.accept(self)?));
+                }
+                printed.push(')');
+                Ok(printed)
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_try_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::Try { body, catch_clause, finally_body } => {
+                let mut value = format!("(try {}", self.visit_block_statement(body)?);
+                if let Some((name, catch_body)) = catch_clause {
+                    value.push_str(&format!(" (catch {} {})", name.lexeme, self.visit_block_statement(catch_body)?));
+                }
+                if let Some(finally_body) = finally_body {
+                    value.push_str(&format!(" (finally {})", self.visit_block_statement(finally_body)?));
+                }
+                value.push(')');
+                Ok(value)
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_class_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::Class { name, fields, methods } => {
+                let mut value = format!("(class {}", name.lexeme);
+                for (field, annotation) in fields {
+                    value.push(' ');
+                    value.push_str(&typed(field, Some(annotation)));
+                }
+                for method in methods {
+                    value.push(' ');
+                    value.push_str(&method.accept(self)?);
+                }
+                value.push(')');
+                Ok(value)
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_import_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::Import { keyword, path, alias, names } => {
+                let mut value = format!("({} {}", keyword.lexeme, path.lexeme);
+                for name in alias.iter().chain(names) {
+                    value.push(' ');
+                    value.push_str(&name.lexeme);
+                }
+                value.push(')');
+                Ok(value)
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_export_statement(&mut self, declaration: &Stmt) -> Result<String> {
+        Ok(format!("(export {})", declaration.accept(self)?))
+    }
+}
+
+// Prints the tree as JSON, every node is an object with its "kind", tokens carry
+// their type, lexeme and line.
+pub struct JsonPrinter;
+
+fn json_string(value: &str) -> String {
+    let mut json = String::from("\"");
+    for ch in value.chars() {
+        match ch {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => json.push(ch),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn json_token(token: &Token) -> String {
+    format!(
+        "{{\"type\":{},\"lexeme\":{},\"line\":{}}}",
+        json_string(&token.token_type.to_string()),
+        json_string(&token.lexeme),
+        token.line
+    )
+}
+
+fn json_pattern(pattern: &Pattern) -> String {
+    let list = |patterns: &[Pattern]| {
+        let patterns: Vec<String> = patterns.iter().map(json_pattern).collect();
+        format!("[{}]", patterns.join(","))
+    };
+    match pattern {
+        Pattern::Literal { token, value } => {
+            json_node("LiteralPattern", &[("token", json_token(token)), ("value", json_literal(value))])
+        }
+        Pattern::Wildcard => json_node("WildcardPattern", &[]),
+        Pattern::Binding { name } => json_node("BindingPattern", &[("name", json_token(name))]),
+        Pattern::Or { alternatives } => json_node("OrPattern", &[("alternatives", list(alternatives))]),
+        Pattern::Class { name, fields } => {
+            json_node("ClassPattern", &[("name", json_token(name)), ("fields", list(fields))])
+        }
+        Pattern::List { elements, rest } => json_node(
+            "ListPattern",
+            &[("elements", list(elements)), ("rest", json_optional_token(rest))],
+        ),
+    }
+}
+
+fn json_optional_token(token: &Option<Token>) -> String {
+    token.as_ref().map_or("null".to_string(), json_token)
+}
+
+fn json_literal(value: &Literal) -> String {
+    match value {
+        Literal::String(value) => json_string(value),
+        Literal::Number(value) if value.is_finite() => value.to_string(),
+        Literal::Number(value) => json_string(&value.to_string()),
+        Literal::Boolean(value) => value.to_string(),
+        Literal::None => "null".to_string(),
+    }
+}
+
+// {"kind":"Binary","operator":...,...} from already printed fields
+fn json_node(kind: &str, fields: &[(&str, String)]) -> String {
+    let mut json = format!("{{\"kind\":{}", json_string(kind));
+    for (name, value) in fields {
+        json.push_str(&format!(",{}:{}", json_string(name), value));
+    }
+    json.push('}');
+    json
+}
+
+impl JsonPrinter {
+    pub fn print(&mut self, expr: &Expr) -> Result<String> {
+        expr.accept(self)
+    }
+
+    pub fn print_statements(&mut self, statements: &[Stmt]) -> Result<String> {
+        self.statements(statements)
+    }
+
+    fn statements(&mut self, statements: &[Stmt]) -> Result<String> {
+        let printed = statements
+            .iter()
+            .map(|statement| statement.accept(self))
+            .collect::<Result<Vec<String>>>()?;
+        Ok(format!("[{}]", printed.join(",")))
+    }
+
+    fn optional(&mut self, expr: &Option<Expr>) -> Result<String> {
+        match expr {
+            Some(expr) => expr.accept(self),
+            None => Ok("null".to_string()),
+        }
+    }
+}
+
+impl Visitor<String> for JsonPrinter {
+    fn visit_binary_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> Result<String> {
+        Ok(json_node(
+            "Binary",
+            &[("left", left.accept(self)?), ("operator", json_token(operator)), ("right", right.accept(self)?)],
+        ))
+    }
+
+    fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) -> Result<String> {
+        Ok(json_node("Unary", &[("operator", json_token(operator)), ("right", right.accept(self)?)]))
+    }
+
+    fn visit_grouping_expr(&mut self, expr: &Expr) -> Result<String> {
+        Ok(json_node("Grouping", &[("expression", expr.accept(self)?)]))
+    }
+
+    fn visit_literal_expr(&mut self, value: &Literal) -> Result<String> {
+        Ok(json_node("Literal", &[("value", json_literal(value))]))
+    }
+
+    fn visit_variable_expr(&mut self, name: &Token) -> Result<String> {
+        Ok(json_node("Variable", &[("name", json_token(name))]))
+    }
+
+    fn visit_variable_assignment_expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::Assign { name, value } => {
+                Ok(json_node("Assign", &[("name", json_token(name)), ("value", value.accept(self)?)]))
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_logical_expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::Logical { left, operator, right } => Ok(json_node(
+                "Logical",
+                &[("left", left.accept(self)?), ("operator", json_token(operator)), ("right", right.accept(self)?)],
+            )),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_call_expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::Call { callee, paren, arguments, named } => {
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| argument.accept(self))
+                    .collect::<Result<Vec<String>>>()?;
+                let named = named
+                    .iter()
+                    .map(|(name, argument)| {
+                        Ok(json_node("Named", &[("name", json_token(name)), ("value", argument.accept(self)?)]))
+                    })
+                    .collect::<Result<Vec<String>>>()?;
+                Ok(json_node(
+                    "Call",
+                    &[
+                        ("callee", callee.accept(self)?),
+                        ("paren", json_token(paren)),
+                        ("arguments", format!("[{}]", arguments.join(","))),
+                        ("named", format!("[{}]", named.join(","))),
+                    ],
+                ))
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_get_expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::Get { object, name } => {
+                Ok(json_node("Get", &[("object", object.accept(self)?), ("name", json_token(name))]))
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_set_expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::Set { object, name, value } => Ok(json_node(
+                "Set",
+                &[("object", object.accept(self)?), ("name", json_token(name)), ("value", value.accept(self)?)],
+            )),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_this_expr(&mut self, keyword: &Token) -> Result<String> {
+        Ok(json_node("This", &[("keyword", json_token(keyword))]))
+    }
+
+    fn visit_interpolation_expr(&mut self, parts: &[Expr]) -> Result<String> {
+        let parts = parts
+            .iter()
+            .map(|part| part.accept(self))
+            .collect::<Result<Vec<String>>>()?;
+        Ok(json_node("Interpolation", &[("parts", format!("[{}]", parts.join(",")))]))
+    }
+
+    fn visit_conditional_expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::Conditional { condition, then_branch, else_branch } => Ok(json_node(
+                "Conditional",
+                &[
+                    ("condition", condition.accept(self)?),
+                    ("then", then_branch.accept(self)?),
+                    ("else", else_branch.accept(self)?),
+                ],
+            )),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_update_expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::Update { target, operator, value, postfix } => Ok(json_node(
+                "Update",
+                &[
+                    ("target", target.accept(self)?),
+                    ("operator", json_token(operator)),
+                    ("value", value.accept(self)?),
+                    ("postfix", postfix.to_string()),
+                ],
+            )),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_list_expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::List { bracket, elements } => {
+                let elements = elements
+                    .iter()
+                    .map(|element| element.accept(self))
+                    .collect::<Result<Vec<String>>>()?;
+                Ok(json_node(
+                    "List",
+                    &[("bracket", json_token(bracket)), ("elements", format!("[{}]", elements.join(",")))],
+                ))
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_map_expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::Map { brace, entries } => {
+                let entries = entries
+                    .iter()
+                    .map(|(key, value)| {
+                        Ok(json_node("Entry", &[("key", key.accept(self)?), ("value", value.accept(self)?)]))
+                    })
+                    .collect::<Result<Vec<String>>>()?;
+                Ok(json_node(
+                    "Map",
+                    &[("brace", json_token(brace)), ("entries", format!("[{}]", entries.join(",")))],
+                ))
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_index_expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::Index { object, bracket, index } => Ok(json_node(
+                "Index",
+                &[("object", object.accept(self)?), ("bracket", json_token(bracket)), ("index", index.accept(self)?)],
+            )),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_set_index_expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::SetIndex { object, bracket, index, value } => Ok(json_node(
+                "SetIndex",
+                &[
+                    ("object", object.accept(self)?),
+                    ("bracket", json_token(bracket)),
+                    ("index", index.accept(self)?),
+                    ("value", value.accept(self)?),
+                ],
+            )),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+}
+
+impl StmtVisitor<String> for JsonPrinter {
+    fn visit_print_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::Print(expr) => Ok(json_node("Print", &[("expression", expr.accept(self)?)])),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_expression_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::Expression(expr) => Ok(json_node("Expression", &[("expression", expr.accept(self)?)])),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_variable_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::Variable { name, annotation, initializer } => Ok(json_node(
+                "Var",
+                &[
+                    ("name", json_token(name)),
+                    ("annotation", json_optional_token(annotation)),
+                    ("initializer", self.optional(initializer)?),
+                ],
+            )),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_block_statement(&mut self, statements: &[Stmt]) -> Result<String> {
+        Ok(json_node("Block", &[("statements", self.statements(statements)?)]))
+    }
+
+    fn visit_if_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::If { condition, then_branch, else_branch } => {
+                let else_branch = match else_branch {
+                    Some(else_branch) => else_branch.accept(self)?,
+                    None => "null".to_string(),
+                };
+                Ok(json_node(
+                    "If",
+                    &[
+                        ("condition", condition.accept(self)?),
+                        ("then", then_branch.accept(self)?),
+                        ("else", else_branch),
+                    ],
+                ))
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_while_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::While { condition, body } => Ok(json_node(
+                "While",
+                &[("condition", condition.accept(self)?), ("body", body.accept(self)?)],
+            )),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_for_in_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::ForIn { name, iterable, body } => Ok(json_node(
+                "ForIn",
+                &[("name", json_token(name)), ("iterable", iterable.accept(self)?), ("body", body.accept(self)?)],
+            )),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_function_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::Function { name, params, param_types, defaults, rest, return_type, body, generator } => {
+                let params: Vec<String> = params.iter().map(json_token).collect();
+                let param_types: Vec<String> = param_types.iter().map(json_optional_token).collect();
+                let defaults = defaults
+                    .iter()
+                    .map(|default| self.optional(default))
+                    .collect::<Result<Vec<String>>>()?;
+                Ok(json_node(
+                    "Function",
+                    &[
+                        ("name", json_token(name)),
+                        ("params", format!("[{}]", params.join(","))),
+                        ("param_types", format!("[{}]", param_types.join(","))),
+                        ("defaults", format!("[{}]", defaults.join(","))),
+                        ("rest", json_optional_token(rest)),
+                        ("return_type", json_optional_token(return_type)),
+                        ("body", self.statements(body)?),
+                        ("generator", generator.to_string()),
+                    ],
+                ))
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_return_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::Return { keyword, value } => Ok(json_node(
+                "Return",
+                &[("keyword", json_token(keyword)), ("value", self.optional(value)?)],
+            )),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_break_statement(&mut self, keyword: &Token) -> Result<String> {
+        Ok(json_node("Break", &[("keyword", json_token(keyword))]))
+    }
+
+    fn visit_yield_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::Yield { keyword, value } => Ok(json_node(
+                "Yield",
+                &[("keyword", json_token(keyword)), ("value", value.accept(self)?)],
+            )),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_throw_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::Throw { keyword, value } => Ok(json_node(
+                "Throw",
+                &[("keyword", json_token(keyword)), ("value", value.accept(self)?)],
+            )),
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_match_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::Match { keyword, value, arms } => {
+                let arms = arms
+                    .iter()
+                    .map(|arm| {
+                        Ok(json_node(
+                            "Arm",
+                            &[
+                                ("pattern", json_pattern(&arm.pattern)),
+                                ("guard", self.optional(&arm.guard)?),
+                                ("body", arm.body.accept(self)?),
+                            ],
+                        ))
+                    })
+                    .collect::<Result<Vec<String>>>()?;
+                Ok(json_node(
+                    "Match",
+                    &[
+                        ("keyword", json_token(keyword)),
+                        ("value", value.accept(self)?),
+                        ("arms", format!("[{}]", arms.join(","))),
+                    ],
+                ))
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_try_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::Try { body, catch_clause, finally_body } => {
+                let catch_clause = match catch_clause {
+                    Some((name, catch_body)) => json_node(
+                        "Catch",
+                        &[("name", json_token(name)), ("body", self.statements(catch_body)?)],
+                    ),
+                    None => "null".to_string(),
+                };
+                let finally_body = match finally_body {
+                    Some(finally_body) => self.statements(finally_body)?,
+                    None => "null".to_string(),
+                };
+                Ok(json_node(
+                    "Try",
+                    &[
+                        ("body", self.statements(body)?),
+                        ("catch", catch_clause),
+                        ("finally", finally_body),
+                    ],
+                ))
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_class_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::Class { name, fields, methods } => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(field, annotation)| {
+                        json_node("Field", &[("name", json_token(field)), ("type", json_token(annotation))])
+                    })
+                    .collect();
+                let methods = methods
+                    .iter()
+                    .map(|method| method.accept(self))
+                    .collect::<Result<Vec<String>>>()?;
+                Ok(json_node(
+                    "Class",
+                    &[
+                        ("name", json_token(name)),
+                        ("fields", format!("[{}]", fields.join(","))),
+                        ("methods", format!("[{}]", methods.join(","))),
+                    ],
+                ))
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_import_statement(&mut self, statement: &Stmt) -> Result<String> {
+        match statement {
+            Stmt::Import { keyword, path, alias, names } => {
+                let names: Vec<String> = names.iter().map(json_token).collect();
+                Ok(json_node(
+                    "Import",
+                    &[
+                        ("keyword", json_token(keyword)),
+                        ("path", json_token(path)),
+                        ("alias", json_optional_token(alias)),
+                        ("names", format!("[{}]", names.join(","))),
+                    ],
+                ))
+            }
+            _ => Err(Error::Runtime("should never happen".to_string())),
+        }
+    }
+
+    fn visit_export_statement(&mut self, declaration: &Stmt) -> Result<String> {
+        Ok(json_node("Export", &[("declaration", declaration.accept(self)?)]))
+    }
+}
@@ -1,39 +1,47 @@
-use rlox::ast::{Expr};
-use rlox::tokens::{Literal, Token, TokenType};
+use std::env;
+use std::fs;
+use std::process::exit;
 
-fn test_ast() {
-    let expr = Expr::Binary {
-        left: Box::new(Expr::Unary {
-            operator: Token {
-                token_type: TokenType::MINUS,
-                lexeme: "-".to_string(),
-                literal: Literal::None,
-                line: 1,
-                unique_count: 0
-            },
-            right: Box::new(Expr::Literal {
-                value: Literal::Number(123 as f64),
-            }),
-        }),
-        operator: Token {
-            token_type: TokenType::STAR,
-            lexeme: "*".to_string(),
-            literal: Literal::None,
-            line: 1,
-            unique_count: 0
-        },
-        right: Box::new(Expr::Grouping {
-            expression: Box::new(Expr::Literal {
-                value: Literal::Number(45.67),
-            }),
-        }),
+use rlox::ast::{AstPrinter, JsonPrinter};
+use rlox::parser::Parser;
+use rlox::scanner::Scanner;
+
+// Prints the syntax tree of a file, as S-expressions or with --json as JSON.
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let json = args.iter().any(|arg| arg == "--json");
+    let files: Vec<&String> = args.iter().filter(|arg| !arg.starts_with("--")).collect();
+
+    let [file] = files.as_slice() else {
+        eprintln!("Usage: ast [--json] <file>");
+        exit(64);
     };
 
-    // let a = AstPrinter {};
+    let source = fs::read_to_string(file).unwrap_or_else(|err| {
+        eprintln!("Could not read {}: {}", file, err);
+        exit(66);
+    });
 
-    // println!("{:?}", a.print(&expr));
-}
+    let (tokens, errors) = Scanner::new(source).scan_tokens();
+    for error in errors.iter() {
+        eprintln!("{}", error);
+    }
+    let statements = match Parser::new(tokens).parse() {
+        Ok(statements) if errors.is_empty() => statements,
+        _ => exit(65),
+    };
 
-fn main() {
-    test_ast();
+    let printed = if json {
+        JsonPrinter.print_statements(&statements)
+    } else {
+        AstPrinter.print_statements(&statements)
+    };
+
+    match printed {
+        Ok(printed) => println!("{}", printed),
+        Err(err) => {
+            eprintln!("{}", err);
+            exit(70);
+        }
+    }
 }
@@ -0,0 +1,936 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::rc::Rc;
+
+use crate::ast::{Expr, Visitor as ExprVisitor};
+use crate::errors::{Error, ResolveError, Result};
+use crate::function::Arity;
+use crate::statement::{Stmt, Visitor as StmtVisitor};
+use crate::tokens::{Literal, Token, TokenType::*};
+
+// A static type checker for the optional annotations, `var x: Number`,
+// `fun f(a: String): Bool` and `x: Number;` fields in a class body.
+//
+// The checker is gradual: anything it can not be sure about has the type Any,
+// which fits everywhere. Unannotated variables take the type of their initializer
+// as long as they are never assigned to again, unannotated functions return what
+// all their return statements agree on. So untyped code only gets errors for
+// things that would fail at runtime anyway, like `"a" - 1`.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Any,
+    Nil,
+    Bool,
+    Number,
+    String,
+    // a function with a known signature
+    Function(Rc<Signature>),
+    // any function or class, from a `Function` annotation
+    Callable,
+    Class(String),
+    Instance(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub params: Vec<Type>,
+    // for named arguments
+    pub names: Vec<String>,
+    pub arity: Arity,
+    pub result: Type,
+}
+
+impl Signature {
+    // what a class without `init` is called with
+    fn empty() -> Self {
+        Signature {
+            params: Vec::new(),
+            names: Vec::new(),
+            arity: Arity::exactly(0),
+            result: Type::Any,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Any => write!(f, "Any"),
+            Type::Nil => write!(f, "Nil"),
+            Type::Bool => write!(f, "Bool"),
+            Type::Number => write!(f, "Number"),
+            Type::String => write!(f, "String"),
+            Type::Function(_) | Type::Callable => write!(f, "Function"),
+            Type::Class(name) => write!(f, "class {}", name),
+            Type::Instance(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl Type {
+    // whether a value of type `other` can go where `self` is expected
+    pub fn accepts(&self, other: &Type) -> bool {
+        match (self, other) {
+            (Type::Any, _) | (_, Type::Any) => true,
+            (Type::Callable, Type::Function(_) | Type::Class(_)) => true,
+            (Type::Function(_), Type::Callable) => true,
+            (Type::Function(_), Type::Function(_)) => true,
+            (expected, actual) => expected == actual,
+        }
+    }
+
+    // the type of a value that is either `self` or `other`
+    fn join(&self, other: &Type) -> Type {
+        if self == other {
+            self.clone()
+        } else {
+            Type::Any
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ClassInfo {
+    fields: HashMap<String, Type>,
+    methods: HashMap<String, Rc<Signature>>,
+}
+
+pub struct Checker {
+    scopes: Vec<HashMap<String, Type>>,
+    classes: HashMap<String, ClassInfo>,
+    // every class name in the program, so annotations can refer to later classes
+    class_names: HashSet<String>,
+    // variables that are assigned after their declaration, or declared twice
+    reassigned: HashSet<String>,
+    current_class: Option<String>,
+    // the declared return type and the types returned so far, per function
+    functions: Vec<(Option<Type>, Vec<Type>)>,
+    errors: Vec<ResolveError>,
+}
+
+impl Default for Checker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Checker {
+    pub fn new() -> Self {
+        Checker {
+            scopes: vec![HashMap::new()],
+            classes: HashMap::new(),
+            class_names: HashSet::new(),
+            reassigned: HashSet::new(),
+            current_class: None,
+            functions: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn errors(&self) -> &[ResolveError] {
+        &self.errors
+    }
+
+    pub fn check(&mut self, statements: &[Stmt]) -> Result<()> {
+        let mut declared = HashSet::new();
+        for statement in statements {
+            let statement = match statement {
+                Stmt::Export { declaration } => declaration.as_ref(),
+                statement => statement,
+            };
+            if let Stmt::Variable { name, .. } | Stmt::Function { name, .. } | Stmt::Class { name, .. } = statement {
+                if !declared.insert(name.lexeme.clone()) {
+                    self.reassigned.insert(name.lexeme.clone());
+                }
+            }
+        }
+        self.collect(statements);
+
+        for statement in statements {
+            statement.accept(self)?;
+        }
+        Ok(())
+    }
+
+    fn error(&mut self, token: &Token, message: String) {
+        self.errors.push(ResolveError {
+            line: token.line,
+            lexeme: token.lexeme.clone(),
+            message,
+        });
+    }
+
+    fn expect(&mut self, token: &Token, expected: &Type, actual: &Type) {
+        if !expected.accepts(actual) {
+            self.error(token, format!("Expected {} but got {}.", expected, actual));
+        }
+    }
+
+    fn check_expression(&mut self, expr: &Expr) -> Result<Type> {
+        expr.accept(self)
+    }
+
+    fn check_block(&mut self, statements: &[Stmt]) -> Result<()> {
+        self.scopes.push(HashMap::new());
+        let result = statements.iter().try_for_each(|statement| statement.accept(self));
+        self.scopes.pop();
+        result
+    }
+
+    fn define(&mut self, name: &Token, ty: Type) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), ty);
+        }
+    }
+
+    fn lookup(&self, name: &Token) -> Type {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&name.lexeme))
+            .cloned()
+            .unwrap_or(Type::Any)
+    }
+
+    // the type an unannotated declaration gets
+    fn inferred(&self, name: &Token, ty: Type) -> Type {
+        if self.reassigned.contains(&name.lexeme) {
+            Type::Any
+        } else {
+            ty
+        }
+    }
+
+    fn annotation(&mut self, token: &Token) -> Type {
+        match token.lexeme.as_str() {
+            "Any" => Type::Any,
+            "Nil" => Type::Nil,
+            "Bool" => Type::Bool,
+            "Number" => Type::Number,
+            "String" => Type::String,
+            "Function" => Type::Callable,
+            name if self.class_names.contains(name) => Type::Instance(name.to_string()),
+            name => {
+                self.error(token, format!("Unknown type '{}'.", name));
+                Type::Any
+            }
+        }
+    }
+
+    fn optional_annotation(&mut self, token: &Option<Token>) -> Option<Type> {
+        token.as_ref().map(|token| self.annotation(token))
+    }
+
+    fn signature(&mut self, function: &Stmt) -> Signature {
+        match function {
+            Stmt::Function { params, param_types, defaults, rest, return_type, .. } => Signature {
+                params: param_types
+                    .iter()
+                    .map(|annotation| self.optional_annotation(annotation).unwrap_or(Type::Any))
+                    .collect(),
+                names: params.iter().map(|param| param.lexeme.clone()).collect(),
+                arity: Arity {
+                    min: defaults.iter().filter(|default| default.is_none()).count(),
+                    max: rest.is_none().then_some(params.len()),
+                },
+                result: self.optional_annotation(return_type).unwrap_or(Type::Any),
+            },
+            _ => Signature::empty(),
+        }
+    }
+
+    // checks a function body, returns the signature with the inferred result type
+    fn check_function(&mut self, function: &Stmt, mut signature: Signature) -> Result<Signature> {
+        let Stmt::Function { params, defaults, rest, return_type, body, generator, .. } = function else {
+            return Err(Error::Runtime("checker::check_function - should never happen".to_string()));
+        };
+
+        let declared = return_type.as_ref().map(|_| signature.result.clone());
+        self.functions.push((declared.clone(), Vec::new()));
+
+        self.scopes.push(HashMap::new());
+        let result = self.check_params(params, defaults, &signature).and_then(|()| {
+            if let Some(rest) = rest {
+                self.define(rest, Type::Any);
+            }
+            body.iter().try_for_each(|statement| statement.accept(self))
+        });
+        self.scopes.pop();
+
+        let (_, mut returned) = self.functions.pop().unwrap_or_default();
+        result?;
+
+        if *generator {
+            // calling it gives an iterator, there is no type for those
+            signature.result = Type::Any;
+        } else if declared.is_none() {
+            // falling off the end returns nil
+            if !matches!(body.last(), Some(Stmt::Return { .. } | Stmt::Throw { .. })) {
+                returned.push(Type::Nil);
+            }
+            signature.result = match returned.split_first() {
+                Some((first, rest)) => rest.iter().fold(first.clone(), |ty, other| ty.join(other)),
+                None => Type::Nil,
+            };
+        }
+        Ok(signature)
+    }
+
+    // a default can use the parameters before it
+    fn check_params(&mut self, params: &[Token], defaults: &[Option<Expr>], signature: &Signature) -> Result<()> {
+        for ((param, default), ty) in params.iter().zip(defaults).zip(signature.params.iter()) {
+            if let Some(default) = default {
+                let actual = self.check_expression(default)?;
+                self.expect(param, ty, &actual);
+            }
+            self.define(param, ty.clone());
+        }
+        Ok(())
+    }
+
+    fn call(&mut self, paren: &Token, signature: &Signature, arguments: &[Type], named: &[(Token, Type)]) {
+        let checked = signature.arity.check(signature.params.len(), arguments.len(), named, |name| {
+            signature.names.iter().position(|param| param == name)
+        });
+        if let Err(message) = checked {
+            self.error(paren, message);
+            return;
+        }
+
+        // the arguments past the parameters go into the rest list
+        for (index, (expected, actual)) in signature.params.iter().zip(arguments).enumerate() {
+            if !expected.accepts(actual) {
+                let message = format!("Expected {} for argument {} but got {}.", expected, index + 1, actual);
+                self.error(paren, message);
+            }
+        }
+
+        for (name, actual) in named {
+            if let Some(index) = signature.names.iter().position(|param| *param == name.lexeme) {
+                self.expect(name, &signature.params[index], actual);
+            }
+        }
+    }
+
+    // the type of `left operator right` for arithmetic and comparisons
+    fn operation(&mut self, operator: &Token, left: &Type, right: &Type) -> Type {
+        match operator.token_type {
+            EQUAL_EQUAL | BANG_EQUAL => Type::Bool,
+            PLUS | PLUS_EQUAL | PLUS_PLUS => match (left, right) {
+                (Type::Number | Type::String, _) if left.accepts(right) => left.clone(),
+                (_, Type::Number | Type::String) if right.accepts(left) => right.clone(),
+                (Type::Any, _) | (_, Type::Any) => Type::Any,
+                _ => {
+                    self.error(operator, "Operands must be two numbers or two strings.".to_string());
+                    Type::Any
+                }
+            },
+            _ => {
+                if !Type::Number.accepts(left) || !Type::Number.accepts(right) {
+                    self.error(operator, "Operands must be numbers.".to_string());
+                }
+                match operator.token_type {
+                    GREATER | GREATER_EQUAL | LESS | LESS_EQUAL => Type::Bool,
+                    // ranges have no type of their own
+                    DOT_DOT => Type::Any,
+                    _ => Type::Number,
+                }
+            }
+        }
+    }
+
+    // collects class names and reassigned variables before checking
+    fn collect(&mut self, statements: &[Stmt]) {
+        for statement in statements {
+            match statement {
+                Stmt::Print(expr) | Stmt::Expression(expr) => self.collect_expression(expr),
+                Stmt::Variable { initializer, .. } => {
+                    if let Some(initializer) = initializer {
+                        self.collect_expression(initializer);
+                    }
+                }
+                Stmt::Block { statements } => self.collect(statements),
+                Stmt::If { condition, then_branch, else_branch } => {
+                    self.collect_expression(condition);
+                    self.collect(std::slice::from_ref(then_branch));
+                    if let Some(else_branch) = else_branch {
+                        self.collect(std::slice::from_ref(else_branch));
+                    }
+                }
+                Stmt::While { condition, body } => {
+                    self.collect_expression(condition);
+                    self.collect(std::slice::from_ref(body));
+                }
+                Stmt::ForIn { iterable, body, .. } => {
+                    self.collect_expression(iterable);
+                    self.collect(std::slice::from_ref(body));
+                }
+                Stmt::Function { defaults, body, .. } => {
+                    defaults.iter().flatten().for_each(|default| self.collect_expression(default));
+                    self.collect(body);
+                }
+                Stmt::Return { value, .. } => {
+                    if let Some(value) = value {
+                        self.collect_expression(value);
+                    }
+                }
+                Stmt::Class { name, methods, .. } => {
+                    self.class_names.insert(name.lexeme.clone());
+                    for method in methods {
+                        self.collect(std::slice::from_ref(method.as_ref()));
+                    }
+                }
+                Stmt::Throw { value, .. } | Stmt::Yield { value, .. } => self.collect_expression(value),
+                Stmt::Match { value, arms, .. } => {
+                    self.collect_expression(value);
+                    for arm in arms {
+                        if let Some(guard) = &arm.guard {
+                            self.collect_expression(guard);
+                        }
+                        self.collect(std::slice::from_ref(&arm.body));
+                    }
+                }
+                Stmt::Try { body, catch_clause, finally_body } => {
+                    self.collect(body);
+                    if let Some((_, catch_body)) = catch_clause {
+                        self.collect(catch_body);
+                    }
+                    if let Some(finally_body) = finally_body {
+                        self.collect(finally_body);
+                    }
+                }
+                Stmt::Export { declaration } => self.collect(std::slice::from_ref(declaration.as_ref())),
+                Stmt::Import { .. } | Stmt::Break { .. } => {}
+            }
+        }
+    }
+
+    fn collect_expression(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Assign { name, value } => {
+                self.reassigned.insert(name.lexeme.clone());
+                self.collect_expression(value);
+            }
+            Expr::Update { target, value, .. } => {
+                if let Expr::Variable { name } = target.as_ref() {
+                    self.reassigned.insert(name.lexeme.clone());
+                }
+                self.collect_expression(target);
+                self.collect_expression(value);
+            }
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                self.collect_expression(left);
+                self.collect_expression(right);
+            }
+            Expr::Unary { right, .. } => self.collect_expression(right),
+            Expr::Grouping { expression } => self.collect_expression(expression),
+            Expr::Call { callee, arguments, named, .. } => {
+                self.collect_expression(callee);
+                arguments.iter().for_each(|argument| self.collect_expression(argument));
+                named.iter().for_each(|(_, argument)| self.collect_expression(argument));
+            }
+            Expr::Get { object, .. } => self.collect_expression(object),
+            Expr::Set { object, value, .. } => {
+                self.collect_expression(object);
+                self.collect_expression(value);
+            }
+            Expr::Interpolation { parts } | Expr::List { elements: parts, .. } => {
+                parts.iter().for_each(|part| self.collect_expression(part))
+            }
+            Expr::Map { entries, .. } => entries.iter().for_each(|(key, value)| {
+                self.collect_expression(key);
+                self.collect_expression(value);
+            }),
+            Expr::Index { object, index, .. } => {
+                self.collect_expression(object);
+                self.collect_expression(index);
+            }
+            Expr::SetIndex { object, index, value, .. } => {
+                self.collect_expression(object);
+                self.collect_expression(index);
+                self.collect_expression(value);
+            }
+            Expr::Conditional { condition, then_branch, else_branch } => {
+                self.collect_expression(condition);
+                self.collect_expression(then_branch);
+                self.collect_expression(else_branch);
+            }
+            Expr::Literal { .. } | Expr::Variable { .. } | Expr::This { .. } => {}
+        }
+    }
+}
+
+impl ExprVisitor<Type> for Checker {
+    fn visit_binary_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> Result<Type> {
+        let left = self.check_expression(left)?;
+        let right = self.check_expression(right)?;
+        Ok(self.operation(operator, &left, &right))
+    }
+
+    fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) -> Result<Type> {
+        let right = self.check_expression(right)?;
+        if operator.token_type == BANG {
+            return Ok(Type::Bool);
+        }
+
+        if !Type::Number.accepts(&right) {
+            self.error(operator, "Operand must be a number.".to_string());
+        }
+        Ok(Type::Number)
+    }
+
+    fn visit_grouping_expr(&mut self, expr: &Expr) -> Result<Type> {
+        self.check_expression(expr)
+    }
+
+    fn visit_literal_expr(&mut self, value: &Literal) -> Result<Type> {
+        Ok(match value {
+            Literal::String(_) => Type::String,
+            Literal::Number(_) => Type::Number,
+            Literal::Boolean(_) => Type::Bool,
+            Literal::None => Type::Nil,
+        })
+    }
+
+    fn visit_variable_expr(&mut self, name: &Token) -> Result<Type> {
+        Ok(self.lookup(name))
+    }
+
+    fn visit_variable_assignment_expr(&mut self, expr: &Expr) -> Result<Type> {
+        if let Expr::Assign { name, value } = expr {
+            let value = self.check_expression(value)?;
+            let expected = self.lookup(name);
+            self.expect(name, &expected, &value);
+            Ok(value)
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_logical_expr(&mut self, expr: &Expr) -> Result<Type> {
+        if let Expr::Logical { left, right, .. } = expr {
+            let left = self.check_expression(left)?;
+            let right = self.check_expression(right)?;
+            Ok(left.join(&right))
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_call_expr(&mut self, expr: &Expr) -> Result<Type> {
+        if let Expr::Call { callee, paren, arguments, named } = expr {
+            let callee = self.check_expression(callee)?;
+            let arguments = arguments
+                .iter()
+                .map(|argument| self.check_expression(argument))
+                .collect::<Result<Vec<Type>>>()?;
+            let named = named
+                .iter()
+                .map(|(name, argument)| Ok((name.clone(), self.check_expression(argument)?)))
+                .collect::<Result<Vec<(Token, Type)>>>()?;
+
+            match callee {
+                Type::Function(signature) => {
+                    self.call(paren, &signature, &arguments, &named);
+                    Ok(signature.result.clone())
+                }
+                Type::Class(name) => {
+                    let init = self
+                        .classes
+                        .get(&name)
+                        .and_then(|class| class.methods.get("init"))
+                        .cloned()
+                        .unwrap_or_else(|| Rc::new(Signature::empty()));
+                    self.call(paren, &init, &arguments, &named);
+                    Ok(Type::Instance(name))
+                }
+                Type::Any | Type::Callable => Ok(Type::Any),
+                _ => {
+                    self.error(paren, "Can only call functions and classes.".to_string());
+                    Ok(Type::Any)
+                }
+            }
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_get_expr(&mut self, expr: &Expr) -> Result<Type> {
+        if let Expr::Get { object, name } = expr {
+            match self.check_expression(object)? {
+                Type::Instance(class) => {
+                    let Some(class) = self.classes.get(&class) else {
+                        return Ok(Type::Any);
+                    };
+                    if let Some(field) = class.fields.get(&name.lexeme) {
+                        Ok(field.clone())
+                    } else if let Some(method) = class.methods.get(&name.lexeme) {
+                        Ok(Type::Function(Rc::clone(method)))
+                    } else {
+                        // fields can also be added without a declaration
+                        Ok(Type::Any)
+                    }
+                }
+                Type::Any => Ok(Type::Any),
+                _ => {
+                    self.error(name, "Only instances have properties.".to_string());
+                    Ok(Type::Any)
+                }
+            }
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_set_expr(&mut self, expr: &Expr) -> Result<Type> {
+        if let Expr::Set { object, name, value } = expr {
+            let value = self.check_expression(value)?;
+            match self.check_expression(object)? {
+                Type::Instance(class) => {
+                    let field = self
+                        .classes
+                        .get(&class)
+                        .and_then(|class| class.fields.get(&name.lexeme))
+                        .cloned();
+                    if let Some(field) = field {
+                        self.expect(name, &field, &value);
+                    }
+                }
+                Type::Any => {}
+                _ => self.error(name, "Only instances have fields.".to_string()),
+            }
+            Ok(value)
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_this_expr(&mut self, _keyword: &Token) -> Result<Type> {
+        Ok(self.current_class.clone().map_or(Type::Any, Type::Instance))
+    }
+
+    fn visit_interpolation_expr(&mut self, parts: &[Expr]) -> Result<Type> {
+        for part in parts {
+            self.check_expression(part)?;
+        }
+        Ok(Type::String)
+    }
+
+    fn visit_conditional_expr(&mut self, expr: &Expr) -> Result<Type> {
+        if let Expr::Conditional { condition, then_branch, else_branch } = expr {
+            self.check_expression(condition)?;
+            let then_branch = self.check_expression(then_branch)?;
+            let else_branch = self.check_expression(else_branch)?;
+            Ok(then_branch.join(&else_branch))
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    // lists and maps hold values of any type
+    fn visit_list_expr(&mut self, expr: &Expr) -> Result<Type> {
+        if let Expr::List { elements, .. } = expr {
+            for element in elements {
+                self.check_expression(element)?;
+            }
+            Ok(Type::Any)
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_map_expr(&mut self, expr: &Expr) -> Result<Type> {
+        if let Expr::Map { entries, .. } = expr {
+            for (key, value) in entries {
+                self.check_expression(key)?;
+                self.check_expression(value)?;
+            }
+            Ok(Type::Any)
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_index_expr(&mut self, expr: &Expr) -> Result<Type> {
+        if let Expr::Index { object, index, .. } = expr {
+            self.check_expression(object)?;
+            self.check_expression(index)?;
+            Ok(Type::Any)
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_set_index_expr(&mut self, expr: &Expr) -> Result<Type> {
+        if let Expr::SetIndex { object, index, value, .. } = expr {
+            self.check_expression(object)?;
+            self.check_expression(index)?;
+            self.check_expression(value)
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_update_expr(&mut self, expr: &Expr) -> Result<Type> {
+        if let Expr::Update { target, operator, value, postfix } = expr {
+            let current = self.check_expression(target)?;
+            let value = self.check_expression(value)?;
+            let updated = self.operation(operator, &current, &value);
+
+            if let Expr::Variable { name } = target.as_ref() {
+                self.expect(name, &current, &updated);
+            }
+            Ok(if *postfix { current } else { updated })
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+}
+
+impl StmtVisitor<()> for Checker {
+    fn visit_print_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::Print(expr) = statement {
+            self.check_expression(expr).map(|_| ())
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_expression_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::Expression(expr) = statement {
+            self.check_expression(expr).map(|_| ())
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_variable_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::Variable { name, annotation, initializer } = statement {
+            let value = match initializer {
+                Some(initializer) => Some(self.check_expression(initializer)?),
+                None => None,
+            };
+
+            let ty = match self.optional_annotation(annotation) {
+                Some(ty) => {
+                    // without an initializer it starts out as nil, that is fine
+                    if let Some(value) = &value {
+                        self.expect(name, &ty, value);
+                    }
+                    ty
+                }
+                None => self.inferred(name, value.unwrap_or(Type::Any)),
+            };
+            self.define(name, ty);
+            Ok(())
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_block_statement(&mut self, statements: &[Stmt]) -> Result<()> {
+        self.check_block(statements)
+    }
+
+    fn visit_if_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::If { condition, then_branch, else_branch } = statement {
+            self.check_expression(condition)?;
+            then_branch.accept(self)?;
+            if let Some(else_branch) = else_branch {
+                else_branch.accept(self)?;
+            }
+            Ok(())
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_while_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::While { condition, body } = statement {
+            self.check_expression(condition)?;
+            body.accept(self)
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_for_in_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::ForIn { name, iterable, body } = statement {
+            self.check_expression(iterable)?;
+            self.scopes.push(HashMap::new());
+            self.define(name, Type::Any);
+            let result = body.accept(self);
+            self.scopes.pop();
+            result
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_function_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::Function { name, .. } = statement {
+            // recursive calls see the signature as annotated
+            let signature = self.signature(statement);
+            let ty = self.inferred(name, Type::Function(Rc::new(signature.clone())));
+            self.define(name, ty);
+
+            let signature = self.check_function(statement, signature)?;
+            let ty = self.inferred(name, Type::Function(Rc::new(signature)));
+            self.define(name, ty);
+            Ok(())
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_return_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::Return { keyword, value } = statement {
+            let value = match value {
+                Some(value) => self.check_expression(value)?,
+                None => Type::Nil,
+            };
+
+            let declared = self.functions.last().and_then(|(declared, _)| declared.clone());
+            if let Some(declared) = declared {
+                self.expect(keyword, &declared, &value);
+            }
+            if let Some((_, returned)) = self.functions.last_mut() {
+                returned.push(value);
+            }
+            Ok(())
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_break_statement(&mut self, _keyword: &Token) -> Result<()> {
+        Ok(())
+    }
+
+    fn visit_yield_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::Yield { value, .. } = statement {
+            self.check_expression(value).map(|_| ())
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_throw_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::Throw { value, .. } = statement {
+            // anything can be thrown
+            self.check_expression(value).map(|_| ())
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_match_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::Match { value, arms, .. } = statement {
+            self.check_expression(value)?;
+            for arm in arms {
+                self.scopes.push(HashMap::new());
+                for name in arm.pattern.bindings() {
+                    self.define(name, Type::Any);
+                }
+                let result = match &arm.guard {
+                    Some(guard) => self.check_expression(guard).and_then(|_| arm.body.accept(self)),
+                    None => arm.body.accept(self),
+                };
+                self.scopes.pop();
+                result?;
+            }
+            Ok(())
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_try_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::Try { body, catch_clause, finally_body } = statement {
+            self.check_block(body)?;
+            if let Some((name, catch_body)) = catch_clause {
+                self.scopes.push(HashMap::new());
+                self.define(name, Type::Any);
+                let result = self.check_block(catch_body);
+                self.scopes.pop();
+                result?;
+            }
+            if let Some(finally_body) = finally_body {
+                self.check_block(finally_body)?;
+            }
+            Ok(())
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_class_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::Class { name, fields, methods } = statement {
+            let mut class = ClassInfo::default();
+            for (field, annotation) in fields {
+                let ty = self.annotation(annotation);
+                class.fields.insert(field.lexeme.clone(), ty);
+            }
+
+            let mut signatures = Vec::new();
+            for method in methods {
+                let mut signature = self.signature(method);
+                if let Stmt::Function { name: method_name, .. } = method.as_ref() {
+                    if method_name.lexeme == "init" {
+                        signature.result = Type::Instance(name.lexeme.clone());
+                    }
+                    class.methods.insert(method_name.lexeme.clone(), Rc::new(signature.clone()));
+                }
+                signatures.push(signature);
+            }
+
+            self.classes.insert(name.lexeme.clone(), class);
+            let ty = self.inferred(name, Type::Class(name.lexeme.clone()));
+            self.define(name, ty);
+
+            let enclosing = self.current_class.replace(name.lexeme.clone());
+            for (method, signature) in methods.iter().zip(signatures) {
+                let Stmt::Function { name: method_name, return_type, .. } = method.as_ref() else {
+                    continue;
+                };
+
+                // an initializer always returns the instance
+                let init = method_name.lexeme == "init" && return_type.is_none();
+                let checked = self.check_function(method, signature.clone());
+                let checked = match checked {
+                    Ok(checked) if !init => checked,
+                    Ok(_) => signature,
+                    Err(err) => {
+                        self.current_class = enclosing;
+                        return Err(err);
+                    }
+                };
+
+                if let Some(class) = self.classes.get_mut(&name.lexeme) {
+                    class.methods.insert(method_name.lexeme.clone(), Rc::new(checked));
+                }
+            }
+            self.current_class = enclosing;
+            Ok(())
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_import_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::Import { alias, names, .. } = statement {
+            // modules are checked when they are loaded, what they export is unknown here
+            for name in alias.iter().chain(names) {
+                self.define(name, Type::Any);
+            }
+            Ok(())
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_export_statement(&mut self, declaration: &Stmt) -> Result<()> {
+        declaration.accept(self)
+    }
+}
@@ -1,49 +1,107 @@
 use crate::errors::{Error, Result};
-use crate::interpreter::{self, Interpreter};
+use crate::function::{Arity, Function};
+use crate::interpreter::Interpreter;
 use crate::object::Object;
 use crate::tokens::Token;
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
 
 #[derive(Clone, Debug)]
 pub struct Class {
-    name: String,
+    pub name: String,
+    // shared between every copy of the class so the heap can trace (and break) method closures
+    pub(crate) methods: Rc<RefCell<HashMap<String, Function>>>,
 }
 
 impl Class {
-    pub fn new(name: String) -> Self {
-        Class { name }
+    pub fn new(name: String, methods: Rc<RefCell<HashMap<String, Function>>>) -> Self {
+        Class { name, methods }
     }
 
-    pub fn call(self, interpreter: &Interpreter, arguments: &Vec<Object>) -> Instance {
-        Instance::new(self)
+    pub fn find_method(&self, name: &str) -> Option<Function> {
+        self.methods.borrow().get(name).cloned()
     }
 
-    pub fn arity() -> usize {
-        0
+    // the arguments, named ones too, go to `init`
+    pub fn call(self, interpreter: &mut Interpreter, arguments: Vec<Object>, named: Vec<(Token, Object)>) -> Result<Object> {
+        let instance = Object::Instance(Instance::new(interpreter, self.clone()));
+
+        if let Some(initializer) = self.find_method("init") {
+            initializer
+                .bind(interpreter, instance.clone())
+                .call_with(interpreter, arguments, named)?;
+        }
+
+        Ok(instance)
+    }
+
+    pub fn check_arguments<T>(&self, positional: usize, named: &[(Token, T)]) -> Result<()> {
+        match self.find_method("init") {
+            Some(init) => init.check_arguments(positional, named),
+            None => Arity::exactly(0)
+                .check(0, positional, named, |_| None)
+                .map_err(Error::Runtime),
+        }
+    }
+
+    // the fields a `Point(x, y)` pattern looks at, named after the parameters of `init`
+    pub fn pattern_fields(&self) -> Vec<String> {
+        match self.find_method("init") {
+            Some(Function::User { params, .. }) => params.iter().map(|param| param.lexeme.clone()).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for Class {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct Instance {
-    class: Class, // should this be Rc<RefCell ??
-    fields: HashMap<String, Object>,
+    pub(crate) class: Class,
+    pub(crate) fields: Rc<RefCell<HashMap<String, Object>>>,
 }
 
 impl Instance {
-    pub fn new(class: Class) -> Self {
+    pub fn new(interpreter: &mut Interpreter, class: Class) -> Self {
         Instance {
             class,
-            fields: HashMap::default(),
+            fields: interpreter.heap.allocate_instance(HashMap::default()),
         }
     }
 
-    pub fn get(&self, name: &Token) -> Result<Object> {
-        match self.fields.get(&name.lexeme) {
-            Some(v) => Ok(v.clone()),
+    pub fn get(&self, interpreter: &mut Interpreter, name: &Token) -> Result<Object> {
+        if let Some(v) = self.fields.borrow().get(&name.lexeme) {
+            return Ok(v.clone());
+        }
+
+        match self.class.find_method(&name.lexeme) {
+            Some(method) => Ok(Object::Callable(
+                method.bind(interpreter, Object::Instance(self.clone())),
+            )),
             None => Err(Error::Runtime(format!(
                 "Undefined property '{}'.",
                 name.lexeme
             ))),
         }
     }
+
+    pub fn set(&self, name: &Token, value: Object) {
+        self.fields.borrow_mut().insert(name.lexeme.clone(), value);
+    }
+
+    pub fn is(&self, other: &Instance) -> bool {
+        Rc::ptr_eq(&self.fields, &other.fields)
+    }
+}
+
+impl fmt::Display for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} instance", self.class.name)
+    }
 }
@@ -0,0 +1,221 @@
+use std::cmp::Reverse;
+
+use crate::errors::{Result, ScanError};
+use crate::parser::Parser;
+use crate::scanner::{Scanner, Trivia};
+use crate::statement::Stmt;
+use crate::tokens::{Literal, Token, TokenType};
+
+// A lossless concrete syntax tree. Every token keeps the whitespace, comments and
+// unscannable characters in front of it, so printing the tree gives back the
+// source byte for byte, even when it does not scan or parse.
+//
+// The tree is built from the token ranges the parser records while it runs. Parts
+// that fail to parse are left as plain tokens under the nearest node that did.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyntaxKind {
+    SourceFile,
+
+    ImportStmt,
+    ExportDecl,
+
+    ClassDecl,
+    FunDecl,
+    Method,
+    Field,
+    VarDecl,
+
+    PrintStmt,
+    ReturnStmt,
+    BreakStmt,
+    ThrowStmt,
+    TryStmt,
+    MatchStmt,
+    MatchArm,
+    Pattern,
+    YieldStmt,
+    WhileStmt,
+    ForStmt,
+    ForInStmt,
+    IfStmt,
+    Block,
+    ExprStmt,
+
+    Assign,
+    Update,
+    Conditional,
+    Logical,
+    Binary,
+    Unary,
+    Call,
+    Get,
+    Index,
+    Grouping,
+    List,
+    Map,
+    Literal,
+    Variable,
+    This,
+    Interpolation,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxToken {
+    pub leading: Vec<Trivia>,
+    pub token: Token,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxNode {
+    pub kind: SyntaxKind,
+    pub children: Vec<SyntaxElement>,
+}
+
+// scans with trivia and parses the whole source, parse errors are reported as usual
+pub fn parse(source: &str) -> (SyntaxNode, Vec<ScanError>) {
+    let mut scanner = Scanner::with_trivia(source.to_string());
+    let (tokens, errors) = scanner.scan_tokens();
+    let trivia = scanner.trivia().to_vec();
+
+    let mut parser = Parser::new(tokens.clone());
+    let _ = parser.parse();
+    let mut nodes = parser.nodes;
+
+    // outer nodes first, a node recorded later encloses an earlier one with the same
+    // range, the sort is stable
+    nodes.reverse();
+    nodes.sort_by_key(|(_, start, end)| (*start, Reverse(*end)));
+
+    let mut tokens = tokens
+        .into_iter()
+        .zip(trivia)
+        .map(|(token, leading)| SyntaxToken { leading, token })
+        .peekable();
+
+    // open nodes with the token index they end at
+    let mut stack = vec![(
+        SyntaxNode {
+            kind: SyntaxKind::SourceFile,
+            children: Vec::new(),
+        },
+        usize::MAX,
+    )];
+    let mut nodes = nodes.into_iter().peekable();
+    let mut index = 0;
+
+    while tokens.peek().is_some() {
+        // close the nodes that end here
+        while stack.len() > 1 && stack.last().is_some_and(|(_, end)| *end == index) {
+            close(&mut stack);
+        }
+
+        match nodes.next_if(|(_, start, _)| *start == index) {
+            Some((kind, _, end)) => {
+                stack.push((SyntaxNode { kind, children: Vec::new() }, end));
+            }
+            None => {
+                if let Some(token) = tokens.next() {
+                    push(&mut stack, SyntaxElement::Token(token));
+                }
+                index += 1;
+            }
+        }
+    }
+
+    while stack.len() > 1 {
+        close(&mut stack);
+    }
+
+    let root = stack.pop().map(|(root, _)| root).unwrap_or(SyntaxNode {
+        kind: SyntaxKind::SourceFile,
+        children: Vec::new(),
+    });
+    (root, errors)
+}
+
+fn push(stack: &mut [(SyntaxNode, usize)], element: SyntaxElement) {
+    if let Some((node, _)) = stack.last_mut() {
+        node.children.push(element);
+    }
+}
+
+fn close(stack: &mut Vec<(SyntaxNode, usize)>) {
+    if let Some((node, _)) = stack.pop() {
+        push(stack, SyntaxElement::Node(node));
+    }
+}
+
+impl SyntaxElement {
+    fn write_text(&self, text: &mut String) {
+        match self {
+            SyntaxElement::Node(node) => {
+                for child in node.children.iter() {
+                    child.write_text(text);
+                }
+            }
+            SyntaxElement::Token(token) => {
+                for trivia in token.leading.iter() {
+                    text.push_str(&trivia.text);
+                }
+                text.push_str(&token.token.lexeme);
+            }
+        }
+    }
+}
+
+impl SyntaxNode {
+    // the exact source the node was parsed from
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for child in self.children.iter() {
+            child.write_text(&mut text);
+        }
+        text
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &SyntaxNode> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    // all tokens in source order, the root includes EOF
+    pub fn tokens(&self) -> Vec<&SyntaxToken> {
+        let mut tokens = Vec::new();
+        self.collect_tokens(&mut tokens);
+        tokens
+    }
+
+    fn collect_tokens<'a>(&'a self, tokens: &mut Vec<&'a SyntaxToken>) {
+        for child in self.children.iter() {
+            match child {
+                SyntaxElement::Node(node) => node.collect_tokens(tokens),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+    }
+
+    // lowers a source file or declaration node to the AST by parsing its tokens again,
+    // the AST ends up with the very tokens stored in the tree
+    pub fn lower(&self) -> Result<Vec<Stmt>> {
+        let mut tokens: Vec<Token> = self
+            .tokens()
+            .into_iter()
+            .map(|token| token.token.clone())
+            .filter(|token| token.token_type != TokenType::EOF)
+            .collect();
+
+        let line = tokens.last().map_or(1, |token| token.line);
+        tokens.push(Token::new(TokenType::EOF, "".to_string(), Literal::None, line, usize::MAX));
+
+        Parser::new(tokens).parse()
+    }
+}
@@ -19,6 +19,18 @@ impl Environment {
         }
     }
 
+    // the outermost environment, the globals of the module it belongs to
+    pub fn root(environment: &Rc<RefCell<Environment>>) -> Rc<RefCell<Environment>> {
+        let mut root = Rc::clone(environment);
+        loop {
+            let enclosing = root.borrow().enclosing.clone();
+            match enclosing {
+                Some(enclosing) => root = enclosing,
+                None => return root,
+            }
+        }
+    }
+
     pub fn define(&mut self, name: String, value: Object) {
         // println!("defining: {:?}", name);
         self.values.insert(name, value);
@@ -61,17 +73,15 @@ impl Environment {
 
         // println!("{}", distance);
         // println!("self.values = {:?}", self.values);
-        let value;
-        if distance > 0 {
-            value = self
-                .ancestor(distance)
+        let value = if distance > 0 {
+            self.ancestor(distance)
                 .borrow()
                 .values
                 .get(&name.lexeme)
-                .cloned();
+                .cloned()
         } else {
-            value = self.values.get(&name.lexeme).cloned();
-        }
+            self.values.get(&name.lexeme).cloned()
+        };
 
         // println!("value = {:?}", value);
 
@@ -7,7 +7,11 @@ pub enum Error {
     Runtime(String),
     Syntax,
     Scanner,
-    Return{value: Object}
+    Return{value: Object},
+    Break,
+    // a thrown value on its way to a `catch`, the message is what gets printed when
+    // nothing catches it
+    Throw{value: Box<Object>, message: String},
 }
 
 impl error::Error for Error {}
@@ -16,9 +20,43 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Error::Runtime(s) => write!(f, "{}", s),
+            Error::Throw { message, .. } => write!(f, "{}", message),
             _ => write!(f, "Lox generic error!"),
         }
     }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
+
+// a problem found while scanning, the column is 1-based and counts characters
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl error::Error for ScanError {}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[line {}:{}] Error: {}", self.line, self.column, self.message)
+    }
+}
+
+// a static error the resolver or the type checker found at a token, in the reference
+// implementation's format
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolveError {
+    pub line: usize,
+    pub lexeme: String,
+    pub message: String,
+}
+
+impl error::Error for ResolveError {}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[line {}] Error at '{}': {}", self.line, self.lexeme, self.message)
+    }
+}
@@ -0,0 +1,393 @@
+use crate::errors::{Error, Result};
+use crate::parser::Parser;
+use crate::scanner::{Scanner, Trivia, TriviaKind};
+use crate::tokens::Token;
+use crate::tokens::TokenType::*;
+
+// An opinionated pretty-printer: two space indentation, one statement per line,
+// spaces around binary operators, `{` on the line it opens and at most one blank
+// line in a row. Argument and parameter lists that do not fit in MAX_WIDTH get one
+// entry per line. Comments are kept where they were, relative to the tokens.
+//
+// It works on the token stream with trivia so that nothing but whitespace changes.
+// The source has to scan and parse though, broken code is left alone.
+
+const INDENT: &str = "  ";
+const MAX_WIDTH: usize = 80;
+
+pub fn format(source: &str) -> Result<String> {
+    let mut scanner = Scanner::with_trivia(source.to_string());
+    let (tokens, errors) = scanner.scan_tokens();
+    if !errors.is_empty() {
+        for error in errors.iter() {
+            eprintln!("{}", error);
+        }
+        return Err(Error::Scanner);
+    }
+
+    // the parser reports its own errors
+    Parser::new(tokens.clone()).parse()?;
+
+    let mut formatter = Formatter {
+        annotations: annotation_colons(&tokens),
+        maps: map_braces(&tokens),
+        tokens: &tokens,
+        trivia: scanner.trivia(),
+        out: String::new(),
+        indent: 0,
+        continuation: false,
+        parens: Vec::new(),
+    };
+    formatter.run();
+    Ok(formatter.out)
+}
+
+struct Formatter<'a> {
+    tokens: &'a [Token],
+    // for every token, whether it is a `:` spaced like a type annotation, which
+    // named arguments and map entries are too
+    annotations: Vec<bool>,
+    // for every token, whether it is a brace of a map literal
+    maps: Vec<bool>,
+    trivia: &'a [Vec<Trivia>],
+    out: String,
+    indent: usize,
+    // a comment broke a statement over two lines
+    continuation: bool,
+    // for every open parenthesis, whether its entries go on separate lines
+    parens: Vec<bool>,
+}
+
+fn newlines(trivia: &Trivia) -> usize {
+    match trivia.kind {
+        TriviaKind::Whitespace => trivia.text.matches('\n').count(),
+        _ => 0,
+    }
+}
+
+// tokens after which a `-` is binary and a `(` starts a call
+fn ends_operand(token: &Token) -> bool {
+    matches!(
+        token.token_type,
+        IDENTIFIER | NUMBER | STRING | RIGHT_PAREN | RIGHT_BRACKET | THIS | TRUE | FALSE | NIL
+    )
+}
+
+fn is_unary(previous: Option<&Token>, token: &Token) -> bool {
+    match token.token_type {
+        BANG => true,
+        // after `a++` it is a subtraction
+        MINUS => !previous.is_some_and(|previous| {
+            ends_operand(previous) || matches!(previous.token_type, PLUS_PLUS | MINUS_MINUS)
+        }),
+        _ => false,
+    }
+}
+
+// the closing part of an interpolated string, `} rest"`
+fn closes_interpolation(token: &Token) -> bool {
+    token.token_type == STRING && token.lexeme.starts_with('}')
+}
+
+// a `:` is spaced like a type annotation unless it ends a `?:`, a `?` is only
+// closed by a `:` inside the same brackets so map entries in a branch don't
+// end the conditional
+fn annotation_colons(tokens: &[Token]) -> Vec<bool> {
+    // the open conditionals in each enclosing bracket
+    let mut conditionals = vec![0];
+    tokens
+        .iter()
+        .map(|token| match token.token_type {
+            LEFT_PAREN | LEFT_BRACKET | LEFT_BRACE => {
+                conditionals.push(0);
+                false
+            }
+            RIGHT_PAREN | RIGHT_BRACKET | RIGHT_BRACE => {
+                if conditionals.len() > 1 {
+                    conditionals.pop();
+                }
+                false
+            }
+            QUESTION => {
+                *conditionals.last_mut().unwrap() += 1;
+                false
+            }
+            COLON if *conditionals.last().unwrap() > 0 => {
+                *conditionals.last_mut().unwrap() -= 1;
+                false
+            }
+            COLON => true,
+            _ => false,
+        })
+        .collect()
+}
+
+// a `{` opens a block after these and a map literal anywhere else, map literals
+// stay on one line
+fn map_braces(tokens: &[Token]) -> Vec<bool> {
+    let mut open = Vec::new();
+    tokens
+        .iter()
+        .enumerate()
+        .map(|(index, token)| match token.token_type {
+            LEFT_BRACE => {
+                let block = index == 0
+                    || matches!(
+                        tokens[index - 1].token_type,
+                        RIGHT_PAREN | ELSE | SEMICOLON | LEFT_BRACE | RIGHT_BRACE | IDENTIFIER | TRY | FINALLY
+                            | FAT_ARROW
+                    );
+                open.push(!block);
+                !block
+            }
+            RIGHT_BRACE => open.pop().unwrap_or(false),
+            _ => false,
+        })
+        .collect()
+}
+
+// whether a space goes between two tokens on the same line
+fn space_between(before: Option<&Token>, previous: &Token, token: &Token) -> bool {
+    match (&previous.token_type, &token.token_type) {
+        (_, SEMICOLON | COMMA | DOT | RIGHT_PAREN | RIGHT_BRACKET | DOT_DOT) => false,
+        (LEFT_PAREN | LEFT_BRACKET | DOT | DOT_DOT | DOT_DOT_DOT | INTERPOLATION, _) => false,
+        (_, _) if closes_interpolation(token) => false,
+        // a++ and ++a
+        (_, PLUS_PLUS | MINUS_MINUS) if ends_operand(previous) => false,
+        (PLUS_PLUS | MINUS_MINUS, _) if !before.is_some_and(ends_operand) => false,
+        (BANG | MINUS, _) if is_unary(before, previous) => false,
+        // calls and indexing
+        (_, LEFT_PAREN | LEFT_BRACKET) => !ends_operand(previous),
+        (LEFT_BRACE, RIGHT_BRACE) => false,
+        // fun* gen()
+        (FUN, STAR) => false,
+        _ => true,
+    }
+}
+
+impl<'a> Formatter<'a> {
+    fn run(&mut self) {
+        for index in 0..self.tokens.len() {
+            self.comments(index);
+
+            let token = &self.tokens[index];
+            match token.token_type {
+                EOF => break,
+                RIGHT_BRACE if !self.maps[index] => {
+                    self.indent = self.indent.saturating_sub(1);
+                    if self.previous(index).token_type != LEFT_BRACE {
+                        self.newline();
+                    }
+                }
+                RIGHT_PAREN if self.parens.last() == Some(&true) => {
+                    self.indent = self.indent.saturating_sub(1);
+                    self.newline();
+                }
+                _ => {}
+            }
+
+            self.separate(index);
+            self.out.push_str(&token.lexeme);
+            self.after(index);
+        }
+
+        if !self.out.is_empty() {
+            self.newline();
+        }
+    }
+
+    fn previous(&self, index: usize) -> &'a Token {
+        &self.tokens[index.saturating_sub(1)]
+    }
+
+    fn next(&self, index: usize) -> &'a Token {
+        &self.tokens[(index + 1).min(self.tokens.len() - 1)]
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.out.is_empty() || self.out.ends_with('\n')
+    }
+
+    fn column(&self) -> usize {
+        self.out.len() - self.out.rfind('\n').map_or(0, |newline| newline + 1)
+    }
+
+    fn newline(&mut self) {
+        if !self.at_line_start() {
+            self.out.push('\n');
+        }
+    }
+
+    fn blank_line(&mut self) {
+        self.newline();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") && !self.out.ends_with("{\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn write_indent(&mut self) {
+        let depth = self.indent + self.continuation as usize;
+        for _ in 0..depth {
+            self.out.push_str(INDENT);
+        }
+    }
+
+    // the space or indentation in front of a token
+    fn separate(&mut self, index: usize) {
+        if self.at_line_start() {
+            self.write_indent();
+            self.continuation = false;
+            return;
+        }
+
+        if index > 0 && self.space(index) {
+            self.out.push(' ');
+        }
+    }
+
+    // whether a space goes in front of a token on the same line
+    fn space(&self, index: usize) -> bool {
+        if self.annotations[index] || self.maps[index] && self.tokens[index].token_type == RIGHT_BRACE {
+            return false;
+        }
+        if index > 0 && self.maps[index - 1] && self.previous(index).token_type == LEFT_BRACE {
+            return false;
+        }
+        let before = index.checked_sub(2).map(|before| &self.tokens[before]);
+        space_between(before, self.previous(index), &self.tokens[index])
+    }
+
+    // line breaks and indentation that follow a token
+    fn after(&mut self, index: usize) {
+        let token = &self.tokens[index];
+        let next = self.next(index);
+
+        match token.token_type {
+            LEFT_BRACE | RIGHT_BRACE if self.maps[index] => {}
+            LEFT_BRACE if next.token_type != RIGHT_BRACE => {
+                self.indent += 1;
+                self.newline();
+            }
+            LEFT_BRACE => self.indent += 1,
+            RIGHT_BRACE if !matches!(next.token_type, ELSE | CATCH | FINALLY | SEMICOLON | RIGHT_PAREN | COMMA) => {
+                self.newline()
+            }
+            SEMICOLON if self.parens.is_empty() && next.token_type != COMMA => self.newline(),
+            // the comma after a match arm
+            COMMA if self.parens.is_empty() && self.ends_statement(index - 1) => self.newline(),
+            LEFT_PAREN => {
+                let wrap = index > 0 && ends_operand(self.previous(index)) && self.too_wide(index);
+                self.parens.push(wrap);
+                if wrap {
+                    self.indent += 1;
+                    self.newline();
+                }
+            }
+            RIGHT_PAREN => {
+                self.parens.pop();
+            }
+            COMMA if self.parens.last() == Some(&true) => self.newline(),
+            _ => {}
+        }
+    }
+
+    // a `;` or the `}` of a block, a comma after one of these ends a match arm
+    fn ends_statement(&self, index: usize) -> bool {
+        match self.tokens[index].token_type {
+            SEMICOLON => true,
+            RIGHT_BRACE => !self.maps[index],
+            _ => false,
+        }
+    }
+
+    // whether the parenthesised list starting at `open` runs past MAX_WIDTH when
+    // written on one line, or holds a comment
+    fn too_wide(&self, open: usize) -> bool {
+        let mut width = self.column();
+        let mut depth = 0;
+
+        for index in open + 1..self.tokens.len() {
+            let token = &self.tokens[index];
+            if self.trivia[index].iter().any(|trivia| trivia.kind != TriviaKind::Whitespace) {
+                return true;
+            }
+
+            if self.space(index) {
+                width += 1;
+            }
+            width += token.lexeme.chars().count();
+
+            match token.token_type {
+                LEFT_PAREN => depth += 1,
+                RIGHT_PAREN if depth == 0 => break,
+                RIGHT_PAREN => depth -= 1,
+                EOF => break,
+                _ => {}
+            }
+        }
+
+        width > MAX_WIDTH
+    }
+
+    // the comments in front of a token and the blank lines between statements
+    fn comments(&mut self, index: usize) {
+        let mut lines = 0;
+        let mut after_comment = false;
+
+        for trivia in self.trivia[index].iter() {
+            if trivia.kind == TriviaKind::Whitespace {
+                lines += newlines(trivia);
+                continue;
+            }
+
+            if lines == 0 && !self.out.is_empty() {
+                // a comment after code on the same line, undo the line break
+                if self.out.ends_with('\n') {
+                    self.out.pop();
+                }
+                self.out.push(' ');
+            } else {
+                if !self.at_line_start() {
+                    self.continuation = !self.line_ended(index);
+                }
+                if lines > 1 {
+                    self.blank_line();
+                }
+                self.newline();
+                self.write_indent();
+            }
+
+            self.out.push_str(trivia.text.trim_end());
+            if trivia.kind == TriviaKind::LineComment {
+                self.out.push('\n');
+                self.continuation = !self.line_ended(index);
+            }
+            lines = 0;
+            after_comment = true;
+        }
+
+        // keep one blank line between statements
+        if lines > 1 && self.at_line_start() && !self.out.is_empty() {
+            self.blank_line();
+        }
+        // a block comment on a line of its own
+        if after_comment && lines > 0 && !self.at_line_start() {
+            self.out.push('\n');
+            self.continuation = !self.line_ended(index);
+        }
+    }
+
+    // whether the token before `index` ends a line anyway
+    fn line_ended(&self, index: usize) -> bool {
+        if index == 0 {
+            return true;
+        }
+
+        match self.previous(index).token_type {
+            SEMICOLON => self.parens.is_empty(),
+            LEFT_BRACE | RIGHT_BRACE => !self.maps[index - 1],
+            COMMA | LEFT_PAREN => self.parens.last() == Some(&true),
+            _ => false,
+        }
+    }
+}
@@ -1,49 +1,154 @@
+use crate::ast::Expr;
 use crate::environment::Environment;
 use crate::errors::{Result, Error};
+use crate::generator::Generator;
 use crate::interpreter::Interpreter;
 use crate::object::Object;
 use crate::statement::Stmt;
 use crate::tokens::Token;
+use std::fmt;
 use std::rc::Rc;
 use std::cell::RefCell;
 
 // We either need to split up the Object
 // and Object type
-// OR 
+// OR
 // implement some custom traits
 
 #[derive(Clone, Debug)]
 pub enum Function {
     User {
         name: Token,
-        params: Vec<Token>,
-        body: Vec<Stmt>,
-        closure: Rc<RefCell<Environment>>
+        params: Rc<Vec<Token>>,
+        // one per parameter
+        defaults: Rc<Vec<Option<Expr>>>,
+        rest: Option<Rc<Token>>,
+        body: Rc<Vec<Stmt>>,
+        closure: Rc<RefCell<Environment>>,
+        is_initializer: bool,
+        is_generator: bool,
     },
     Native {
-        body: Box<fn(Vec<Object>) -> Object>,
+        body: Box<fn(&mut Interpreter, Vec<Object>) -> Object>,
         arity: usize,
     },
+    // the `next` method of a generator, gives nil once it is done
+    Next(Rc<Generator>),
+    // the `done` method, whether the generator has finished. It tells the nil of
+    // `yield nil;` apart from the one `next` gives at the end.
+    Done(Rc<Generator>),
+}
+
+// how many arguments a call can have, there is no upper bound with a rest parameter
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Arity {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl Arity {
+    pub fn exactly(count: usize) -> Self {
+        Arity { min: count, max: Some(count) }
+    }
+
+    pub fn accepts(&self, count: usize) -> bool {
+        count >= self.min && self.max.is_none_or(|max| count <= max)
+    }
+
+    pub fn mismatch(&self, count: usize) -> String {
+        let noun = if matches!((self.min, self.max), (1, Some(1) | None)) { "argument" } else { "arguments" };
+        format!("Expected {} {} but got {}.", self, noun, count)
+    }
+
+    // whether a call fills every parameter without a default exactly once, `index_of`
+    // finds the parameter of a named argument. The names are bound before anything
+    // is counted, so that `f(1, a: 2)` is a clash and not one argument too many.
+    pub fn check<T>(
+        &self,
+        params: usize,
+        positional: usize,
+        named: &[(Token, T)],
+        index_of: impl Fn(&str) -> Option<usize>,
+    ) -> std::result::Result<(), String> {
+        let count = positional + named.len();
+        if named.is_empty() {
+            return if self.accepts(count) { Ok(()) } else { Err(self.mismatch(count)) };
+        }
+        if self.max.is_some_and(|max| positional > max) {
+            return Err(self.mismatch(count));
+        }
+
+        let mut filled: Vec<bool> = (0..params).map(|index| index < positional).collect();
+        for (name, _) in named {
+            let Some(index) = index_of(&name.lexeme) else {
+                return Err(format!("No parameter named '{}'.", name.lexeme));
+            };
+            if filled[index] {
+                return Err(format!("Parameter '{}' got more than one argument.", name.lexeme));
+            }
+            filled[index] = true;
+        }
+
+        // the parameters without a default come first
+        if filled[..self.min].contains(&false) {
+            return Err(self.mismatch(count));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{}", max),
+            Some(max) => write!(f, "{} to {}", self.min, max),
+            None => write!(f, "at least {}", self.min),
+        }
+    }
 }
 
 impl Function {
-    pub fn call(&self, 
-        interpreter: &mut Interpreter, 
+    pub fn call(&self,
+        interpreter: &mut Interpreter,
         arguments: Vec<Object>) -> Result<Object> {
+        self.check_arguments::<Object>(arguments.len(), &[])?;
+        self.call_with(interpreter, arguments, Vec::new())
+    }
+
+    // a call with `name: value` arguments after the positional ones, they have been
+    // checked with `check_arguments`
+    pub fn call_with(&self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Object>,
+        named: Vec<(Token, Object)>) -> Result<Object> {
         let res = match self {
-            Function::Native { body, ..}  => Ok(body(arguments)),
-            Function::User { params, body, closure , ..} => {
+            Function::Native { body, ..}  => Ok(body(interpreter, arguments)),
+            Function::Next(generator) => Ok(generator.resume(interpreter)?.unwrap_or(Object::None)),
+            Function::Done(generator) => Ok(Object::Boolean(generator.is_done())),
+            Function::User { name, body, closure, is_initializer, is_generator, .. } => {
 
-                let environment = Rc::new(RefCell::new(Environment::new(&closure)));
-                for (param, arg) in params.iter().zip(arguments.iter()){
-                    environment.borrow_mut().define(param.lexeme.clone(), arg.clone());
-                }
+                let environment = interpreter.heap.allocate_environment(Environment::new(closure));
 
-                match interpreter.execute_block(body, environment) {
-                    Err(Error::Return{value}) => Ok(value),
-                    Err(other) => Err(other),
-                    Ok(..) => Ok(Object::None)
-                }
+                // globals are those of the module the function was declared in, the
+                // defaults see them too
+                let globals = std::mem::replace(&mut interpreter.globals, Environment::root(closure));
+                let result = self.bind_arguments(interpreter, &environment, arguments, named).and_then(|()| {
+                    // the body does not run until the first `next()`
+                    if *is_generator {
+                        let generator = Generator::new(name.lexeme.clone(), body, environment, Environment::root(closure));
+                        return Ok(Object::Generator(interpreter.heap.allocate_generator(generator)));
+                    }
+
+                    match interpreter.execute_block(body, environment) {
+                        // an initializer always hands back the instance, even on an early `return;`
+                        Err(Error::Return{..}) | Ok(..) if *is_initializer => Ok(self.this()),
+                        Err(Error::Return{value}) => Ok(value),
+                        Err(other) => Err(other),
+                        Ok(..) => Ok(Object::None)
+                    }
+                });
+                interpreter.globals = globals;
+                result
             }
         };
 
@@ -52,10 +157,111 @@ impl Function {
         res
     }
 
-    pub fn arity(&self) -> usize {
+    pub fn check_arguments<T>(&self, positional: usize, named: &[(Token, T)]) -> Result<()> {
+        let checked = match self {
+            Function::User { params, .. } => self.arity().check(params.len(), positional, named, |name| {
+                params.iter().position(|param| param.lexeme == name)
+            }),
+            _ if !named.is_empty() => Err(format!("{} doesn't take named arguments.", self)),
+            _ => self.arity().check(0, positional, named, |_| None),
+        };
+        checked.map_err(Error::Runtime)
+    }
+
+    // defines the parameters in the environment of a call
+    fn bind_arguments(&self,
+        interpreter: &mut Interpreter,
+        environment: &Rc<RefCell<Environment>>,
+        arguments: Vec<Object>,
+        named: Vec<(Token, Object)>) -> Result<()> {
+        let Function::User { params, defaults, rest, .. } = self else {
+            return Ok(());
+        };
+
+        let mut values: Vec<Option<Object>> = vec![None; params.len()];
+        let mut arguments = arguments.into_iter();
+        for (value, argument) in values.iter_mut().zip(&mut arguments) {
+            *value = Some(argument);
+        }
+        let extra: Vec<Object> = arguments.collect();
+
+        for (param, argument) in named {
+            if let Some(index) = params.iter().position(|p| p.lexeme == param.lexeme) {
+                values[index] = Some(argument);
+            }
+        }
+
+        // a default is evaluated after the parameters before it are defined
+        for ((param, default), value) in params.iter().zip(defaults.iter()).zip(values) {
+            let value = match (value, default) {
+                (Some(value), _) => value,
+                (None, Some(default)) => interpreter.evaluate_in(default, Rc::clone(environment))?,
+                // the check leaves no parameter without a default out
+                (None, None) => Object::None,
+            };
+            environment.borrow_mut().define(param.lexeme.clone(), value);
+        }
+
+        if let Some(rest) = rest {
+            let extra = Object::List(interpreter.heap.allocate_list(extra));
+            environment.borrow_mut().define(rest.lexeme.clone(), extra);
+        }
+        Ok(())
+    }
+
+    pub fn arity(&self) -> Arity {
+        match self {
+            Function::Native { arity, ..} => Arity::exactly(*arity),
+            Function::User { params, defaults, rest, ..} => Arity {
+                min: defaults.iter().filter(|default| default.is_none()).count(),
+                max: rest.is_none().then_some(params.len()),
+            },
+            Function::Next(_) | Function::Done(_) => Arity::exactly(0),
+        }
+    }
+
+    // wraps the method in a new environment where `this` refers to the instance
+    pub fn bind(&self, interpreter: &mut Interpreter, instance: Object) -> Function {
+        match self {
+            Function::User { name, params, defaults, rest, body, closure, is_initializer, is_generator } => {
+                let environment = interpreter.heap.allocate_environment(Environment::new(closure));
+                environment.borrow_mut().define("this".to_string(), instance);
+
+                Function::User {
+                    name: name.clone(),
+                    params: Rc::clone(params),
+                    defaults: Rc::clone(defaults),
+                    rest: rest.clone(),
+                    body: Rc::clone(body),
+                    closure: environment,
+                    is_initializer: *is_initializer,
+                    is_generator: *is_generator,
+                }
+            }
+            Function::Native { .. } | Function::Next(_) | Function::Done(_) => self.clone(),
+        }
+    }
+
+    fn this(&self) -> Object {
+        match self {
+            Function::User { closure, .. } => closure
+                .borrow()
+                .values
+                .get("this")
+                .cloned()
+                .unwrap_or(Object::None),
+            Function::Native { .. } | Function::Next(_) | Function::Done(_) => Object::None,
+        }
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Function::Native { arity, ..} => *arity,
-            Function::User { params, ..} => params.len(),
+            Function::User { name, .. } => write!(f, "<fn {}>", name.lexeme),
+            Function::Native { .. } => write!(f, "<native fn>"),
+            Function::Next(_) => write!(f, "<fn next>"),
+            Function::Done(_) => write!(f, "<fn done>"),
         }
     }
 }
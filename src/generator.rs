@@ -0,0 +1,378 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::ast::Expr;
+use crate::environment::Environment;
+use crate::errors::{Error, Result};
+use crate::interpreter::Interpreter;
+use crate::iterator::Iter;
+use crate::object::Object;
+use crate::statement::{Pattern, Stmt};
+use crate::tokens::Token;
+
+// The interpreter runs statements by recursing on the Rust stack, which can't be put
+// on hold in the middle of a `yield`. So the body of a `fun*` is compiled into a flat
+// list of steps: where the generator is becomes a program counter and the blocks it
+// is in a stack of environments, both kept here between calls to `next()`.
+//
+// Only statements with a `yield` somewhere inside are taken apart, everything else
+// is handed to the interpreter as a whole. The resolver keeps `yield` out of `try`.
+//
+// Generators are allocated on the heap, which traces the environments and iterators
+// they hold on to.
+
+// a break target that is not known until the end of its loop is compiled
+const PENDING: usize = usize::MAX;
+
+#[derive(Debug, Clone, Copy)]
+struct Exit {
+    target: usize,
+    // how many block scopes are open at the loop
+    depth: usize,
+    // and how many for-in iterators
+    iterators: usize,
+}
+
+#[derive(Debug)]
+enum Step {
+    // a statement without a yield in it, a `break` coming out of it goes to `exit`
+    Execute { statement: Stmt, exit: Option<Exit> },
+    Yield(Expr),
+    Jump(usize),
+    JumpUnless { condition: Expr, target: usize },
+    // starts a for-in loop over the value
+    Iterate(Expr),
+    // binds the next value of the innermost iterator in a new scope, or drops the
+    // iterator and jumps to `target` once it runs out
+    Next { name: Token, target: usize },
+    // runs the arm that matches the value in a new scope for what its pattern bound,
+    // `targets` are where the arms start
+    Match {
+        keyword: Token,
+        value: Expr,
+        arms: Vec<(Pattern, Option<Expr>)>,
+        targets: Vec<usize>,
+    },
+    EnterScope,
+    LeaveScope,
+}
+
+fn contains_yield(statement: &Stmt) -> bool {
+    match statement {
+        Stmt::Yield { .. } => true,
+        Stmt::Block { statements } => statements.iter().any(contains_yield),
+        Stmt::If { then_branch, else_branch, .. } => {
+            contains_yield(then_branch) || else_branch.as_deref().is_some_and(contains_yield)
+        }
+        Stmt::While { body, .. } | Stmt::ForIn { body, .. } => contains_yield(body),
+        Stmt::Match { arms, .. } => arms.iter().any(|arm| contains_yield(&arm.body)),
+        // a yield in a nested function belongs to that function
+        _ => false,
+    }
+}
+
+#[derive(Default)]
+struct Compiler {
+    steps: Vec<Step>,
+    depth: usize,
+    iterators: usize,
+    exit: Option<Exit>,
+}
+
+impl Compiler {
+    fn statement(&mut self, statement: &Stmt) {
+        if !contains_yield(statement) {
+            self.steps.push(Step::Execute {
+                statement: statement.clone(),
+                exit: self.exit,
+            });
+            return;
+        }
+
+        match statement {
+            Stmt::Yield { value, .. } => self.steps.push(Step::Yield(value.clone())),
+            Stmt::Block { statements } => {
+                self.steps.push(Step::EnterScope);
+                self.depth += 1;
+                statements.iter().for_each(|statement| self.statement(statement));
+                self.depth -= 1;
+                self.steps.push(Step::LeaveScope);
+            }
+            Stmt::If { condition, then_branch, else_branch } => {
+                let skip_then = self.jump_unless(condition);
+                self.statement(then_branch);
+
+                if let Some(else_branch) = else_branch {
+                    let skip_else = self.steps.len();
+                    self.steps.push(Step::Jump(PENDING));
+                    self.patch(skip_then);
+                    self.statement(else_branch);
+                    self.patch(skip_else);
+                } else {
+                    self.patch(skip_then);
+                }
+            }
+            Stmt::While { condition, body } => {
+                let start = self.steps.len();
+                let done = self.jump_unless(condition);
+
+                let enclosing = self.enter_loop();
+                self.statement(body);
+                self.exit = enclosing;
+
+                self.steps.push(Step::Jump(start));
+                self.patch(done);
+                self.patch_exits(start);
+            }
+            Stmt::Match { keyword, value, arms } => {
+                let start = self.steps.len();
+                self.steps.push(Step::Match {
+                    keyword: keyword.clone(),
+                    value: value.clone(),
+                    arms: arms.iter().map(|arm| (arm.pattern.clone(), arm.guard.clone())).collect(),
+                    targets: Vec::new(),
+                });
+
+                let mut starts = Vec::new();
+                let mut ends = Vec::new();
+                for arm in arms {
+                    starts.push(self.steps.len());
+                    self.depth += 1;
+                    self.statement(&arm.body);
+                    self.depth -= 1;
+                    self.steps.push(Step::LeaveScope);
+                    ends.push(self.steps.len());
+                    self.steps.push(Step::Jump(PENDING));
+                }
+
+                ends.into_iter().for_each(|end| self.patch(end));
+                if let Step::Match { targets, .. } = &mut self.steps[start] {
+                    *targets = starts;
+                }
+            }
+            Stmt::ForIn { name, iterable, body } => {
+                self.steps.push(Step::Iterate(iterable.clone()));
+                let start = self.steps.len();
+                self.steps.push(Step::Next {
+                    name: name.clone(),
+                    target: PENDING,
+                });
+
+                let enclosing = self.enter_loop();
+                self.iterators += 1;
+                self.depth += 1;
+                self.statement(body);
+                self.depth -= 1;
+                self.iterators -= 1;
+                self.exit = enclosing;
+                self.steps.push(Step::LeaveScope);
+
+                self.steps.push(Step::Jump(start));
+                self.patch(start);
+                self.patch_exits(start);
+            }
+            _ => self.steps.push(Step::Execute {
+                statement: statement.clone(),
+                exit: self.exit,
+            }),
+        }
+    }
+
+    // a break in the loop leaves the scopes and iterators it opened behind, gives
+    // back the exit of the enclosing loop
+    fn enter_loop(&mut self) -> Option<Exit> {
+        self.exit.replace(Exit {
+            target: PENDING,
+            depth: self.depth,
+            iterators: self.iterators,
+        })
+    }
+
+    // points the breaks of the loop starting at `start` past its end, the breaks of
+    // nested loops have their targets already
+    fn patch_exits(&mut self, start: usize) {
+        let end = self.steps.len();
+        for step in &mut self.steps[start..] {
+            if let Step::Execute { exit: Some(exit), .. } = step {
+                if exit.target == PENDING {
+                    exit.target = end;
+                }
+            }
+        }
+    }
+
+    fn jump_unless(&mut self, condition: &Expr) -> usize {
+        self.steps.push(Step::JumpUnless {
+            condition: condition.clone(),
+            target: PENDING,
+        });
+        self.steps.len() - 1
+    }
+
+    // points the jump at `index` to the next step
+    fn patch(&mut self, index: usize) {
+        let next = self.steps.len();
+        match &mut self.steps[index] {
+            Step::Jump(target) | Step::JumpUnless { target, .. } | Step::Next { target, .. } => *target = next,
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct State {
+    next: usize,
+    pub(crate) scopes: Vec<Rc<RefCell<Environment>>>,
+    pub(crate) iterators: Vec<Iter>,
+    done: bool,
+}
+
+#[derive(Debug)]
+pub struct Generator {
+    name: String,
+    steps: Vec<Step>,
+    // the parameters, the body runs in here
+    pub(crate) environment: Rc<RefCell<Environment>>,
+    // the globals of the module the function was declared in
+    pub(crate) globals: Rc<RefCell<Environment>>,
+    pub(crate) state: RefCell<State>,
+}
+
+impl Generator {
+    pub fn new(
+        name: String,
+        body: &[Stmt],
+        environment: Rc<RefCell<Environment>>,
+        globals: Rc<RefCell<Environment>>,
+    ) -> Self {
+        let mut compiler = Compiler::default();
+        body.iter().for_each(|statement| compiler.statement(statement));
+
+        Generator {
+            name,
+            steps: compiler.steps,
+            environment,
+            globals,
+            state: RefCell::new(State::default()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // runs up to the next `yield`, None once the body has finished
+    pub fn resume(&self, interpreter: &mut Interpreter) -> Result<Option<Object>> {
+        let Ok(mut state) = self.state.try_borrow_mut() else {
+            return Err(Error::Runtime(format!("Generator '{}' is already running.", self.name)));
+        };
+        if state.done {
+            return Ok(None);
+        }
+
+        let globals = std::mem::replace(&mut interpreter.globals, Rc::clone(&self.globals));
+        let result = self.run(interpreter, &mut state);
+        interpreter.globals = globals;
+
+        // an error finishes the generator as well
+        if !matches!(result, Ok(Some(_))) {
+            state.finish();
+        }
+        result
+    }
+
+    // whether the body has run to its end, or stopped on an error
+    pub fn is_done(&self) -> bool {
+        // only a running generator is borrowed, and that one is not done
+        self.state.try_borrow().is_ok_and(|state| state.done)
+    }
+
+    // ends the generator and lets go of what it holds on to
+    pub(crate) fn finish(&self) {
+        self.state.borrow_mut().finish();
+    }
+
+    fn run(&self, interpreter: &mut Interpreter, state: &mut State) -> Result<Option<Object>> {
+        while let Some(step) = self.steps.get(state.next) {
+            let environment = Rc::clone(state.scopes.last().unwrap_or(&self.environment));
+            state.next += 1;
+
+            match step {
+                Step::Execute { statement, exit } => {
+                    let result = interpreter.execute_block(std::slice::from_ref(statement), environment);
+                    match (result, exit) {
+                        (Err(Error::Break), Some(exit)) => {
+                            state.next = exit.target;
+                            state.scopes.truncate(exit.depth);
+                            state.iterators.truncate(exit.iterators);
+                        }
+                        (Err(Error::Return { .. }), _) => return Ok(None),
+                        (result, _) => result?,
+                    }
+                }
+                Step::Yield(value) => return interpreter.evaluate_in(value, environment).map(Some),
+                Step::Jump(target) => state.next = *target,
+                Step::JumpUnless { condition, target } => {
+                    if !interpreter.evaluate_in(condition, environment)?.is_truthy() {
+                        state.next = *target;
+                    }
+                }
+                Step::Iterate(iterable) => {
+                    let iterable = interpreter.evaluate_in(iterable, environment)?;
+                    state.iterators.push(Iter::new(interpreter, iterable)?);
+                }
+                Step::Next { name, target } => {
+                    let value = match state.iterators.last_mut() {
+                        Some(iterator) => iterator.next(interpreter)?,
+                        None => None,
+                    };
+                    match value {
+                        Some(value) => {
+                            let scope = interpreter
+                                .heap
+                                .allocate_environment(Environment::new(&environment));
+                            scope.borrow_mut().define(name.lexeme.clone(), value);
+                            state.scopes.push(scope);
+                        }
+                        None => {
+                            state.iterators.pop();
+                            state.next = *target;
+                        }
+                    }
+                }
+                Step::Match { keyword, value, arms, targets } => {
+                    let value = interpreter.evaluate_in(value, Rc::clone(&environment))?;
+                    let patterns = arms.iter().map(|(pattern, guard)| (pattern, guard.as_ref()));
+                    let (index, scope) = interpreter.select_arm(keyword, &value, patterns, &environment)?;
+                    state.scopes.push(scope);
+                    state.next = targets[index];
+                }
+                Step::EnterScope => {
+                    let scope = interpreter
+                        .heap
+                        .allocate_environment(Environment::new(&environment));
+                    state.scopes.push(scope);
+                }
+                Step::LeaveScope => {
+                    state.scopes.pop();
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl State {
+    fn finish(&mut self) {
+        self.done = true;
+        self.scopes.clear();
+        self.iterators.clear();
+    }
+}
+
+impl fmt::Display for Generator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<generator {}>", self.name)
+    }
+}
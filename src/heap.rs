@@ -0,0 +1,422 @@
+use crate::environment::Environment;
+use crate::function::Function;
+use crate::generator::Generator;
+use crate::iterator::Iter;
+use crate::object::Object;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+// Environments, instances, classes, lists, maps and generators are reference counted,
+// which leaks as soon as they point at each other (a local function stored in the
+// environment it closes over, two instances holding each other in fields, a list
+// holding a closure over the list, ...). The heap keeps a weak handle to
+// every allocation and periodically runs a synchronous cycle collection over them:
+//
+//  1. count how many strong references each object receives from other heap objects
+//  2. anything with more strong references than that is held from outside the heap
+//     (the interpreter, a Rust stack frame) and is a root
+//  3. mark everything reachable from the roots and clear the rest, which breaks
+//     the cycles and lets `Rc` free them
+//
+// Because roots are derived from the reference counts it is safe to collect at any
+// allocation, not only at statement boundaries.
+
+const INITIAL_THRESHOLD: usize = 1024;
+
+type Fields = RefCell<HashMap<String, Object>>;
+type Methods = RefCell<HashMap<String, Function>>;
+type List = RefCell<Vec<Object>>;
+type Map = RefCell<Vec<(Object, Object)>>;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HeapStats {
+    pub allocated: usize,
+    pub collections: usize,
+    pub freed: usize,
+    // largest number of objects that survived a collection
+    pub peak_live: usize,
+    pub live_environments: usize,
+    pub live_instances: usize,
+    pub live_classes: usize,
+    pub live_lists: usize,
+    pub live_maps: usize,
+    pub live_generators: usize,
+}
+
+impl HeapStats {
+    pub fn live(&self) -> usize {
+        self.live_environments
+            + self.live_instances
+            + self.live_classes
+            + self.live_lists
+            + self.live_maps
+            + self.live_generators
+    }
+}
+
+impl fmt::Display for HeapStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "allocated: {}, collections: {}, freed: {}, peak: {}, live: {} ({} environments, {} instances, {} classes, {} lists, {} maps, {} generators)",
+            self.allocated,
+            self.collections,
+            self.freed,
+            self.peak_live,
+            self.live(),
+            self.live_environments,
+            self.live_instances,
+            self.live_classes,
+            self.live_lists,
+            self.live_maps,
+            self.live_generators
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct Heap {
+    environments: Vec<Weak<RefCell<Environment>>>,
+    instances: Vec<Weak<Fields>>,
+    classes: Vec<Weak<Methods>>,
+    lists: Vec<Weak<List>>,
+    maps: Vec<Weak<Map>>,
+    generators: Vec<Weak<Generator>>,
+
+    since_last_gc: usize,
+    next_gc: usize,
+
+    // collect on every single allocation, to shake out rooting bugs
+    pub stress: bool,
+    // report every collection on stderr
+    pub trace: bool,
+
+    stats: HeapStats,
+}
+
+impl Default for Heap {
+    fn default() -> Self {
+        Heap {
+            environments: Vec::new(),
+            instances: Vec::new(),
+            classes: Vec::new(),
+            lists: Vec::new(),
+            maps: Vec::new(),
+            generators: Vec::new(),
+            since_last_gc: 0,
+            next_gc: INITIAL_THRESHOLD,
+            stress: false,
+            trace: false,
+            stats: HeapStats::default(),
+        }
+    }
+}
+
+enum Node {
+    Environment(Rc<RefCell<Environment>>),
+    Instance(Rc<Fields>),
+    Class(Rc<Methods>),
+    List(Rc<List>),
+    Map(Rc<Map>),
+    Generator(Rc<Generator>),
+}
+
+fn address<T>(rc: &Rc<T>) -> usize {
+    Rc::as_ptr(rc) as *const () as usize
+}
+
+fn trace_function(function: &Function, children: &mut Vec<usize>) {
+    match function {
+        Function::User { closure, .. } => children.push(address(closure)),
+        Function::Next(generator) | Function::Done(generator) => children.push(address(generator)),
+        Function::Native { .. } => {}
+    }
+}
+
+fn trace_object(object: &Object, children: &mut Vec<usize>) {
+    match object {
+        Object::Callable(function) => trace_function(function, children),
+        Object::Class(class) => children.push(address(&class.methods)),
+        Object::Instance(instance) => {
+            children.push(address(&instance.fields));
+            children.push(address(&instance.class.methods));
+        }
+        Object::List(list) => children.push(address(list)),
+        Object::Map(map) => children.push(address(map)),
+        Object::Generator(generator) => children.push(address(generator)),
+        _ => {}
+    }
+}
+
+fn trace_iterator(iterator: &Iter, children: &mut Vec<usize>) {
+    match iterator {
+        Iter::List(list, _) => children.push(address(list)),
+        Iter::Values(values) => values
+            .as_slice()
+            .iter()
+            .for_each(|value| trace_object(value, children)),
+        Iter::Range { .. } => {}
+        Iter::Generator(generator) => children.push(address(generator)),
+        Iter::Next(next) => trace_function(next, children),
+    }
+}
+
+impl Node {
+    fn address(&self) -> usize {
+        match self {
+            Node::Environment(env) => address(env),
+            Node::Instance(fields) => address(fields),
+            Node::Class(methods) => address(methods),
+            Node::List(list) => address(list),
+            Node::Map(map) => address(map),
+            Node::Generator(generator) => address(generator),
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Node::Environment(env) => Rc::strong_count(env),
+            Node::Instance(fields) => Rc::strong_count(fields),
+            Node::Class(methods) => Rc::strong_count(methods),
+            Node::List(list) => Rc::strong_count(list),
+            Node::Map(map) => Rc::strong_count(map),
+            Node::Generator(generator) => Rc::strong_count(generator),
+        }
+    }
+
+    // None when the node is borrowed right now, in which case it has to be treated as a root
+    fn children(&self) -> Option<Vec<usize>> {
+        let mut children = Vec::new();
+        match self {
+            Node::Environment(env) => {
+                let env = env.try_borrow().ok()?;
+                if let Some(enclosing) = &env.enclosing {
+                    children.push(address(enclosing));
+                }
+                env.values
+                    .values()
+                    .for_each(|value| trace_object(value, &mut children));
+            }
+            Node::Instance(fields) => fields
+                .try_borrow()
+                .ok()?
+                .values()
+                .for_each(|value| trace_object(value, &mut children)),
+            Node::Class(methods) => methods
+                .try_borrow()
+                .ok()?
+                .values()
+                .for_each(|method| trace_function(method, &mut children)),
+            Node::List(list) => list
+                .try_borrow()
+                .ok()?
+                .iter()
+                .for_each(|element| trace_object(element, &mut children)),
+            Node::Map(map) => map.try_borrow().ok()?.iter().for_each(|(key, value)| {
+                trace_object(key, &mut children);
+                trace_object(value, &mut children);
+            }),
+            Node::Generator(generator) => {
+                let state = generator.state.try_borrow().ok()?;
+                children.push(address(&generator.environment));
+                children.push(address(&generator.globals));
+                children.extend(state.scopes.iter().map(address));
+                state
+                    .iterators
+                    .iter()
+                    .for_each(|iterator| trace_iterator(iterator, &mut children));
+            }
+        }
+        Some(children)
+    }
+
+    // drops every outgoing reference, the contents are handed back so that they are
+    // released after the collector lets go of the nodes themselves
+    fn clear(&self, garbage: &mut Vec<Object>) {
+        match self {
+            Node::Environment(env) => {
+                let mut env = env.borrow_mut();
+                env.enclosing = None;
+                garbage.extend(env.values.drain().map(|(_, value)| value));
+            }
+            Node::Instance(fields) => {
+                garbage.extend(fields.borrow_mut().drain().map(|(_, value)| value))
+            }
+            Node::Class(methods) => garbage.extend(
+                methods
+                    .borrow_mut()
+                    .drain()
+                    .map(|(_, method)| Object::Callable(method)),
+            ),
+            Node::List(list) => garbage.append(&mut list.borrow_mut()),
+            Node::Map(map) => garbage.extend(
+                map.borrow_mut()
+                    .drain(..)
+                    .flat_map(|(key, value)| [key, value]),
+            ),
+            // the scopes and iterators it drops are nodes, or only hold nodes, and
+            // those are still held by the collector
+            Node::Generator(generator) => generator.finish(),
+        }
+    }
+}
+
+impl Heap {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn allocate_environment(&mut self, environment: Environment) -> Rc<RefCell<Environment>> {
+        let environment = Rc::new(RefCell::new(environment));
+        self.environments.push(Rc::downgrade(&environment));
+        self.allocated();
+        environment
+    }
+
+    pub fn allocate_instance(&mut self, fields: HashMap<String, Object>) -> Rc<Fields> {
+        let fields = Rc::new(RefCell::new(fields));
+        self.instances.push(Rc::downgrade(&fields));
+        self.allocated();
+        fields
+    }
+
+    pub fn allocate_class(&mut self, methods: HashMap<String, Function>) -> Rc<Methods> {
+        let methods = Rc::new(RefCell::new(methods));
+        self.classes.push(Rc::downgrade(&methods));
+        self.allocated();
+        methods
+    }
+
+    pub fn allocate_list(&mut self, elements: Vec<Object>) -> Rc<List> {
+        let list = Rc::new(RefCell::new(elements));
+        self.lists.push(Rc::downgrade(&list));
+        self.allocated();
+        list
+    }
+
+    pub fn allocate_map(&mut self, entries: Vec<(Object, Object)>) -> Rc<Map> {
+        let map = Rc::new(RefCell::new(entries));
+        self.maps.push(Rc::downgrade(&map));
+        self.allocated();
+        map
+    }
+
+    pub fn allocate_generator(&mut self, generator: Generator) -> Rc<Generator> {
+        let generator = Rc::new(generator);
+        self.generators.push(Rc::downgrade(&generator));
+        self.allocated();
+        generator
+    }
+
+    fn allocated(&mut self) {
+        self.stats.allocated += 1;
+        self.since_last_gc += 1;
+
+        if self.stress || self.since_last_gc >= self.next_gc {
+            self.collect();
+        }
+    }
+
+    pub fn stats(&self) -> HeapStats {
+        let mut stats = self.stats.clone();
+        stats.live_environments = self.environments.iter().filter(|w| w.strong_count() > 0).count();
+        stats.live_instances = self.instances.iter().filter(|w| w.strong_count() > 0).count();
+        stats.live_classes = self.classes.iter().filter(|w| w.strong_count() > 0).count();
+        stats.live_lists = self.lists.iter().filter(|w| w.strong_count() > 0).count();
+        stats.live_maps = self.maps.iter().filter(|w| w.strong_count() > 0).count();
+        stats.live_generators = self.generators.iter().filter(|w| w.strong_count() > 0).count();
+        stats
+    }
+
+    // runs a full collection and returns the number of objects that were freed
+    pub fn collect(&mut self) -> usize {
+        let before = self.live_handles();
+
+        let nodes: Vec<Node> = self
+            .environments
+            .iter()
+            .filter_map(|w| w.upgrade().map(Node::Environment))
+            .chain(self.instances.iter().filter_map(|w| w.upgrade().map(Node::Instance)))
+            .chain(self.classes.iter().filter_map(|w| w.upgrade().map(Node::Class)))
+            .chain(self.lists.iter().filter_map(|w| w.upgrade().map(Node::List)))
+            .chain(self.maps.iter().filter_map(|w| w.upgrade().map(Node::Map)))
+            .chain(self.generators.iter().filter_map(|w| w.upgrade().map(Node::Generator)))
+            .collect();
+
+        let index: HashMap<usize, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node.address(), i))
+            .collect();
+
+        let mut internal = vec![0; nodes.len()];
+        let mut edges = vec![Vec::new(); nodes.len()];
+        let mut roots = Vec::new();
+
+        for (i, node) in nodes.iter().enumerate() {
+            match node.children() {
+                Some(children) => {
+                    for child in children {
+                        if let Some(&j) = index.get(&child) {
+                            internal[j] += 1;
+                            edges[i].push(j);
+                        }
+                    }
+                }
+                None => roots.push(i),
+            }
+        }
+
+        // one of the strong references is the upgraded handle held in `nodes`
+        roots.extend((0..nodes.len()).filter(|&i| nodes[i].strong_count() - 1 > internal[i]));
+
+        let mut marked = vec![false; nodes.len()];
+        while let Some(i) = roots.pop() {
+            if !marked[i] {
+                marked[i] = true;
+                roots.extend(edges[i].iter().filter(|&&j| !marked[j]));
+            }
+        }
+
+        let mut garbage = Vec::new();
+        for (node, _) in nodes.iter().zip(marked).filter(|(_, marked)| !marked) {
+            node.clear(&mut garbage);
+        }
+
+        drop(nodes);
+        drop(garbage);
+
+        self.environments.retain(|w| w.strong_count() > 0);
+        self.instances.retain(|w| w.strong_count() > 0);
+        self.classes.retain(|w| w.strong_count() > 0);
+        self.lists.retain(|w| w.strong_count() > 0);
+        self.maps.retain(|w| w.strong_count() > 0);
+        self.generators.retain(|w| w.strong_count() > 0);
+
+        let live = self.live_handles();
+        let freed = before - live;
+
+        self.stats.collections += 1;
+        self.stats.freed += freed;
+        self.stats.peak_live = self.stats.peak_live.max(live);
+        self.since_last_gc = 0;
+        self.next_gc = INITIAL_THRESHOLD.max(live * 2);
+
+        if self.trace {
+            eprintln!("[gc] freed {} objects, {}", freed, self.stats());
+        }
+
+        freed
+    }
+
+    fn live_handles(&self) -> usize {
+        self.environments.iter().filter(|w| w.strong_count() > 0).count()
+            + self.instances.iter().filter(|w| w.strong_count() > 0).count()
+            + self.classes.iter().filter(|w| w.strong_count() > 0).count()
+            + self.lists.iter().filter(|w| w.strong_count() > 0).count()
+            + self.maps.iter().filter(|w| w.strong_count() > 0).count()
+            + self.generators.iter().filter(|w| w.strong_count() > 0).count()
+    }
+}
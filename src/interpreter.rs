@@ -1,16 +1,25 @@
 #![allow(dead_code, unused, non_camel_case_types, non_snake_case)]
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::default;
-use std::f32::MIN;
+use std::fmt;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::ast::{Expr, Visitor as ExprVisitor};
-use crate::class::Class;
+use crate::class::{Class, Instance};
 use crate::environment::{self, Environment};
 use crate::errors::{Error, Result};
 use crate::function::Function;
+use crate::heap::Heap;
+use crate::iterator::Iter;
+use crate::module::{self, Module};
 use crate::object::Object;
-use crate::statement::{self, Stmt, Visitor as StmtVisitor};
+use crate::parser::Parser;
+use crate::resolver::Resolver;
+use crate::scanner::Scanner;
+use crate::statement::{self, Pattern, Stmt, Visitor as StmtVisitor};
 use crate::tokens::{
     Literal, Token,
     TokenType::{self, *},
@@ -20,36 +29,336 @@ use std::rc::Rc;
 
 use Object::{Boolean, None as Null, Number, String};
 
-fn clock_fun(args: Vec<Object>) -> Object {
-    Object::Number(10.0)
+fn clock_fun(interpreter: &mut Interpreter, args: Vec<Object>) -> Object {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    Object::Number(now.as_secs_f64())
+}
+
+fn gc_fun(interpreter: &mut Interpreter, args: Vec<Object>) -> Object {
+    Object::Number(interpreter.heap.collect() as f64)
+}
+
+// written in Lox itself, every global environment gets the classes declared here
+const PRELUDE: &str = "
+class Error {
+  init(message) {
+    this.message = message;
+  }
+}
+";
+
+// the number of characters, elements or entries, nil for anything else
+fn len_fun(interpreter: &mut Interpreter, args: Vec<Object>) -> Object {
+    match &args[0] {
+        String(string) => Number(string.chars().count() as f64),
+        Object::List(list) => Number(list.borrow().len() as f64),
+        Object::Map(map) => Number(map.borrow().len() as f64),
+        _ => Null,
+    }
+}
+
+fn define_natives(globals: &Rc<RefCell<Environment>>) {
+    let clock = Object::Callable(Function::Native {
+        body: Box::new(clock_fun),
+        arity: 0,
+    });
+    let gc = Object::Callable(Function::Native {
+        body: Box::new(gc_fun),
+        arity: 0,
+    });
+
+    globals.borrow_mut().define("clock".to_string(), clock);
+    globals.borrow_mut().define("gc".to_string(), gc);
+    globals.borrow_mut().define(
+        "len".to_string(),
+        Object::Callable(Function::Native {
+            body: Box::new(len_fun),
+            arity: 1,
+        }),
+    );
+}
+
+// the operators are kept free of the interpreter state so that the optimizer
+// folds constants with exactly the same semantics
+pub fn binary_operation(operator: &Token, left: &Object, right: &Object) -> Result<Object> {
+    match (&operator.token_type, left, right) {
+        (GREATER, Number(left), Number(right)) => Ok(Boolean(left > right)),
+        (GREATER_EQUAL, Number(left), Number(right)) => Ok(Boolean(left >= right)),
+        (LESS, Number(left), Number(right)) => Ok(Boolean(left < right)),
+        (LESS_EQUAL, Number(left), Number(right)) => Ok(Boolean(left <= right)),
+
+        (BANG_EQUAL, left, right) => Ok(Boolean(left != right)),
+        (EQUAL_EQUAL, left, right) => Ok(Boolean(left == right)),
+
+        (MINUS, Number(left), Number(right)) => Ok(Number(left - right)),
+        (SLASH, Number(left), Number(right)) => Ok(Number(left / right)),
+        (STAR, Number(left), Number(right)) => Ok(Number(left * right)),
+        (PLUS, Number(left), Number(right)) => Ok(Number(left + right)),
+        (PLUS, String(left), String(right)) => Ok(String(left.clone() + right)),
+        (DOT_DOT, Number(start), Number(end)) => Ok(Object::Range(*start, *end)),
+        (DOT_DOT, _, _) => Err(Error::Runtime("Range bounds must be numbers.".to_string())),
+
+        _ => {
+            // println!("off the rails!");
+            Err(Error::Runtime(format!(
+                "Error evaluating {} {} {}",
+                left, operator, right
+            )))
+        }
+    }
+}
+
+// a whole number that can index a list of this length
+fn list_index(index: &Object, len: usize) -> Result<usize> {
+    match index {
+        Number(index) if index.fract() != 0.0 => Err(Error::Runtime("Index must be a whole number.".to_string())),
+        Number(index) if *index >= 0.0 && (*index as usize) < len => Ok(*index as usize),
+        Number(_) => Err(Error::Runtime("Index out of range.".to_string())),
+        _ => Err(Error::Runtime("Index must be a number.".to_string())),
+    }
+}
+
+// `object[index]`, maps give nil for keys they don't have
+fn index_get(object: &Object, index: &Object) -> Result<Object> {
+    match object {
+        Object::List(list) => {
+            let list = list.borrow();
+            Ok(list[list_index(index, list.len())?].clone())
+        }
+        Object::Map(map) => Ok(map
+            .borrow()
+            .iter()
+            .find(|(key, _)| key == index)
+            .map_or(Null, |(_, value)| value.clone())),
+        String(string) => {
+            let chars: Vec<char> = string.chars().collect();
+            Ok(String(chars[list_index(index, chars.len())?].to_string()))
+        }
+        _ => Err(Error::Runtime("Only lists, maps and strings can be indexed.".to_string())),
+    }
+}
+
+fn index_set(object: &Object, index: Object, value: Object) -> Result<()> {
+    match object {
+        Object::List(list) => {
+            let mut list = list.borrow_mut();
+            let index = list_index(&index, list.len())?;
+            list[index] = value;
+        }
+        Object::Map(map) => {
+            let mut map = map.borrow_mut();
+            match map.iter_mut().find(|(key, _)| *key == index) {
+                Some((_, entry)) => *entry = value,
+                None => map.push((index, value)),
+            }
+        }
+        _ => return Err(Error::Runtime("Only lists and maps can be assigned to by index.".to_string())),
+    }
+    Ok(())
+}
+
+pub fn unary_operation(operator: &Token, right: &Object) -> Result<Object> {
+    match (&operator.token_type, right) {
+        (MINUS, Number(val)) => Ok(Number(-val)),
+        (MINUS, _) => Err(Error::Runtime(format!(
+            "Tried to negate invalid operand {:?}",
+            right
+        ))),
+        (BANG, _) => Ok(Boolean(!right.is_truthy())),
+        _ => Err(Error::Runtime(format!(
+            "Invalid unary expr: {:?} {:?}",
+            operator, right,
+        ))),
+    }
+}
+
+// where `print` statements end up
+#[derive(Default, Debug, Clone)]
+pub enum Output {
+    #[default]
+    Stdout,
+    Discard,
+    Buffer(Rc<RefCell<Vec<u8>>>),
+}
+
+impl Output {
+    pub fn print(&self, value: &dyn fmt::Display) {
+        match self {
+            Output::Stdout => println!("{}", value),
+            Output::Discard => {}
+            Output::Buffer(buffer) => {
+                let _ = writeln!(buffer.borrow_mut(), "{}", value);
+            }
+        }
+    }
 }
 
 #[derive(Default, Debug)]
 pub struct Interpreter {
+    // the globals of the module that is running
     pub globals: Rc<RefCell<Environment>>,
     environment: Rc<RefCell<Environment>>,
     locals: HashMap<Token, usize>,
+    // tokens are numbered across files so they stay distinct keys in `locals`
+    next_token: usize,
+    pub heap: Heap,
+    pub output: Output,
+
+    // the file that is running, imports are relative to it
+    pub current_file: Option<PathBuf>,
+    // where imports are looked for when they are not next to the importing file
+    pub search_path: Vec<PathBuf>,
+    pub(crate) modules: HashMap<PathBuf, Rc<Module>>,
+    // the modules that are being loaded, innermost last
+    pub(crate) loading: Vec<PathBuf>,
+    // the names the running module has exported so far
+    exports: HashSet<std::string::String>,
+
+    // the line of the last operation that could fail, for error objects
+    line: usize,
+    // the functions being called, with the line of each call, innermost last
+    frames: Vec<(std::string::String, usize)>,
+    // the class runtime errors are turned into, from the prelude
+    error_class: Option<Class>,
 }
 
 impl Interpreter {
     pub fn new() -> Self {
-        let globals: Rc<RefCell<Environment>> = Default::default();
+        let mut heap = Heap::new();
+        let globals = heap.allocate_environment(Environment::default());
+        define_natives(&globals);
         let environment = Rc::clone(&globals);
 
-        let clock = Object::Callable(Function::Native {
-            body: Box::new(clock_fun),
-            arity: 0,
-        });
-
-        globals.borrow_mut().define("clock".to_string(), clock);
-
-        Interpreter {
+        let mut interpreter = Interpreter {
             globals,
             environment,
             locals: HashMap::default(),
+            next_token: 0,
+            heap,
+            output: Output::default(),
+            current_file: None,
+            search_path: Vec::new(),
+            modules: HashMap::default(),
+            loading: Vec::new(),
+            exports: HashSet::default(),
+            line: 0,
+            frames: Vec::new(),
+            error_class: None,
+        };
+        interpreter.run_prelude();
+        interpreter
+    }
+
+    fn run_prelude(&mut self) {
+        let (mut tokens, _) = Scanner::new(PRELUDE.to_string()).scan_tokens();
+        self.number_tokens(&mut tokens);
+        let statements = Parser::new(tokens).parse().expect("the prelude parses");
+        Resolver::new(self)
+            .resolve_statements(&statements)
+            .expect("the prelude resolves");
+        self.interpret(&statements).expect("the prelude runs");
+
+        if let Some(Object::Class(class)) = self.globals.borrow().values.get("Error") {
+            self.error_class = Some(class.clone());
+        }
+    }
+
+    fn define_prelude(&self, globals: &Rc<RefCell<Environment>>) {
+        if let Some(class) = &self.error_class {
+            globals
+                .borrow_mut()
+                .define("Error".to_string(), Object::Class(class.clone()));
+        }
+    }
+
+    fn is_error(&self, instance: &Instance) -> bool {
+        self.error_class
+            .as_ref()
+            .is_some_and(|class| Rc::ptr_eq(&class.methods, &instance.class.methods))
+    }
+
+    // where the program is, innermost call first
+    fn stack_trace(&self) -> std::string::String {
+        let mut line = self.line;
+        let mut trace = Vec::new();
+        for (name, call_line) in self.frames.iter().rev() {
+            trace.push(format!("[line {}] in {}()", line, name));
+            line = *call_line;
+        }
+        trace.push(format!("[line {}] in script", line));
+        trace.join("\n")
+    }
+
+    // runtime errors become instances of `Error` so that they can be caught,
+    // this has to happen before the calls they came out of are unwound
+    fn exception(&mut self, err: Error) -> Error {
+        let Error::Runtime(message) = err else {
+            return err;
+        };
+        let Some(class) = self.error_class.clone() else {
+            return Error::Runtime(message);
+        };
+
+        let instance = Instance::new(self, class);
+        instance
+            .fields
+            .borrow_mut()
+            .insert("message".to_string(), String(message.clone()));
+        self.locate(&instance);
+        Error::Throw {
+            value: Box::new(Object::Instance(instance)),
+            message,
         }
     }
-    pub fn interpret(&mut self, statements: &Vec<Stmt>) -> Result<()> {
+
+    // the line and stack trace of an error, unless it already has them
+    fn locate(&self, instance: &Instance) {
+        let mut fields = instance.fields.borrow_mut();
+        fields
+            .entry("line".to_string())
+            .or_insert(Number(self.line as f64));
+        fields
+            .entry("stack".to_string())
+            .or_insert_with(|| String(self.stack_trace()));
+    }
+
+    // a fresh global environment for a module, with the native functions in it
+    pub(crate) fn new_globals(&mut self) -> Rc<RefCell<Environment>> {
+        let globals = self.heap.allocate_environment(Environment::default());
+        define_natives(&globals);
+        self.define_prelude(&globals);
+        globals
+    }
+
+    // gives the tokens of a newly scanned file numbers no other file has used
+    pub fn number_tokens(&mut self, tokens: &mut [Token]) {
+        for token in tokens.iter_mut() {
+            token.unique_count += self.next_token;
+        }
+        self.next_token += tokens.len();
+    }
+
+    // runs a module in its own globals, returns the names it exported
+    pub(crate) fn run_module(
+        &mut self,
+        statements: &[Stmt],
+        globals: Rc<RefCell<Environment>>,
+        file: PathBuf,
+    ) -> Result<HashSet<std::string::String>> {
+        let previous_globals = std::mem::replace(&mut self.globals, Rc::clone(&globals));
+        let previous_file = self.current_file.replace(file);
+        let previous_exports = std::mem::take(&mut self.exports);
+
+        let result = self.execute_block(statements, globals);
+
+        self.globals = previous_globals;
+        self.current_file = previous_file;
+        let exports = std::mem::replace(&mut self.exports, previous_exports);
+        result.map(|_| exports)
+    }
+    pub fn interpret(&mut self, statements: &[Stmt]) -> Result<()> {
         statements.iter().try_for_each(|statement| {
             // println!("Executing {:?}", statement);
             self.execute(statement)
@@ -61,11 +370,7 @@ impl Interpreter {
     }
 
     pub fn is_truthy(&self, literal: &Object) -> bool {
-        match *literal {
-            Null => false,
-            Boolean(false) => false,
-            _ => true,
-        }
+        literal.is_truthy()
     }
 
     pub fn execute(&mut self, statement: &Stmt) -> Result<()> {
@@ -79,20 +384,128 @@ impl Interpreter {
 
     pub fn execute_block(
         &mut self,
-        statements: &Vec<Stmt>,
+        statements: &[Stmt],
         environment: Rc<RefCell<Environment>>,
     ) -> Result<()> {
         let previous = Rc::clone(&self.environment);
         self.environment = environment;
 
         let result = statements.iter().try_for_each(|statement| {
-            let res = self.execute(statement);
-            res
+            
+            self.execute(statement)
         });
         self.environment = previous;
         result
     }
 
+    // the first arm of a `match` whose pattern matches the value and whose guard holds,
+    // along with a new environment in `enclosing` that has the names it bound
+    pub(crate) fn select_arm<'a>(
+        &mut self,
+        keyword: &Token,
+        value: &Object,
+        arms: impl IntoIterator<Item = (&'a Pattern, Option<&'a Expr>)>,
+        enclosing: &Rc<RefCell<Environment>>,
+    ) -> Result<(usize, Rc<RefCell<Environment>>)> {
+        for (index, (pattern, guard)) in arms.into_iter().enumerate() {
+            let environment = self.heap.allocate_environment(Environment::new(enclosing));
+            if !self.match_pattern(pattern, value, &environment)? {
+                continue;
+            }
+            if let Some(guard) = guard {
+                if !self.evaluate_in(guard, Rc::clone(&environment))?.is_truthy() {
+                    continue;
+                }
+            }
+            return Ok((index, environment));
+        }
+
+        self.line = keyword.line;
+        Err(Error::Runtime(format!("Non-exhaustive match, no arm matches {}.", value)))
+    }
+
+    fn match_pattern(&mut self, pattern: &Pattern, value: &Object, environment: &Rc<RefCell<Environment>>) -> Result<bool> {
+        match pattern {
+            Pattern::Literal { value: literal, .. } => Ok(Object::from(literal) == *value),
+            Pattern::Wildcard => Ok(true),
+            Pattern::Binding { name } => {
+                environment.borrow_mut().define(name.lexeme.clone(), value.clone());
+                Ok(true)
+            }
+            Pattern::Or { alternatives } => {
+                for alternative in alternatives {
+                    if self.match_pattern(alternative, value, environment)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            Pattern::Class { name, fields } => {
+                let class = self.evaluate_in(&Expr::Variable { name: name.clone() }, Rc::clone(environment))?;
+                self.line = name.line;
+                let Object::Class(class) = class else {
+                    return Err(Error::Runtime(format!("'{}' is not a class.", name.lexeme)));
+                };
+                let names = class.pattern_fields();
+                if names.len() != fields.len() {
+                    return Err(Error::Runtime(format!(
+                        "Pattern for '{}' needs {} fields but has {}.",
+                        name.lexeme,
+                        names.len(),
+                        fields.len()
+                    )));
+                }
+
+                let Object::Instance(instance) = value else {
+                    return Ok(false);
+                };
+                if !Rc::ptr_eq(&instance.class.methods, &class.methods) {
+                    return Ok(false);
+                }
+                for (name, field) in names.iter().zip(fields) {
+                    let Some(value) = instance.fields.borrow().get(name).cloned() else {
+                        return Ok(false);
+                    };
+                    if !self.match_pattern(field, &value, environment)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            Pattern::List { elements, rest } => {
+                let Object::List(list) = value else {
+                    return Ok(false);
+                };
+                // the patterns can run code, don't hold on to the borrow
+                let list = list.borrow().clone();
+                if list.len() < elements.len() || rest.is_none() && list.len() != elements.len() {
+                    return Ok(false);
+                }
+
+                for (element, pattern) in list.iter().zip(elements) {
+                    if !self.match_pattern(pattern, element, environment)? {
+                        return Ok(false);
+                    }
+                }
+                if let Some(rest) = rest {
+                    let rest_list = self.heap.allocate_list(list[elements.len()..].to_vec());
+                    environment
+                        .borrow_mut()
+                        .define(rest.lexeme.clone(), Object::List(rest_list));
+                }
+                Ok(true)
+            }
+        }
+    }
+
+    // evaluates an expression as if it appeared in the given environment
+    pub(crate) fn evaluate_in(&mut self, expr: &Expr, environment: Rc<RefCell<Environment>>) -> Result<Object> {
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let result = self.evaluate(expr);
+        self.environment = previous;
+        result
+    }
+
     fn lookup_variable(&self, name: &Token) -> Result<Object> {
         // println!("locals = {:?}", self.locals);
         if let Some(distance) = self.locals.get(name) {
@@ -110,76 +523,42 @@ impl ExprVisitor<Object> for Interpreter {
         let left = self.evaluate(left)?;
         let right = self.evaluate(right)?;
 
-        match (&operator.token_type, &left, &right) {
-            (GREATER, Number(left), Number(right)) => Ok(Boolean(left > right)),
-            (GREATER_EQUAL, Number(left), Number(right)) => Ok(Boolean(left >= right)),
-            (LESS, Number(left), Number(right)) => Ok(Boolean(left < right)),
-            (LESS_EQUAL, Number(left), Number(right)) => Ok(Boolean(left <= right)),
-
-            (BANG_EQUAL, left, right) => Ok(Boolean(left != right)),
-            (EQUAL_EQUAL, left, right) => Ok(Boolean(left == right)),
-
-            (MINUS, Number(left), Number(right)) => Ok(Number(left - right)),
-            (SLASH, Number(left), Number(right)) => Ok(Number(left / right)),
-            (STAR, Number(left), Number(right)) => Ok(Number(left * right)),
-            (PLUS, Number(left), Number(right)) => Ok(Number(left + right)),
-            (PLUS, String(left), String(right)) => Ok(String(left.clone() + right)),
-
-            _ => {
-                // println!("off the rails!");
-                Err(Error::Runtime(format!(
-                    "Error evaluating {} {} {}",
-                    left, operator, right
-                )))
-            }
-        }
+        self.line = operator.line;
+        binary_operation(operator, &left, &right)
     }
 
     fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) -> Result<Object> {
         let right = self.evaluate(right)?;
 
-        match (&operator.token_type, &right) {
-            (MINUS, Number(val)) => Ok(Number(-val)),
-            (MINUS, _) => Err(Error::Runtime(format!(
-                "Tried to negate invalid operand {:?}",
-                right
-            ))),
-            (BANG, _) => Ok(Boolean(self.is_truthy(&right))),
-            _ => Err(Error::Runtime(format!(
-                "Invalid unary expr: {:?} {:?}",
-                operator, right,
-            ))),
-        }
+        self.line = operator.line;
+        unary_operation(operator, &right)
     }
     fn visit_grouping_expr(&mut self, expr: &Expr) -> Result<Object> {
         self.evaluate(expr)
     }
 
     fn visit_literal_expr(&mut self, value: &Literal) -> Result<Object> {
-        match value {
-            Literal::Boolean(b) => Ok(Object::Boolean(*b)),
-            Literal::None => Ok(Object::None),
-            Literal::Number(n) => Ok(Object::Number(*n)),
-            Literal::String(s) => Ok(Object::String(s.clone())),
-        }
+        Ok(Object::from(value))
     }
 
     fn visit_variable_expr(&mut self, name: &Token) -> Result<Object> {
+        self.line = name.line;
         self.lookup_variable(name)
     }
 
     fn visit_variable_assignment_expr(&mut self, expr: &Expr) -> Result<Object> {
         if let Expr::Assign { name, value: expr } = expr {
             let value = self.evaluate(expr)?;
+            self.line = name.line;
             if let Some(distance) = self.locals.get(name) {
                 self.environment
                     .borrow_mut()
-                    .assign_at(distance.clone(), name, value)
+                    .assign_at(*distance, name, value)
             } else {
                 self.globals.borrow_mut().assign(name, value)
             }
         } else {
-            Err(Error::Runtime(format!("Something is very wrong!")))
+            Err(Error::Runtime("Something is very wrong!".to_string()))
         }
     }
 
@@ -188,6 +567,7 @@ impl ExprVisitor<Object> for Interpreter {
             callee,
             paren,
             arguments,
+            named,
         } = expr
         {
             let callee = self.evaluate(callee)?;
@@ -195,29 +575,37 @@ impl ExprVisitor<Object> for Interpreter {
                 .iter()
                 .map(|x| self.evaluate(x))
                 .collect::<Result<Vec<Object>>>()?;
+            let named = named
+                .iter()
+                .map(|(name, x)| Ok((name.clone(), self.evaluate(x)?)))
+                .collect::<Result<Vec<(Token, Object)>>>()?;
+            self.line = paren.line;
 
-            if let Object::Callable(func) = callee {
-                if arguments.len() != func.arity() {
-                    Err(Error::Runtime(format!(
-                        "{:?} Expected {} arguments but got {}.",
-                        paren,
-                        func.arity(),
-                        arguments.len()
-                    )))
-                } else {
-                    func.call(self, arguments)
-                }
-            } else if let Object::Class(class) = callee {
-                let instance = class.call(self, &arguments);
-                Ok(Object::Instance(instance))
-            } else {
-                Err(Error::Runtime(format!(
-                    "{:?} Call only call functions and classes.",
-                    paren
-                )))
+            match &callee {
+                Object::Callable(func) => func.check_arguments(arguments.len(), &named)?,
+                Object::Class(class) => class.check_arguments(arguments.len(), &named)?,
+                _ => return Err(Error::Runtime("Can only call functions and classes.".to_string())),
             }
+
+            let name = match &callee {
+                Object::Callable(Function::User { name, .. }) => name.lexeme.clone(),
+                Object::Class(class) => class.name.clone(),
+                Object::Callable(Function::Next(generator) | Function::Done(generator)) => {
+                    generator.name().to_string()
+                }
+                _ => "native fn".to_string(),
+            };
+            self.frames.push((name, paren.line));
+            let result = match callee {
+                Object::Callable(func) => func.call_with(self, arguments, named),
+                Object::Class(class) => class.call(self, arguments, named),
+                _ => Ok(Object::None),
+            };
+            let result = result.map_err(|err| self.exception(err));
+            self.frames.pop();
+            result
         } else {
-            Err(Error::Runtime(format!("Something is very wrong!")))
+            Err(Error::Runtime("Something is very wrong!".to_string()))
         }
     }
 
@@ -239,28 +627,185 @@ impl ExprVisitor<Object> for Interpreter {
                 return Ok(left);
             }
 
-            return self.evaluate(right);
+            self.evaluate(right)
         } else {
-            Err(Error::Runtime(format!(
-                "visit_logical_expr called for non Expr::Logical enum!"
-            )))
+            Err(Error::Runtime("visit_logical_expr called for non Expr::Logical enum!".to_string()))
         }
     }
 
-    fn visit_get_expr(&mut self, expr: &Expr) -> Result<Object> {
+    // only the branch that is taken gets evaluated
+    fn visit_conditional_expr(&mut self, expr: &Expr) -> Result<Object> {
+        if let Expr::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        } = expr
+        {
+            let condition = self.evaluate(condition)?;
+            if self.is_truthy(&condition) {
+                self.evaluate(then_branch)
+            } else {
+                self.evaluate(else_branch)
+            }
+        } else {
+            Err(Error::Runtime("visit_conditional_expr called for non Expr::Conditional enum!".to_string()))
+        }
+    }
 
-        println!("calling instance.get");
+    fn visit_update_expr(&mut self, expr: &Expr) -> Result<Object> {
+        if let Expr::Update {
+            target,
+            operator,
+            value,
+            postfix,
+        } = expr
+        {
+            // `+=` and `++` do a `+` and so on
+            self.line = operator.line;
+            let token_type = match operator.token_type {
+                PLUS_EQUAL | PLUS_PLUS => PLUS,
+                MINUS_EQUAL | MINUS_MINUS => MINUS,
+                STAR_EQUAL => STAR,
+                _ => SLASH,
+            };
+            let binary = Token {
+                token_type,
+                ..operator.clone()
+            };
+
+            let (old, new) = match target.as_ref() {
+                Expr::Variable { name } => {
+                    let old = self.lookup_variable(name)?;
+                    let new = binary_operation(&binary, &old, &self.evaluate(value)?)?;
+                    match self.locals.get(name) {
+                        Some(distance) => self.environment.borrow_mut().assign_at(*distance, name, new.clone())?,
+                        None => self.globals.borrow_mut().assign(name, new.clone())?,
+                    };
+                    (old, new)
+                }
+                Expr::Get { object, name } => {
+                    let Object::Instance(object) = self.evaluate(object)? else {
+                        return Err(Error::Runtime("Only instances have fields.".to_string()));
+                    };
+                    let old = object.get(self, name)?;
+                    let new = binary_operation(&binary, &old, &self.evaluate(value)?)?;
+                    object.set(name, new.clone());
+                    (old, new)
+                }
+                Expr::Index { object, index, .. } => {
+                    let object = self.evaluate(object)?;
+                    let index = self.evaluate(index)?;
+                    let old = index_get(&object, &index)?;
+                    let new = binary_operation(&binary, &old, &self.evaluate(value)?)?;
+                    index_set(&object, index, new.clone())?;
+                    (old, new)
+                }
+                _ => return Err(Error::Runtime("Invalid assignment target.".to_string())),
+            };
+
+            Ok(if *postfix { old } else { new })
+        } else {
+            Err(Error::Runtime("visit_update_expr called for non Expr::Update enum!".to_string()))
+        }
+    }
+
+    fn visit_get_expr(&mut self, expr: &Expr) -> Result<Object> {
         if let Expr::Get { object, name } = expr {
             let object = self.evaluate(object)?;
+            self.line = name.line;
+            match object {
+                Object::Instance(object) => object.get(self, name),
+                Object::Module(module) => module.get(name),
+                Object::Generator(generator) if name.lexeme == "next" => {
+                    Ok(Object::Callable(Function::Next(generator)))
+                }
+                Object::Generator(generator) if name.lexeme == "done" => {
+                    Ok(Object::Callable(Function::Done(generator)))
+                }
+                _ => Err(Error::Runtime("Only instances have properties.".to_string())),
+            }
+        } else {
+            Err(Error::Runtime("Something is very wrong!".to_string()))
+        }
+    }
+
+    fn visit_set_expr(&mut self, expr: &Expr) -> Result<Object> {
+        if let Expr::Set { object, name, value } = expr {
+            let object = self.evaluate(object)?;
+            self.line = name.line;
             if let Object::Instance(object) = object {
-                object.get(name)
+                let value = self.evaluate(value)?;
+                object.set(name, value.clone());
+                Ok(value)
             } else {
-                Err(Error::Runtime(format!("Only instances have properties.")))
+                Err(Error::Runtime("Only instances have fields.".to_string()))
+            }
+        } else {
+            Err(Error::Runtime("Something is very wrong!".to_string()))
+        }
+    }
+
+    fn visit_this_expr(&mut self, keyword: &Token) -> Result<Object> {
+        self.lookup_variable(keyword)
+    }
+
+    fn visit_list_expr(&mut self, expr: &Expr) -> Result<Object> {
+        if let Expr::List { elements, .. } = expr {
+            let elements = elements
+                .iter()
+                .map(|element| self.evaluate(element))
+                .collect::<Result<Vec<Object>>>()?;
+            Ok(Object::List(self.heap.allocate_list(elements)))
+        } else {
+            Err(Error::Runtime("Something is very wrong!".to_string()))
+        }
+    }
+
+    fn visit_map_expr(&mut self, expr: &Expr) -> Result<Object> {
+        if let Expr::Map { brace, entries } = expr {
+            let map = Object::Map(self.heap.allocate_map(Vec::new()));
+            for (key, value) in entries {
+                let key = self.evaluate(key)?;
+                let value = self.evaluate(value)?;
+                index_set(&map, key, value)?;
             }
+            Ok(map)
+        } else {
+            Err(Error::Runtime("Something is very wrong!".to_string()))
+        }
+    }
+
+    fn visit_index_expr(&mut self, expr: &Expr) -> Result<Object> {
+        if let Expr::Index { object, bracket, index } = expr {
+            let object = self.evaluate(object)?;
+            let index = self.evaluate(index)?;
+            self.line = bracket.line;
+            index_get(&object, &index)
         } else {
-            Err(Error::Runtime(format!("Something is very wrong!")))
+            Err(Error::Runtime("Something is very wrong!".to_string()))
         }
     }
+
+    fn visit_set_index_expr(&mut self, expr: &Expr) -> Result<Object> {
+        if let Expr::SetIndex { object, bracket, index, value } = expr {
+            let object = self.evaluate(object)?;
+            let index = self.evaluate(index)?;
+            let value = self.evaluate(value)?;
+            self.line = bracket.line;
+            index_set(&object, index, value.clone())?;
+            Ok(value)
+        } else {
+            Err(Error::Runtime("Something is very wrong!".to_string()))
+        }
+    }
+
+    fn visit_interpolation_expr(&mut self, parts: &[Expr]) -> Result<Object> {
+        let values = parts
+            .iter()
+            .map(|part| Ok(self.evaluate(part)?.to_string()))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(String(values.concat()))
+    }
 }
 
 impl StmtVisitor<()> for Interpreter {
@@ -268,7 +813,7 @@ impl StmtVisitor<()> for Interpreter {
         if let Stmt::Print(expr) = statement {
             match self.evaluate(expr) {
                 Ok(value) => {
-                    println!("{}", value);
+                    self.output.print(&value);
                     Ok(())
                 }
                 Err(err) => Err(err),
@@ -293,7 +838,7 @@ impl StmtVisitor<()> for Interpreter {
 
     fn visit_variable_statement(&mut self, statement: &Stmt) -> Result<()> {
         // println!("visit_variable_statement for {:?}", statement);
-        if let Stmt::Variable { name, initializer } = statement {
+        if let Stmt::Variable { name, initializer, .. } = statement {
             initializer
                 .as_ref()
                 .map_or(Ok(Object::None), |init| self.evaluate(init))
@@ -303,15 +848,15 @@ impl StmtVisitor<()> for Interpreter {
                         .define(name.lexeme.clone(), value)
                 })
         } else {
-            Err(Error::Runtime(format!("this should never happend")))
+            Err(Error::Runtime("this should never happend".to_string()))
         }
     }
 
-    fn visit_block_statement(&mut self, statements: &Vec<Stmt>) -> Result<()> {
-        self.execute_block(
-            statements,
-            Rc::new(RefCell::new(Environment::new(&self.environment))),
-        )
+    fn visit_block_statement(&mut self, statements: &[Stmt]) -> Result<()> {
+        let environment = self
+            .heap
+            .allocate_environment(Environment::new(&self.environment));
+        self.execute_block(statements, environment)
     }
 
     fn visit_if_statement(&mut self, statement: &Stmt) -> Result<()> {
@@ -325,7 +870,7 @@ impl StmtVisitor<()> for Interpreter {
             if self.is_truthy(&value) {
                 self.execute(then_branch)?;
             } else if let Some(else_branch) = else_branch {
-                self.execute(&else_branch)?;
+                self.execute(else_branch)?;
             }
         }
 
@@ -336,7 +881,10 @@ impl StmtVisitor<()> for Interpreter {
         if let Stmt::While { condition, body } = statement {
             let mut value = self.evaluate(condition)?;
             while self.is_truthy(&value) {
-                self.execute(body)?;
+                match self.execute(body) {
+                    Err(Error::Break) => break,
+                    result => result?,
+                }
                 value = self.evaluate(condition)?;
             }
         }
@@ -344,13 +892,41 @@ impl StmtVisitor<()> for Interpreter {
         Ok(())
     }
 
+    fn visit_for_in_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::ForIn { name, iterable, body } = statement {
+            let iterable = self.evaluate(iterable)?;
+            self.line = name.line;
+            let mut iterator = Iter::new(self, iterable)?;
+
+            while let Some(value) = iterator.next(self)? {
+                // a new variable every time round, closures keep the value they saw
+                let environment = self
+                    .heap
+                    .allocate_environment(Environment::new(&self.environment));
+                environment.borrow_mut().define(name.lexeme.clone(), value);
+
+                match self.execute_block(std::slice::from_ref(body), environment) {
+                    Err(Error::Break) => break,
+                    result => result?,
+                }
+            }
+            Ok(())
+        } else {
+            Err(Error::Runtime("this should never happend".to_string()))
+        }
+    }
+
     fn visit_function_statement(&mut self, statement: &Stmt) -> Result<()> {
-        if let Stmt::Function { name, params, body } = statement {
+        if let Stmt::Function { name, params, defaults, rest, body, generator, .. } = statement {
             let function = Object::Callable(Function::User {
                 name: name.clone(),
-                params: params.clone(),
-                body: body.clone(),
+                params: Rc::new(params.clone()),
+                defaults: Rc::new(defaults.clone()),
+                rest: rest.clone().map(Rc::new),
+                body: Rc::new(body.clone()),
                 closure: Rc::clone(&self.environment), // i guess we need the closure here <_<
+                is_initializer: false,
+                is_generator: *generator,
             });
             self.environment
                 .borrow_mut()
@@ -371,23 +947,140 @@ impl StmtVisitor<()> for Interpreter {
                 value: return_value,
             })
         } else {
-            Err(Error::Runtime(format!("this should never happend")))
+            Err(Error::Runtime("this should never happend".to_string()))
+        }
+    }
+
+    fn visit_break_statement(&mut self, _keyword: &Token) -> Result<()> {
+        Err(Error::Break)
+    }
+
+    // generators run their yields themselves, the resolver keeps them everywhere else out
+    fn visit_yield_statement(&mut self, _statement: &Stmt) -> Result<()> {
+        Err(Error::Runtime("Can't yield outside of a generator.".to_string()))
+    }
+
+    fn visit_throw_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::Throw { keyword, value } = statement {
+            let value = self.evaluate(value)?;
+            self.line = keyword.line;
+
+            let message = match &value {
+                Object::Instance(instance) if self.is_error(instance) => {
+                    self.locate(instance);
+                    let message = instance.fields.borrow().get("message").cloned();
+                    message.unwrap_or(Object::None).to_string()
+                }
+                value => format!("Uncaught exception: {}", value),
+            };
+            Err(Error::Throw {
+                value: Box::new(value),
+                message,
+            })
+        } else {
+            Err(Error::Runtime("this should never happend".to_string()))
+        }
+    }
+
+    fn visit_match_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::Match { keyword, value, arms } = statement {
+            let value = self.evaluate(value)?;
+            let enclosing = Rc::clone(&self.environment);
+            let patterns = arms.iter().map(|arm| (&arm.pattern, arm.guard.as_ref()));
+            let (index, environment) = self.select_arm(keyword, &value, patterns, &enclosing)?;
+
+            self.execute_block(std::slice::from_ref(&arms[index].body), environment)
+        } else {
+            Err(Error::Runtime("this should never happend".to_string()))
+        }
+    }
+
+    fn visit_try_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::Try { body, catch_clause, finally_body } = statement {
+            let result = self.visit_block_statement(body);
+            let result = result.map_err(|err| self.exception(err));
+
+            // `return` and `break` go straight to the finally block
+            let result = match (result, catch_clause) {
+                (Err(Error::Throw { value, .. }), Some((name, catch_body))) => {
+                    let environment = self
+                        .heap
+                        .allocate_environment(Environment::new(&self.environment));
+                    environment.borrow_mut().define(name.lexeme.clone(), *value);
+                    self.execute_block(catch_body, environment)
+                }
+                (result, _) => result,
+            };
+
+            // an error in the finally block replaces whatever happened before it
+            if let Some(finally_body) = finally_body {
+                self.visit_block_statement(finally_body)?;
+            }
+            result
+        } else {
+            Err(Error::Runtime("this should never happend".to_string()))
         }
     }
 
     fn visit_class_statement(&mut self, statement: &Stmt) -> Result<()> {
-        if let Stmt::Class { name, methods } = statement {
+        if let Stmt::Class { name, methods, .. } = statement {
             self.environment
                 .borrow_mut()
                 .define(name.lexeme.clone(), Object::None);
 
-            let class = Object::Class(Class::new(name.lexeme.clone()));
+            let mut class_methods = HashMap::new();
+            for method in methods.iter() {
+                if let Stmt::Function { name, params, defaults, rest, body, .. } = method.as_ref() {
+                    let function = Function::User {
+                        name: name.clone(),
+                        params: Rc::new(params.clone()),
+                        defaults: Rc::new(defaults.clone()),
+                        rest: rest.clone().map(Rc::new),
+                        body: Rc::new(body.clone()),
+                        closure: Rc::clone(&self.environment),
+                        is_initializer: name.lexeme == "init",
+                        is_generator: false,
+                    };
+                    class_methods.insert(name.lexeme.clone(), function);
+                }
+            }
+
+            let methods = self.heap.allocate_class(class_methods);
+            let class = Object::Class(Class::new(name.lexeme.clone(), methods));
+
+            self.environment.borrow_mut().assign(name, class)?;
 
-            self.environment.borrow_mut().assign(name, class);
+            Ok(())
+        } else {
+            Err(Error::Runtime("this should never happend".to_string()))
+        }
+    }
 
+    fn visit_import_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::Import { path, alias, names, .. } = statement {
+            let module = module::import(self, path)?;
+
+            if let Some(alias) = alias {
+                self.environment
+                    .borrow_mut()
+                    .define(alias.lexeme.clone(), Object::Module(Rc::clone(&module)));
+            }
+            for name in names.iter() {
+                let value = module.get(name)?;
+                self.environment.borrow_mut().define(name.lexeme.clone(), value);
+            }
             Ok(())
         } else {
-            Err(Error::Runtime(format!("this should never happend")))
+            Err(Error::Runtime("this should never happend".to_string()))
         }
     }
+
+    fn visit_export_statement(&mut self, declaration: &Stmt) -> Result<()> {
+        self.execute(declaration)?;
+
+        if let Stmt::Variable { name, .. } | Stmt::Function { name, .. } | Stmt::Class { name, .. } = declaration {
+            self.exports.insert(name.lexeme.clone());
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,109 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::class::Instance;
+use crate::errors::{Error, Result};
+use crate::function::Function;
+use crate::generator::Generator;
+use crate::interpreter::Interpreter;
+use crate::object::Object;
+use crate::tokens::{Literal, Token, TokenType};
+
+// What a `for-in` loop walks over. Strings give their characters, lists their
+// elements, maps their keys and ranges their numbers. Generators and instances are
+// asked for one value at a time: an instance has a `next()` method, or an `iter()`
+// method that returns something to iterate over, and `next()` giving nil ends it.
+
+#[derive(Debug)]
+pub enum Iter {
+    // read as the loop goes, elements added to the list on the way are visited too
+    List(Rc<RefCell<Vec<Object>>>, usize),
+    // characters and map keys, taken when the loop starts
+    Values(std::vec::IntoIter<Object>),
+    Range { next: f64, end: f64 },
+    Generator(Rc<Generator>),
+    Next(Function),
+}
+
+impl Iter {
+    pub fn new(interpreter: &mut Interpreter, value: Object) -> Result<Iter> {
+        match value {
+            Object::String(string) => Ok(Iter::Values(
+                string
+                    .chars()
+                    .map(|ch| Object::String(ch.to_string()))
+                    .collect::<Vec<Object>>()
+                    .into_iter(),
+            )),
+            Object::List(list) => Ok(Iter::List(list, 0)),
+            Object::Map(map) => Ok(Iter::Values(
+                map.borrow()
+                    .iter()
+                    .map(|(key, _)| key.clone())
+                    .collect::<Vec<Object>>()
+                    .into_iter(),
+            )),
+            Object::Range(start, end) => Ok(Iter::Range { next: start, end }),
+            Object::Generator(generator) => Ok(Iter::Generator(generator)),
+            Object::Instance(instance) => match method(interpreter, &instance, "iter")? {
+                Some(iter) => match iter.call(interpreter, Vec::new())? {
+                    // an iterator, not another iterable
+                    Object::Instance(iterator) => Iter::next_of(interpreter, &iterator),
+                    iterable => Iter::new(interpreter, iterable),
+                },
+                None => Iter::next_of(interpreter, &instance),
+            },
+            _ => Err(not_iterable()),
+        }
+    }
+
+    fn next_of(interpreter: &mut Interpreter, instance: &Instance) -> Result<Iter> {
+        method(interpreter, instance, "next")?.map(Iter::Next).ok_or_else(not_iterable)
+    }
+
+    // the next value, None at the end
+    pub fn next(&mut self, interpreter: &mut Interpreter) -> Result<Option<Object>> {
+        match self {
+            Iter::List(list, index) => {
+                let element = list.borrow().get(*index).cloned();
+                *index += 1;
+                Ok(element)
+            }
+            Iter::Values(values) => Ok(values.next()),
+            Iter::Range { next, end } => {
+                if *next >= *end {
+                    return Ok(None);
+                }
+                let value = *next;
+                *next += 1.0;
+                Ok(Some(Object::Number(value)))
+            }
+            Iter::Generator(generator) => generator.resume(interpreter),
+            Iter::Next(next) => match next.call(interpreter, Vec::new())? {
+                Object::None => Ok(None),
+                value => Ok(Some(value)),
+            },
+        }
+    }
+}
+
+fn not_iterable() -> Error {
+    Error::Runtime(
+        "Can only iterate over strings, lists, maps, ranges, generators and instances with 'iter' or 'next'."
+            .to_string(),
+    )
+}
+
+// a method or a field holding a function, bound to the instance
+fn method(interpreter: &mut Interpreter, instance: &Instance, name: &str) -> Result<Option<Function>> {
+    let present = instance.fields.borrow().contains_key(name) || instance.class.find_method(name).is_some();
+    if !present {
+        return Ok(None);
+    }
+
+    let token = Token::new(TokenType::IDENTIFIER, name.to_string(), Literal::None, 0, 0);
+    match instance.get(interpreter, &token)? {
+        Object::Callable(function) => Ok(Some(function)),
+        _ => Err(Error::Runtime(format!("'{}' of an iterator must be a function.", name))),
+    }
+}
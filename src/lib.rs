@@ -1,7 +1,11 @@
 pub mod ast;
+pub mod checker;
 pub mod environment;
 pub mod errors;
+pub mod formatter;
+pub mod generator;
 pub mod interpreter;
+pub mod iterator;
 pub mod lox;
 pub mod parser;
 pub mod scanner;
@@ -10,4 +14,11 @@ pub mod tokens;
 pub mod function;
 pub mod object;
 pub mod resolver;
-pub mod class;
\ No newline at end of file
+pub mod class;
+pub mod cst;
+pub mod heap;
+pub mod lint;
+pub mod line_editor;
+pub mod module;
+pub mod optimizer;
+pub mod repl;
@@ -0,0 +1,263 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+// A minimal line editor for the REPL: cursor movement, history navigation and a
+// history file that survives between sessions. The terminal is switched into raw
+// mode with `stty` only while a line is being read. When stdin is not a terminal
+// it falls back to plain buffered reads.
+
+const MAX_HISTORY: usize = 1000;
+
+pub enum ReadLine {
+    Line(String),
+    Interrupted,
+    Eof,
+}
+
+pub struct LineEditor {
+    history: Vec<String>,
+    history_path: Option<PathBuf>,
+}
+
+// restores the terminal settings when the line has been read (or reading failed)
+struct RawMode {
+    saved: String,
+}
+
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(File::open("/dev/tty")?)
+        .stderr(Stdio::null())
+        .output()?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(io::Error::other("stty failed"))
+    }
+}
+
+impl RawMode {
+    fn enable() -> io::Result<RawMode> {
+        let saved = stty(&["-g"])?;
+        stty(&["raw", "-echo"])?;
+        Ok(RawMode { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    KillLine,
+    Interrupt,
+    Eof,
+    Ignored,
+}
+
+fn read_byte(input: &mut impl Read) -> io::Result<Option<u8>> {
+    let mut byte = [0; 1];
+    match input.read(&mut byte)? {
+        0 => Ok(None),
+        _ => Ok(Some(byte[0])),
+    }
+}
+
+fn read_key(input: &mut impl Read) -> io::Result<Key> {
+    let byte = match read_byte(input)? {
+        Some(byte) => byte,
+        None => return Ok(Key::Eof),
+    };
+
+    let key = match byte {
+        b'\r' | b'\n' => Key::Enter,
+        0x7f | 0x08 => Key::Backspace,
+        0x01 => Key::Home,
+        0x05 => Key::End,
+        0x02 => Key::Left,
+        0x06 => Key::Right,
+        0x10 => Key::Up,
+        0x0e => Key::Down,
+        0x15 => Key::KillLine,
+        0x03 => Key::Interrupt,
+        0x04 => Key::Eof,
+        0x1b => match (read_byte(input)?, read_byte(input)?) {
+            (Some(b'['), Some(b'A')) => Key::Up,
+            (Some(b'['), Some(b'B')) => Key::Down,
+            (Some(b'['), Some(b'C')) => Key::Right,
+            (Some(b'['), Some(b'D')) => Key::Left,
+            (Some(b'['), Some(b'H')) | (Some(b'O'), Some(b'H')) => Key::Home,
+            (Some(b'['), Some(b'F')) | (Some(b'O'), Some(b'F')) => Key::End,
+            (Some(b'['), Some(b'3')) => {
+                read_byte(input)?;
+                Key::Delete
+            }
+            _ => Key::Ignored,
+        },
+        byte if byte < 0x20 => Key::Ignored,
+        byte => {
+            // utf-8: the leading byte tells how many continuation bytes follow
+            let mut bytes = vec![byte];
+            let length = match byte {
+                0xf0.. => 4,
+                0xe0.. => 3,
+                0xc0.. => 2,
+                _ => 1,
+            };
+            while bytes.len() < length {
+                match read_byte(input)? {
+                    Some(byte) => bytes.push(byte),
+                    None => break,
+                }
+            }
+            match String::from_utf8_lossy(&bytes).chars().next() {
+                Some(ch) => Key::Char(ch),
+                None => Key::Ignored,
+            }
+        }
+    };
+
+    Ok(key)
+}
+
+impl LineEditor {
+    pub fn new(history_path: Option<PathBuf>) -> Self {
+        let history = history_path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|contents| contents.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+
+        LineEditor {
+            history,
+            history_path,
+        }
+    }
+
+    // ~/.lox_history
+    pub fn default_history_path() -> Option<PathBuf> {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".lox_history"))
+    }
+
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    pub fn add_history(&mut self, entry: &str) {
+        if entry.trim().is_empty() || self.history.last().is_some_and(|last| last == entry) {
+            return;
+        }
+
+        self.history.push(entry.to_string());
+        if self.history.len() > MAX_HISTORY {
+            self.history.remove(0);
+        }
+
+        if let Some(path) = &self.history_path {
+            let _ = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| writeln!(file, "{}", entry));
+        }
+    }
+
+    pub fn read_line(&mut self, prompt: &str) -> io::Result<ReadLine> {
+        if io::stdin().is_terminal() {
+            if let Ok(raw) = RawMode::enable() {
+                let line = self.edit(prompt);
+                drop(raw);
+                println!();
+                return line;
+            }
+        }
+
+        print!("{}", prompt);
+        io::stdout().flush()?;
+
+        let mut line = String::new();
+        match io::stdin().lock().read_line(&mut line)? {
+            0 => Ok(ReadLine::Eof),
+            _ => Ok(ReadLine::Line(line.trim_end_matches(['\n', '\r']).to_string())),
+        }
+    }
+
+    fn edit(&mut self, prompt: &str) -> io::Result<ReadLine> {
+        let mut stdin = io::stdin().lock();
+        let mut stdout = io::stdout();
+
+        let mut line: Vec<char> = Vec::new();
+        let mut cursor = 0;
+        // index into the history, history.len() is the line being typed
+        let mut position = self.history.len();
+        let mut pending = String::new();
+
+        loop {
+            let text: String = line.iter().collect();
+            write!(stdout, "\r{}{}\x1b[K\r", prompt, text)?;
+            let column = prompt.chars().count() + cursor;
+            if column > 0 {
+                write!(stdout, "\x1b[{}C", column)?;
+            }
+            stdout.flush()?;
+
+            match read_key(&mut stdin)? {
+                Key::Char(ch) => {
+                    line.insert(cursor, ch);
+                    cursor += 1;
+                }
+                Key::Enter => return Ok(ReadLine::Line(line.into_iter().collect())),
+                Key::Backspace if cursor > 0 => {
+                    cursor -= 1;
+                    line.remove(cursor);
+                }
+                Key::Delete if cursor < line.len() => {
+                    line.remove(cursor);
+                }
+                Key::Left if cursor > 0 => cursor -= 1,
+                Key::Right if cursor < line.len() => cursor += 1,
+                Key::Home => cursor = 0,
+                Key::End => cursor = line.len(),
+                Key::KillLine => {
+                    line.clear();
+                    cursor = 0;
+                }
+                Key::Up if position > 0 => {
+                    if position == self.history.len() {
+                        pending = line.iter().collect();
+                    }
+                    position -= 1;
+                    line = self.history[position].chars().collect();
+                    cursor = line.len();
+                }
+                Key::Down if position < self.history.len() => {
+                    position += 1;
+                    line = match self.history.get(position) {
+                        Some(entry) => entry.chars().collect(),
+                        None => pending.chars().collect(),
+                    };
+                    cursor = line.len();
+                }
+                Key::Interrupt => return Ok(ReadLine::Interrupted),
+                Key::Eof if line.is_empty() => return Ok(ReadLine::Eof),
+                _ => {}
+            }
+        }
+    }
+}
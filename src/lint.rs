@@ -0,0 +1,93 @@
+use std::collections::HashSet;
+use std::fmt;
+
+// Warnings the resolver can give on top of its errors. None of them stop a program
+// from running, they point at code that is most likely a mistake.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    UnusedVariable,
+    UnusedParameter,
+    UnreachableCode,
+    Shadowing,
+    UnreadAssignment,
+    ConstantComparison,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 6] = [
+        Rule::UnusedVariable,
+        Rule::UnusedParameter,
+        Rule::UnreachableCode,
+        Rule::Shadowing,
+        Rule::UnreadAssignment,
+        Rule::ConstantComparison,
+    ];
+
+    // the name used on the command line and in warnings
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::UnusedVariable => "unused-variable",
+            Rule::UnusedParameter => "unused-parameter",
+            Rule::UnreachableCode => "unreachable-code",
+            Rule::Shadowing => "shadowing",
+            Rule::UnreadAssignment => "unread-assignment",
+            Rule::ConstantComparison => "constant-comparison",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Rule> {
+        Rule::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+}
+
+// which rules are switched on, none by default
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Lints {
+    enabled: HashSet<Rule>,
+}
+
+impl Lints {
+    pub fn all() -> Self {
+        Lints {
+            enabled: Rule::ALL.into_iter().collect(),
+        }
+    }
+
+    pub fn enable(&mut self, rule: Rule) {
+        self.enabled.insert(rule);
+    }
+
+    pub fn disable(&mut self, rule: Rule) {
+        self.enabled.remove(&rule);
+    }
+
+    pub fn is_enabled(&self, rule: Rule) -> bool {
+        self.enabled.contains(&rule)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.enabled.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub rule: Rule,
+    pub line: usize,
+    pub lexeme: String,
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[line {}] Warning at '{}': {} [{}]",
+            self.line,
+            self.lexeme,
+            self.message,
+            self.rule.name()
+        )
+    }
+}
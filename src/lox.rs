@@ -1,28 +1,84 @@
 use std::error;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use crate::checker::Checker;
 use crate::errors::Error;
-use crate::interpreter::Interpreter;
+use crate::heap::Heap;
+use crate::interpreter::{Interpreter, Output};
+use crate::lint::Lints;
+use crate::optimizer::Optimizer;
+use crate::object::Object;
 use crate::parser::Parser;
+use crate::repl::Repl;
 use crate::resolver::Resolver;
 use crate::scanner::Scanner;
+use crate::statement::Stmt;
 use crate::tokens::Token;
 
 type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
 
+// exit codes from sysexits.h, the same ones the reference implementation uses
+pub const EX_DATAERR: i32 = 65;
+pub const EX_SOFTWARE: i32 = 70;
+
+// how running a piece of source went
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Success,
+    // scanning, parsing or resolving failed, nothing was executed
+    StaticError,
+    RuntimeError,
+}
+
+impl Status {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Status::Success => 0,
+            Status::StaticError => EX_DATAERR,
+            Status::RuntimeError => EX_SOFTWARE,
+        }
+    }
+}
+
 pub struct Lox {
-    had_error: bool,
     interpreter: Interpreter,
+    // see `Optimizer` for what each level does, 0 disables the pass
+    pub optimization_level: u8,
+    // the lint rules to warn about, warnings never stop a program from running
+    pub lints: Lints,
+    // run the type checker on the annotations before running anything
+    pub type_check: bool,
+}
+
+impl Default for Lox {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Lox {
     pub fn new() -> Self {
         Lox {
-            had_error: false,
             interpreter: Interpreter::new(),
+            optimization_level: 0,
+            lints: Lints::default(),
+            type_check: false,
         }
     }
 
+    pub fn heap(&mut self) -> &mut Heap {
+        &mut self.interpreter.heap
+    }
+
+    // directories to look for imported modules in, after the importing file's own
+    pub fn search_path(&mut self) -> &mut Vec<PathBuf> {
+        &mut self.interpreter.search_path
+    }
+
+    pub fn set_output(&mut self, output: Output) {
+        self.interpreter.output = output;
+    }
+
     pub fn error(&self, line: u64, message: &str) {
         self.report(line, " ", message);
     }
@@ -31,59 +87,151 @@ impl Lox {
         eprintln!("[line {} ] Error {}  : {}", line, _where, message);
     }
 
-    pub fn run_prompt(&mut self) -> Result<()> {
-        let mut line = String::new();
-        let input = std::io::stdin();
-        while let Ok(n) = input.read_line(&mut line) {
-            if n == 0 {
-                break;
-            }
-            self.run(&line);
-            line.clear();
-            self.had_error = false;
-        }
+    pub fn output(&self) -> &Output {
+        &self.interpreter.output
+    }
 
+    // the global variables, sorted by name
+    pub fn globals(&self) -> Vec<(String, Object)> {
+        let mut globals: Vec<(String, Object)> = self
+            .interpreter
+            .globals
+            .borrow()
+            .values
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        globals.sort_by(|a, b| a.0.cmp(&b.0));
+        globals
+    }
+
+    // starts over with a fresh interpreter, keeping the output and heap settings
+    pub fn reset(&mut self) {
+        let mut interpreter = Interpreter::new();
+        interpreter.output = self.interpreter.output.clone();
+        interpreter.heap.stress = self.interpreter.heap.stress;
+        interpreter.heap.trace = self.interpreter.heap.trace;
+        interpreter.search_path = self.interpreter.search_path.clone();
+
+        self.interpreter = interpreter;
+    }
+
+    pub fn run_prompt(&mut self) -> Result<()> {
+        Repl::new(self).run()?;
         Ok(())
     }
 
-    pub fn run_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+    pub fn run_file<P: AsRef<Path>>(&mut self, path: P) -> Result<Status> {
+        let source: String = std::fs::read_to_string(&path)?;
+
+        // imports are relative to the file, and importing it again is a cycle
+        let previous = self.interpreter.current_file.replace(path.as_ref().to_path_buf());
+        let canonical = path.as_ref().canonicalize()?;
+        self.interpreter.loading.push(canonical);
+        let status = self.run(&source);
+        self.interpreter.loading.pop();
+        self.interpreter.current_file = previous;
+
+        Ok(status)
+    }
+
+    pub fn run_debug_file<P: AsRef<Path>>(&mut self, path: P) -> Vec<Token> {
+        let source: String = std::fs::read_to_string(path).unwrap_or_default();
+
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().0
+    }
+
+    // prints the tokens of a file one per line, `IDENTIFIER andy null`
+    pub fn run_tokens_file<P: AsRef<Path>>(&mut self, path: P) -> Result<Status> {
         let source: String = std::fs::read_to_string(path)?;
-        self.run(&source);
 
-        if self.had_error {
-            Err(Error::Syntax.into())
+        let (tokens, errors) = Scanner::new(source).scan_tokens();
+        for token in tokens.iter() {
+            self.interpreter.output.print(token);
+        }
+        errors.iter().for_each(|error| eprintln!("{}", error));
+
+        if errors.is_empty() {
+            Ok(Status::Success)
         } else {
-            Ok(())
+            Ok(Status::StaticError)
         }
     }
 
-    pub fn run_debug_file<P: AsRef<Path>>(&mut self, path: P) -> Vec<Token> {
-        let source: String = std::fs::read_to_string(path).unwrap_or(String::new());
-
-        let mut scanner = Scanner::new(source.clone());
-        let tokens = scanner.scan_tokens();
+    pub fn run(&mut self, s: &str) -> Status {
+        self.execute(s, false)
+    }
 
-        tokens
+    // like `run`, but a lone expression statement has its value printed
+    pub fn run_interactive(&mut self, s: &str) -> Status {
+        self.execute(s, true)
     }
 
-    pub fn run(&mut self, s: &String) {
-        let mut scanner = Scanner::new(s.clone());
-        let tokens = scanner.scan_tokens();
-        let mut parser = Parser::new(tokens.clone());
-        let statements = parser.parse();
+    fn execute(&mut self, s: &str, echo: bool) -> Status {
+        let mut scanner = Scanner::new(s.to_string());
+        let (mut tokens, errors) = scanner.scan_tokens();
+        if !errors.is_empty() {
+            errors.iter().for_each(|error| eprintln!("{}", error));
+            return Status::StaticError;
+        }
+        self.interpreter.number_tokens(&mut tokens);
+
+        let mut parser = Parser::new(tokens);
+
+        // the parser reports its own errors as it goes
+        let Ok(statements) = parser.parse() else {
+            return Status::StaticError;
+        };
+
+        let mut resolver = Resolver::with_lints(&mut self.interpreter, self.lints.clone());
+        let resolved = resolver.resolve_statements(&statements);
+
+        let mut warnings = resolver.warnings().to_vec();
+        warnings.sort_by_key(|warning| warning.line);
+        warnings.iter().for_each(|warning| eprintln!("{}", warning));
 
-        match statements.and_then(|statements| {
-            let mut resolver = Resolver::new(&mut self.interpreter);
-            resolver.resolve_statements(&statements)?;
-            self.interpreter.interpret(&statements)
-        }) {
-            Ok(_) => {}
+        if !resolver.errors().is_empty() {
+            resolver.errors().iter().for_each(|error| eprintln!("{}", error));
+            return Status::StaticError;
+        }
+
+        if self.type_check {
+            let mut checker = Checker::new();
+            if let Err(err) = checker.check(&statements) {
+                eprintln!("{}", err);
+                return Status::StaticError;
+            }
+            if !checker.errors().is_empty() {
+                checker.errors().iter().for_each(|error| eprintln!("{}", error));
+                return Status::StaticError;
+            }
+        }
+
+        let statements = match resolved.and_then(|_| Optimizer::new(self.optimization_level).optimize(&statements)) {
+            Ok(statements) => statements,
             Err(err) => {
-                self.had_error = true;
-                println!("ERROR {}", err);
-                return;
+                eprintln!("{}", err);
+                return Status::StaticError;
+            }
+        };
+
+        let result = match statements.as_slice() {
+            [Stmt::Expression(expr)] if echo => self
+                .interpreter
+                .evaluate(expr)
+                .map(|value| self.interpreter.output.print(&value)),
+            _ => self.interpreter.interpret(&statements),
+        };
+
+        match result {
+            Ok(()) => Status::Success,
+            // an imported module did not compile
+            Err(Error::Syntax) => Status::StaticError,
+            Err(err) => {
+                eprintln!("{}", err);
+                Status::RuntimeError
             }
         }
     }
 }
-
@@ -1,25 +1,120 @@
 use std::env;
 use std::error;
+use std::fs;
 use std::process::exit;
 
-use rlox::lox::Lox;
+use rlox::formatter;
+use rlox::lint::{Lints, Rule};
+use rlox::lox::{Lox, Status};
 
 const EX_USAGE: i32 = 64;
 type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
 
+fn usage() -> ! {
+    eprintln!("Usage: rlox [-O<level>] [--gc-stress] [--gc-stats] [--tokens] [--typecheck]");
+    eprintln!("            [--lint[=<rule>,...]] [--allow=<rule>,...] [--path=<dir>]... [script]");
+    eprintln!("       rlox fmt [--check] <file>...");
+    exit(EX_USAGE);
+}
+
+// rewrites the files in place, with --check it only lists the ones that would change
+fn fmt(args: &[String]) -> Result<()> {
+    let check = args.iter().any(|arg| arg == "--check");
+    let files: Vec<&String> = args.iter().filter(|arg| *arg != "--check").collect();
+    if files.is_empty() || files.iter().any(|file| file.starts_with("--")) {
+        usage();
+    }
+
+    let mut unformatted = false;
+    let mut broken = false;
+
+    for file in files {
+        let source = fs::read_to_string(file)?;
+        let formatted = match formatter::format(&source) {
+            Ok(formatted) => formatted,
+            Err(_) => {
+                eprintln!("Could not format {}", file);
+                broken = true;
+                continue;
+            }
+        };
+
+        if formatted == source {
+            continue;
+        }
+        if check {
+            println!("{} is not formatted", file);
+            unformatted = true;
+        } else {
+            fs::write(file, formatted)?;
+        }
+    }
+
+    if broken {
+        exit(Status::StaticError.exit_code());
+    }
+    exit(if unformatted { 1 } else { 0 })
+}
+
+// a comma separated list of lint rules, `unused-variable,shadowing`
+fn rules(list: &str) -> Vec<Rule> {
+    list.split(',')
+        .map(|name| {
+            Rule::from_name(name).unwrap_or_else(|| {
+                eprintln!("Unknown lint rule '{}'", name);
+                usage()
+            })
+        })
+        .collect()
+}
+
 fn main() -> Result<()> {
-    let args: Vec<String> = env::args().collect();
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "fmt") {
+        return fmt(&args[1..]);
+    }
 
     let mut lox = Lox::new();
+    let mut scripts = Vec::new();
+    let mut gc_stats = false;
+    let mut tokens = false;
+    let mut lints = Lints::default();
+    let mut allowed = Vec::new();
+
+    for arg in args.iter() {
+        match arg.as_str() {
+            "--gc-stress" => lox.heap().stress = true,
+            "--gc-stats" => gc_stats = true,
+            "--tokens" => tokens = true,
+            "--typecheck" => lox.type_check = true,
+            flag if flag.starts_with("--path=") => lox.search_path().push(flag[7..].into()),
+            "--lint" => lints = Lints::all(),
+            flag if flag.starts_with("--lint=") => rules(&flag[7..]).into_iter().for_each(|rule| lints.enable(rule)),
+            flag if flag.starts_with("--allow=") => allowed.extend(rules(&flag[8..])),
+            flag if flag.starts_with("-O") => {
+                lox.optimization_level = flag[2..].parse().unwrap_or_else(|_| usage())
+            }
+            flag if flag.starts_with("--") => usage(),
+            script => scripts.push(script),
+        }
+    }
+    lox.heap().trace = gc_stats;
+
+    // --allow wins over --lint, wherever they are
+    allowed.into_iter().for_each(|rule| lints.disable(rule));
+    lox.lints = lints;
+
+    let status = match scripts.as_slice() {
+        [script] if tokens => lox.run_tokens_file(script)?,
+        _ if tokens => usage(),
+        [] => lox.run_prompt().map(|_| Status::Success)?,
+        [script] => lox.run_file(script)?,
+        _ => usage(),
+    };
 
-    if args.len() > 2 {
-        eprintln!("Usage: rlox [script]");
-        exit(EX_USAGE);
-    } else if args.len() == 2 {
-        lox.run_file(&args[1])?;
-    } else {
-        lox.run_prompt()?;
+    if gc_stats {
+        eprintln!("[gc] {}", lox.heap().stats());
     }
 
-    Ok(())
+    exit(status.exit_code())
 }
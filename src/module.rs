@@ -0,0 +1,141 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::environment::Environment;
+use crate::errors::{Error, Result};
+use crate::interpreter::Interpreter;
+use crate::object::Object;
+use crate::parser::Parser;
+use crate::resolver::Resolver;
+use crate::scanner::Scanner;
+use crate::tokens::{Literal, Token};
+
+// Every file is a module with its own globals. `import` runs a module the first time
+// it is asked for and caches it by its canonical path, later imports share it. A
+// module only shows the names it declared with `export`.
+//
+// Paths are relative to the importing file, or to the current directory for the
+// REPL, and then to each directory of the search path in turn.
+
+#[derive(Debug)]
+pub struct Module {
+    pub path: PathBuf,
+    pub globals: Rc<RefCell<Environment>>,
+    pub exports: HashSet<String>,
+}
+
+impl Module {
+    pub fn name(&self) -> String {
+        self.path
+            .file_stem()
+            .map_or_else(|| self.path.display().to_string(), |stem| stem.to_string_lossy().to_string())
+    }
+
+    // an exported name, the value is read when it is asked for
+    pub fn get(&self, name: &Token) -> Result<Object> {
+        if !self.exports.contains(&name.lexeme) {
+            return Err(Error::Runtime(format!(
+                "Module '{}' does not export '{}'.",
+                self.name(),
+                name.lexeme
+            )));
+        }
+        self.globals.borrow().get(name)
+    }
+}
+
+impl fmt::Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<module {}>", self.name())
+    }
+}
+
+// the module a path token of an import refers to, loading it if needed
+pub fn import(interpreter: &mut Interpreter, path: &Token) -> Result<Rc<Module>> {
+    let Literal::String(relative) = &path.literal else {
+        return Err(Error::Runtime(format!("Invalid module path {}.", path.lexeme)));
+    };
+    let file = find(interpreter, Path::new(relative))
+        .ok_or_else(|| Error::Runtime(format!("Could not find module '{}'.", relative)))?;
+
+    if let Some(module) = interpreter.modules.get(&file) {
+        return Ok(Rc::clone(module));
+    }
+
+    if let Some(start) = interpreter.loading.iter().position(|loading| *loading == file) {
+        let cycle: Vec<String> = interpreter.loading[start..]
+            .iter()
+            .chain([&file])
+            .map(|path| file_name(path))
+            .collect();
+        return Err(Error::Runtime(format!("Circular import: {}.", cycle.join(" -> "))));
+    }
+
+    interpreter.loading.push(file.clone());
+    let module = load(interpreter, &file);
+    interpreter.loading.pop();
+
+    let module = Rc::new(module?);
+    interpreter.modules.insert(file, Rc::clone(&module));
+    Ok(module)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().to_string())
+}
+
+fn find(interpreter: &Interpreter, relative: &Path) -> Option<PathBuf> {
+    let base = interpreter
+        .current_file
+        .as_ref()
+        .and_then(|file| file.parent())
+        .map_or_else(|| PathBuf::from("."), Path::to_path_buf);
+
+    std::iter::once(base)
+        .chain(interpreter.search_path.iter().cloned())
+        .map(|directory| directory.join(relative))
+        .find(|candidate| candidate.is_file())
+        .and_then(|candidate| candidate.canonicalize().ok())
+}
+
+fn load(interpreter: &mut Interpreter, file: &Path) -> Result<Module> {
+    let name = file_name(file);
+    let source = std::fs::read_to_string(file)
+        .map_err(|err| Error::Runtime(format!("Could not read module '{}': {}", name, err)))?;
+    // a static error like one in the importing file, the errors have been reported
+    let compile_error = || {
+        eprintln!("Could not compile module '{}'.", name);
+        Error::Syntax
+    };
+
+    let (mut tokens, errors) = Scanner::new(source).scan_tokens();
+    if !errors.is_empty() {
+        errors.iter().for_each(|error| eprintln!("{}", error));
+        return Err(compile_error());
+    }
+    interpreter.number_tokens(&mut tokens);
+
+    // the parser reports its own errors
+    let statements = Parser::new(tokens).parse().map_err(|_| compile_error())?;
+
+    let mut resolver = Resolver::new(interpreter);
+    let resolved = resolver.resolve_statements(&statements);
+    if !resolver.errors().is_empty() {
+        resolver.errors().iter().for_each(|error| eprintln!("{}", error));
+        return Err(compile_error());
+    }
+    resolved?;
+
+    let globals = interpreter.new_globals();
+    let exports = interpreter.run_module(&statements, Rc::clone(&globals), file.to_path_buf())?;
+
+    Ok(Module {
+        path: file.to_path_buf(),
+        globals,
+        exports,
+    })
+}
@@ -1,6 +1,11 @@
 use crate::function::Function;
 use crate::class::{Class, Instance};
+use crate::generator::Generator;
+use crate::module::Module;
+use crate::tokens::Literal;
+use std::cell::RefCell;
 use std::fmt;
+use std::rc::Rc;
 
 #[derive(Debug, Clone)]
 pub enum Object {
@@ -11,6 +16,14 @@ pub enum Object {
     Callable(Function),
     Class(Class),
     Instance(Instance),
+    Module(Rc<Module>),
+    Generator(Rc<Generator>),
+    // lists and maps are shared, not copied, and allocated on the heap. A map keeps
+    // its entries in the order they were added.
+    List(Rc<RefCell<Vec<Object>>>),
+    Map(Rc<RefCell<Vec<(Object, Object)>>>),
+    // `start..end`, counting up by one and stopping before `end`
+    Range(f64, f64),
     None,
 }
 
@@ -22,10 +35,24 @@ impl fmt::Display for Object {
             Object::String(value) => write!(f, "{}", value),
             Object::Boolean(value) => write!(f, "{}", value),
             Object::None => write!(f, "null"),
-            Object::Callable(func) => write!(f, "{:?}", func),
-            Object::Class(class) => write!(f, "{:?}", class),
-            Object::Instance(instance) => write!(f, "{:?}", instance),
-
+            Object::Callable(func) => write!(f, "{}", func),
+            Object::Class(class) => write!(f, "{}", class),
+            Object::Instance(instance) => write!(f, "{}", instance),
+            Object::Module(module) => write!(f, "{}", module),
+            Object::Generator(generator) => write!(f, "{}", generator),
+            Object::List(list) => {
+                let elements: Vec<String> = list.borrow().iter().map(|element| element.to_string()).collect();
+                write!(f, "[{}]", elements.join(", "))
+            }
+            Object::Map(map) => {
+                let entries: Vec<String> = map
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key, value))
+                    .collect();
+                write!(f, "{{{}}}", entries.join(", "))
+            }
+            Object::Range(start, end) => write!(f, "{}..{}", start, end),
         }
     }
 }
@@ -38,7 +65,43 @@ impl PartialEq for Object {
             (Object::Boolean(left), Object::Boolean(right)) => left == right,
             (Object::Number(left), Object::Number(right)) => left == right,
             (Object::String(left), Object::String(right)) => left == right,
+            (Object::Instance(left), Object::Instance(right)) => left.is(right),
+            (Object::Module(left), Object::Module(right)) => Rc::ptr_eq(left, right),
+            (Object::Generator(left), Object::Generator(right)) => Rc::ptr_eq(left, right),
+            (Object::List(left), Object::List(right)) => Rc::ptr_eq(left, right),
+            (Object::Map(left), Object::Map(right)) => Rc::ptr_eq(left, right),
+            (Object::Range(left_start, left_end), Object::Range(right_start, right_end)) => {
+                left_start == right_start && left_end == right_end
+            }
             _ => false,
         }
     }
 }
+
+impl Object {
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Object::None | Object::Boolean(false))
+    }
+
+    // the inverse of `From<&Literal>`, for values that can be written in source code
+    pub fn to_literal(&self) -> Option<Literal> {
+        match self {
+            Object::String(value) => Some(Literal::String(value.clone())),
+            Object::Number(value) => Some(Literal::Number(*value)),
+            Object::Boolean(value) => Some(Literal::Boolean(*value)),
+            Object::None => Some(Literal::None),
+            _ => None,
+        }
+    }
+}
+
+impl From<&Literal> for Object {
+    fn from(literal: &Literal) -> Self {
+        match literal {
+            Literal::Boolean(b) => Object::Boolean(*b),
+            Literal::None => Object::None,
+            Literal::Number(n) => Object::Number(*n),
+            Literal::String(s) => Object::String(s.clone()),
+        }
+    }
+}
@@ -0,0 +1,599 @@
+use crate::ast::{Expr, Visitor as ExprVisitor};
+use crate::errors::{Error, Result};
+use crate::interpreter::{binary_operation, unary_operation};
+use crate::object::Object;
+use crate::statement::{MatchArm, Stmt, Visitor as StmtVisitor};
+use crate::tokens::{Literal, Token, TokenType};
+
+// Rewrites the resolved statements before they reach the interpreter.
+//
+// level 0 - leaves the program untouched
+// level 1 - folds constant unary, binary and logical expressions
+// level 2 - also drops dead code: untaken `if` branches, `while (false)` loops and
+//           statements after a `return`
+//
+// Only nodes are removed or replaced by literals, the tokens the resolver recorded
+// in `Interpreter::locals` survive unchanged. Folding reuses the interpreter's
+// operators and gives up whenever they fail, so `"a" - 1` still fails at runtime.
+pub struct Optimizer {
+    level: u8,
+}
+
+fn literal(expr: &Expr) -> Option<Object> {
+    match expr {
+        Expr::Literal { value } => Some(Object::from(value)),
+        _ => None,
+    }
+}
+
+fn fold(value: Result<Object>, original: Expr) -> Expr {
+    match value.ok().and_then(|value| value.to_literal()) {
+        Some(value) => Expr::Literal { value },
+        None => original,
+    }
+}
+
+impl Optimizer {
+    pub fn new(level: u8) -> Self {
+        Optimizer { level }
+    }
+
+    pub fn optimize(&mut self, statements: &[Stmt]) -> Result<Vec<Stmt>> {
+        if self.level == 0 {
+            return Ok(statements.to_vec());
+        }
+
+        self.optimize_statements(statements)
+    }
+
+    fn optimize_statements(&mut self, statements: &[Stmt]) -> Result<Vec<Stmt>> {
+        let mut optimized = Vec::new();
+        for statement in statements.iter() {
+            optimized.extend(statement.accept(self)?);
+
+            if self.level >= 2 && matches!(optimized.last(), Some(Stmt::Return { .. })) {
+                break;
+            }
+        }
+        Ok(optimized)
+    }
+
+    fn optimize_statement(&mut self, statement: &Stmt) -> Result<Box<Stmt>> {
+        let mut statements = statement.accept(self)?;
+        if statements.len() == 1 {
+            Ok(Box::new(statements.remove(0)))
+        } else {
+            Ok(Box::new(Stmt::Block { statements }))
+        }
+    }
+
+    fn expression(&mut self, expr: &Expr) -> Result<Expr> {
+        expr.accept(self)
+    }
+
+    fn boxed(&mut self, expr: &Expr) -> Result<Box<Expr>> {
+        Ok(Box::new(self.expression(expr)?))
+    }
+}
+
+impl ExprVisitor<Expr> for Optimizer {
+    fn visit_binary_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> Result<Expr> {
+        let left = self.expression(left)?;
+        let right = self.expression(right)?;
+
+        let value = match (literal(&left), literal(&right)) {
+            (Some(l), Some(r)) => binary_operation(operator, &l, &r),
+            _ => Err(Error::Parse),
+        };
+
+        Ok(fold(
+            value,
+            Expr::Binary {
+                left: Box::new(left),
+                operator: operator.clone(),
+                right: Box::new(right),
+            },
+        ))
+    }
+
+    fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) -> Result<Expr> {
+        let right = self.expression(right)?;
+
+        let value = match literal(&right) {
+            Some(r) => unary_operation(operator, &r),
+            None => Err(Error::Parse),
+        };
+
+        Ok(fold(
+            value,
+            Expr::Unary {
+                operator: operator.clone(),
+                right: Box::new(right),
+            },
+        ))
+    }
+
+    fn visit_grouping_expr(&mut self, expr: &Expr) -> Result<Expr> {
+        let expression = self.expression(expr)?;
+        match expression {
+            Expr::Literal { .. } => Ok(expression),
+            _ => Ok(Expr::Grouping {
+                expression: Box::new(expression),
+            }),
+        }
+    }
+
+    fn visit_literal_expr(&mut self, value: &Literal) -> Result<Expr> {
+        Ok(Expr::Literal {
+            value: value.clone(),
+        })
+    }
+
+    fn visit_variable_expr(&mut self, name: &Token) -> Result<Expr> {
+        Ok(Expr::Variable { name: name.clone() })
+    }
+
+    fn visit_variable_assignment_expr(&mut self, expr: &Expr) -> Result<Expr> {
+        if let Expr::Assign { name, value } = expr {
+            Ok(Expr::Assign {
+                name: name.clone(),
+                value: self.boxed(value)?,
+            })
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_logical_expr(&mut self, expr: &Expr) -> Result<Expr> {
+        if let Expr::Logical {
+            left,
+            operator,
+            right,
+        } = expr
+        {
+            let left = self.expression(left)?;
+            let right = self.expression(right)?;
+
+            // a literal on the left decides whether the right hand side is evaluated at all
+            if let Some(value) = literal(&left) {
+                let short_circuits = if operator.token_type == TokenType::OR {
+                    value.is_truthy()
+                } else {
+                    !value.is_truthy()
+                };
+
+                return Ok(if short_circuits { left } else { right });
+            }
+
+            Ok(Expr::Logical {
+                left: Box::new(left),
+                operator: operator.clone(),
+                right: Box::new(right),
+            })
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_call_expr(&mut self, expr: &Expr) -> Result<Expr> {
+        if let Expr::Call {
+            callee,
+            paren,
+            arguments,
+            named,
+        } = expr
+        {
+            Ok(Expr::Call {
+                callee: self.boxed(callee)?,
+                paren: paren.clone(),
+                arguments: arguments
+                    .iter()
+                    .map(|argument| self.expression(argument))
+                    .collect::<Result<Vec<Expr>>>()?,
+                named: named
+                    .iter()
+                    .map(|(name, argument)| Ok((name.clone(), self.expression(argument)?)))
+                    .collect::<Result<Vec<(Token, Expr)>>>()?,
+            })
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_conditional_expr(&mut self, expr: &Expr) -> Result<Expr> {
+        if let Expr::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        } = expr
+        {
+            let condition = self.expression(condition)?;
+            let then_branch = self.expression(then_branch)?;
+            let else_branch = self.expression(else_branch)?;
+
+            // a literal condition picks the branch right away
+            if let Some(value) = literal(&condition) {
+                return Ok(if value.is_truthy() { then_branch } else { else_branch });
+            }
+
+            Ok(Expr::Conditional {
+                condition: Box::new(condition),
+                then_branch: Box::new(then_branch),
+                else_branch: Box::new(else_branch),
+            })
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_update_expr(&mut self, expr: &Expr) -> Result<Expr> {
+        if let Expr::Update {
+            target,
+            operator,
+            value,
+            postfix,
+        } = expr
+        {
+            Ok(Expr::Update {
+                target: self.boxed(target)?,
+                operator: operator.clone(),
+                value: self.boxed(value)?,
+                postfix: *postfix,
+            })
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_get_expr(&mut self, expr: &Expr) -> Result<Expr> {
+        if let Expr::Get { object, name } = expr {
+            Ok(Expr::Get {
+                object: self.boxed(object)?,
+                name: name.clone(),
+            })
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_set_expr(&mut self, expr: &Expr) -> Result<Expr> {
+        if let Expr::Set {
+            object,
+            name,
+            value,
+        } = expr
+        {
+            Ok(Expr::Set {
+                object: self.boxed(object)?,
+                name: name.clone(),
+                value: self.boxed(value)?,
+            })
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_list_expr(&mut self, expr: &Expr) -> Result<Expr> {
+        if let Expr::List { bracket, elements } = expr {
+            Ok(Expr::List {
+                bracket: bracket.clone(),
+                elements: elements
+                    .iter()
+                    .map(|element| self.expression(element))
+                    .collect::<Result<Vec<Expr>>>()?,
+            })
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_map_expr(&mut self, expr: &Expr) -> Result<Expr> {
+        if let Expr::Map { brace, entries } = expr {
+            Ok(Expr::Map {
+                brace: brace.clone(),
+                entries: entries
+                    .iter()
+                    .map(|(key, value)| Ok((self.expression(key)?, self.expression(value)?)))
+                    .collect::<Result<Vec<(Expr, Expr)>>>()?,
+            })
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_index_expr(&mut self, expr: &Expr) -> Result<Expr> {
+        if let Expr::Index { object, bracket, index } = expr {
+            Ok(Expr::Index {
+                object: self.boxed(object)?,
+                bracket: bracket.clone(),
+                index: self.boxed(index)?,
+            })
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_set_index_expr(&mut self, expr: &Expr) -> Result<Expr> {
+        if let Expr::SetIndex { object, bracket, index, value } = expr {
+            Ok(Expr::SetIndex {
+                object: self.boxed(object)?,
+                bracket: bracket.clone(),
+                index: self.boxed(index)?,
+                value: self.boxed(value)?,
+            })
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_this_expr(&mut self, keyword: &Token) -> Result<Expr> {
+        Ok(Expr::This {
+            keyword: keyword.clone(),
+        })
+    }
+
+    // an interpolation of literals is a literal string
+    fn visit_interpolation_expr(&mut self, parts: &[Expr]) -> Result<Expr> {
+        let parts = parts
+            .iter()
+            .map(|part| self.expression(part))
+            .collect::<Result<Vec<Expr>>>()?;
+
+        let values = parts.iter().map(literal).collect::<Option<Vec<Object>>>();
+        match values {
+            Some(values) => Ok(Expr::Literal {
+                value: Literal::String(values.iter().map(|value| value.to_string()).collect()),
+            }),
+            None => Ok(Expr::Interpolation { parts }),
+        }
+    }
+}
+
+impl StmtVisitor<Vec<Stmt>> for Optimizer {
+    fn visit_print_statement(&mut self, statement: &Stmt) -> Result<Vec<Stmt>> {
+        if let Stmt::Print(expr) = statement {
+            Ok(vec![Stmt::Print(self.expression(expr)?)])
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_expression_statement(&mut self, statement: &Stmt) -> Result<Vec<Stmt>> {
+        if let Stmt::Expression(expr) = statement {
+            Ok(vec![Stmt::Expression(self.expression(expr)?)])
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_variable_statement(&mut self, statement: &Stmt) -> Result<Vec<Stmt>> {
+        if let Stmt::Variable { name, annotation, initializer } = statement {
+            let initializer = match initializer {
+                Some(initializer) => Some(self.expression(initializer)?),
+                None => None,
+            };
+
+            Ok(vec![Stmt::Variable {
+                name: name.clone(),
+                annotation: annotation.clone(),
+                initializer,
+            }])
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_block_statement(&mut self, statements: &[Stmt]) -> Result<Vec<Stmt>> {
+        Ok(vec![Stmt::Block {
+            statements: self.optimize_statements(statements)?,
+        }])
+    }
+
+    fn visit_if_statement(&mut self, statement: &Stmt) -> Result<Vec<Stmt>> {
+        if let Stmt::If {
+            condition,
+            then_branch,
+            else_branch,
+        } = statement
+        {
+            let condition = self.expression(condition)?;
+
+            if let (true, Some(value)) = (self.level >= 2, literal(&condition)) {
+                return match (value.is_truthy(), else_branch) {
+                    (true, _) => then_branch.accept(self),
+                    (false, Some(else_branch)) => else_branch.accept(self),
+                    (false, None) => Ok(Vec::new()),
+                };
+            }
+
+            let else_branch = match else_branch {
+                Some(else_branch) => Some(self.optimize_statement(else_branch)?),
+                None => None,
+            };
+
+            Ok(vec![Stmt::If {
+                condition,
+                then_branch: self.optimize_statement(then_branch)?,
+                else_branch,
+            }])
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_while_statement(&mut self, statement: &Stmt) -> Result<Vec<Stmt>> {
+        if let Stmt::While { condition, body } = statement {
+            let condition = self.expression(condition)?;
+
+            if let (true, Some(value)) = (self.level >= 2, literal(&condition)) {
+                if !value.is_truthy() {
+                    return Ok(Vec::new());
+                }
+            }
+
+            Ok(vec![Stmt::While {
+                condition,
+                body: self.optimize_statement(body)?,
+            }])
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_for_in_statement(&mut self, statement: &Stmt) -> Result<Vec<Stmt>> {
+        if let Stmt::ForIn { name, iterable, body } = statement {
+            Ok(vec![Stmt::ForIn {
+                name: name.clone(),
+                iterable: self.expression(iterable)?,
+                body: self.optimize_statement(body)?,
+            }])
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_function_statement(&mut self, statement: &Stmt) -> Result<Vec<Stmt>> {
+        if let Stmt::Function { name, params, param_types, defaults, rest, return_type, body, generator } = statement {
+            let defaults = defaults
+                .iter()
+                .map(|default| default.as_ref().map(|default| self.expression(default)).transpose())
+                .collect::<Result<Vec<Option<Expr>>>>()?;
+
+            Ok(vec![Stmt::Function {
+                name: name.clone(),
+                params: params.clone(),
+                param_types: param_types.clone(),
+                defaults,
+                rest: rest.clone(),
+                return_type: return_type.clone(),
+                body: self.optimize_statements(body)?,
+                generator: *generator,
+            }])
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_return_statement(&mut self, statement: &Stmt) -> Result<Vec<Stmt>> {
+        if let Stmt::Return { keyword, value } = statement {
+            let value = match value {
+                Some(value) => Some(self.expression(value)?),
+                None => None,
+            };
+
+            Ok(vec![Stmt::Return {
+                keyword: keyword.clone(),
+                value,
+            }])
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_break_statement(&mut self, keyword: &Token) -> Result<Vec<Stmt>> {
+        Ok(vec![Stmt::Break {
+            keyword: keyword.clone(),
+        }])
+    }
+
+    fn visit_yield_statement(&mut self, statement: &Stmt) -> Result<Vec<Stmt>> {
+        if let Stmt::Yield { keyword, value } = statement {
+            Ok(vec![Stmt::Yield {
+                keyword: keyword.clone(),
+                value: self.expression(value)?,
+            }])
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_throw_statement(&mut self, statement: &Stmt) -> Result<Vec<Stmt>> {
+        if let Stmt::Throw { keyword, value } = statement {
+            Ok(vec![Stmt::Throw {
+                keyword: keyword.clone(),
+                value: self.expression(value)?,
+            }])
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_match_statement(&mut self, statement: &Stmt) -> Result<Vec<Stmt>> {
+        if let Stmt::Match { keyword, value, arms } = statement {
+            let arms = arms
+                .iter()
+                .map(|arm| {
+                    let guard = match &arm.guard {
+                        Some(guard) => Some(self.expression(guard)?),
+                        None => None,
+                    };
+                    Ok(MatchArm {
+                        pattern: arm.pattern.clone(),
+                        guard,
+                        body: *self.optimize_statement(&arm.body)?,
+                    })
+                })
+                .collect::<Result<Vec<MatchArm>>>()?;
+
+            Ok(vec![Stmt::Match {
+                keyword: keyword.clone(),
+                value: self.expression(value)?,
+                arms,
+            }])
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_try_statement(&mut self, statement: &Stmt) -> Result<Vec<Stmt>> {
+        if let Stmt::Try { body, catch_clause, finally_body } = statement {
+            let catch_clause = match catch_clause {
+                Some((name, catch_body)) => Some((name.clone(), self.optimize_statements(catch_body)?)),
+                None => None,
+            };
+            let finally_body = match finally_body {
+                Some(finally_body) => Some(self.optimize_statements(finally_body)?),
+                None => None,
+            };
+
+            Ok(vec![Stmt::Try {
+                body: self.optimize_statements(body)?,
+                catch_clause,
+                finally_body,
+            }])
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_class_statement(&mut self, statement: &Stmt) -> Result<Vec<Stmt>> {
+        if let Stmt::Class { name, fields, methods } = statement {
+            let methods = methods
+                .iter()
+                .map(|method| Ok(Box::new(method.accept(self)?.remove(0))))
+                .collect::<Result<Vec<Box<Stmt>>>>()?;
+
+            Ok(vec![Stmt::Class {
+                name: name.clone(),
+                fields: fields.clone(),
+                methods,
+            }])
+        } else {
+            Err(Error::Runtime("should never happen!".to_string()))
+        }
+    }
+
+    fn visit_import_statement(&mut self, statement: &Stmt) -> Result<Vec<Stmt>> {
+        Ok(vec![statement.clone()])
+    }
+
+    fn visit_export_statement(&mut self, declaration: &Stmt) -> Result<Vec<Stmt>> {
+        Ok(declaration
+            .accept(self)?
+            .into_iter()
+            .map(|declaration| Stmt::Export {
+                declaration: Box::new(declaration),
+            })
+            .collect())
+    }
+}
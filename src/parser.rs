@@ -3,8 +3,9 @@
 use std::{error, fmt};
 
 use crate::ast::Expr;
+use crate::cst::SyntaxKind::{self, *};
 use crate::errors::{Error, Result};
-use crate::statement::Stmt;
+use crate::statement::{MatchArm, Pattern, Stmt};
 use crate::tokens::{Literal, Token, TokenType};
 
 use TokenType::*;
@@ -12,11 +13,25 @@ use TokenType::*;
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    // the token range of everything parsed so far, for building the CST
+    pub(crate) nodes: Vec<(SyntaxKind, usize, usize)>,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Parser { tokens, current: 0 }
+        Parser {
+            tokens,
+            current: 0,
+            nodes: Vec::new(),
+        }
+    }
+
+    // records that the tokens from `start` up to the current one make up a `kind`
+    fn node<T>(&mut self, kind: SyntaxKind, start: usize, result: Result<T>) -> Result<T> {
+        if result.is_ok() {
+            self.nodes.push((kind, start, self.current));
+        }
+        result
     }
 
     pub fn parse(&mut self) -> Result<Vec<Stmt>> {
@@ -32,50 +47,129 @@ impl Parser {
     fn declaration(&mut self) -> Result<Stmt> {
         // varDecl  → "var" IDENTIFIER ( "=" expression )? ";" ;
 
+        let start = self.current;
         let res = {
-            if self.matches(vec![CLASS]) {
-                self.class_declaration()
+            if self.contextual("import", &[STRING]) || self.contextual("from", &[STRING]) {
+                let import = self.import();
+                self.node(ImportStmt, start, import)
+            } else if self.contextual("export", &[VAR, FUN, CLASS]) {
+                self.advance();
+                let declaration = self.declaration();
+                self.node(ExportDecl, start, declaration).map(|declaration| Stmt::Export {
+                    declaration: Box::new(declaration),
+                })
+            } else if self.matches(vec![CLASS]) {
+                let class = self.class_declaration();
+                self.node(ClassDecl, start, class)
             } else if self.matches(vec![FUN]) {
-                self.function("function")
+                let generator = self.matches(vec![STAR]);
+                let function = self.function("function", generator);
+                self.node(FunDecl, start, function)
             } else if self.matches(vec![VAR]) {
-                self.var_declaration()
+                let var = self.var_declaration();
+                self.node(VarDecl, start, var)
             } else {
                 self.statement()
             }
         };
 
-        if res.is_err() {
+        if let Err(err) = &res {
+            // errors from `consume` and `primary` have already been reported
+            if let Error::Runtime(message) = err {
+                eprintln!("{}", message);
+            }
             self.synchronize();
-            Ok(Stmt::Print(Expr::Literal {
-                value: Literal::String("called sync".to_string()),
-            }))
-        } else {
-            res
         }
+
+        res
+    }
+
+    // import → "import" STRING ( "as" IDENTIFIER )? ";"
+    //        | "from" STRING "import" IDENTIFIER ( "," IDENTIFIER )* ";" ;
+    fn import(&mut self) -> Result<Stmt> {
+        let keyword = self.advance();
+        let path = self.consume(STRING, "Expect module path.")?;
+
+        let mut alias = None;
+        let mut names = Vec::new();
+        if keyword.lexeme == "from" {
+            let import = self.consume(IDENTIFIER, "Expect 'import' after module path.")?;
+            if import.lexeme != "import" {
+                return Err(error(&import, "Expect 'import' after module path."));
+            }
+            loop {
+                names.push(self.consume(IDENTIFIER, "Expect name to import.")?);
+                if !self.matches(vec![COMMA]) {
+                    break;
+                }
+            }
+        } else if self.contextual("as", &[IDENTIFIER]) {
+            self.advance();
+            alias = Some(self.consume(IDENTIFIER, "Expect module name after 'as'.")?);
+        }
+
+        self.consume(SEMICOLON, "Expect ';' after import.")?;
+
+        Ok(Stmt::Import {
+            keyword,
+            path,
+            alias,
+            names,
+        })
     }
 
     fn class_declaration(&mut self) -> Result<Stmt> {
         let name = self.consume(IDENTIFIER, "Expect a class name")?;
         self.consume(LEFT_BRACE, "Expect a '{' before class body.")?;
 
+        let mut fields = Vec::new();
         let mut methods = Vec::new();
 
         while !self.check(RIGHT_BRACE) && !self.is_at_end() {
-            methods.push(Box::new(self.function("method")?));
+            let start = self.current;
+            if self.check_next(COLON) {
+                let field = self.field();
+                fields.push(self.node(Field, start, field)?);
+                continue;
+            }
+
+            let method = self.function("method", false);
+            methods.push(Box::new(self.node(Method, start, method)?));
         }
 
         self.consume(RIGHT_BRACE, "Expect a '}' after class body.")?;
 
-        Ok(Stmt::Class { name, methods })
+        Ok(Stmt::Class { name, fields, methods })
+    }
+
+    fn field(&mut self) -> Result<(Token, Token)> {
+        let name = self.consume(IDENTIFIER, "Expect field name.")?;
+        self.consume(COLON, "Expect ':' after field name.")?;
+        let annotation = self.consume(IDENTIFIER, "Expect type name after ':'.")?;
+        self.consume(SEMICOLON, "Expect ';' after field type.")?;
+
+        Ok((name, annotation))
     }
 
-    fn function(&mut self, kind: &str) -> Result<Stmt> {
-        println!("inside function()");
+    // an optional `: Type` after a name or parameter list
+    fn annotation(&mut self) -> Result<Option<Token>> {
+        if self.matches(vec![COLON]) {
+            Ok(Some(self.consume(IDENTIFIER, "Expect type name after ':'.")?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn function(&mut self, kind: &str, generator: bool) -> Result<Stmt> {
         let name = self.consume(IDENTIFIER, &format!("Expect {} name.", kind))?;
 
         self.consume(LEFT_PAREN, &format!("Expect '(' after {} name.", kind))?;
 
+        // parameter → "..." IDENTIFIER | IDENTIFIER ( ":" type )? ( "=" expression )? ;
         let mut parameters = Vec::new();
+        let mut param_types = Vec::new();
+        let mut defaults = Vec::new();
+        let mut rest = None;
         if !self.check(RIGHT_PAREN) {
             loop {
                 if parameters.len() >= 255 {
@@ -85,7 +179,22 @@ impl Parser {
                     )));
                 }
 
-                parameters.push(self.consume(IDENTIFIER, "Expect parameter name.")?);
+                // the rest parameter comes last
+                if self.matches(vec![DOT_DOT_DOT]) {
+                    rest = Some(self.consume(IDENTIFIER, "Expect parameter name after '...'.")?);
+                    break;
+                }
+
+                let parameter = self.consume(IDENTIFIER, "Expect parameter name.")?;
+                param_types.push(self.annotation()?);
+                if self.matches(vec![EQUAL]) {
+                    defaults.push(Some(self.expression()?));
+                } else if defaults.iter().any(Option::is_some) {
+                    return Err(error(&parameter, "A parameter without a default can't follow one with a default."));
+                } else {
+                    defaults.push(None);
+                }
+                parameters.push(parameter);
 
                 if !self.matches(vec![COMMA]) {
                     break;
@@ -94,20 +203,25 @@ impl Parser {
         }
 
         self.consume(RIGHT_PAREN, "Expect ')' after parameters.")?;
+        let return_type = self.annotation()?;
         self.consume(LEFT_BRACE, &format!("Expect '{{' before {} body.", kind))?;
         let body = self.block()?;
 
         Ok(Stmt::Function {
             name,
             params: parameters,
+            param_types,
+            defaults,
+            rest,
+            return_type,
             body,
+            generator,
         })
     }
 
     fn var_declaration(&mut self) -> Result<Stmt> {
         let name = self.consume(IDENTIFIER, "Expect variable name.")?;
-
-        let mut initializer: Option<Expr> = None;
+        let annotation = self.annotation()?;
 
         let initializer = if self.matches(vec![EQUAL]) {
             Some(self.expression()?)
@@ -118,28 +232,58 @@ impl Parser {
         self.consume(SEMICOLON, "Expect ';' after variable declaration")?;
 
         Ok(Stmt::Variable {
-            name: name,
-            initializer: initializer,
+            name,
+            annotation,
+            initializer,
         })
     }
 
     fn statement(&mut self) -> Result<Stmt> {
+        let start = self.current;
         if self.matches(vec![PRINT]) {
-            self.print_statement()
+            let statement = self.print_statement();
+            self.node(PrintStmt, start, statement)
         } else if self.matches(vec![RETURN]) {
-            self.return_statement()
+            let statement = self.return_statement();
+            self.node(ReturnStmt, start, statement)
+        } else if self.matches(vec![BREAK]) {
+            let keyword = self.previous();
+            let statement = self
+                .consume(SEMICOLON, "Expect ';' after 'break'.")
+                .map(|_| Stmt::Break { keyword });
+            self.node(BreakStmt, start, statement)
+        } else if self.matches(vec![YIELD]) {
+            let statement = self.yield_statement();
+            self.node(YieldStmt, start, statement)
+        } else if self.matches(vec![THROW]) {
+            let statement = self.throw_statement();
+            self.node(ThrowStmt, start, statement)
+        } else if self.matches(vec![TRY]) {
+            let statement = self.try_statement();
+            self.node(TryStmt, start, statement)
+        } else if self.matches(vec![MATCH]) {
+            let statement = self.match_statement();
+            self.node(MatchStmt, start, statement)
         } else if self.matches(vec![WHILE]) {
-            self.while_statement()
+            let statement = self.while_statement();
+            self.node(WhileStmt, start, statement)
         } else if self.matches(vec![FOR]) {
-            self.for_statement()
+            if self.for_in_ahead() {
+                let statement = self.for_in_statement();
+                self.node(ForInStmt, start, statement)
+            } else {
+                let statement = self.for_statement();
+                self.node(ForStmt, start, statement)
+            }
         } else if (self.matches(vec![LEFT_BRACE])) {
-            Ok(Stmt::Block {
-                statements: self.block()?,
-            })
+            let statement = self.block().map(|statements| Stmt::Block { statements });
+            self.node(Block, start, statement)
         } else if (self.matches(vec![IF])) {
-            self.if_statement()
+            let statement = self.if_statement();
+            self.node(IfStmt, start, statement)
         } else {
-            self.expr_statement()
+            let statement = self.expr_statement();
+            self.node(ExprStmt, start, statement)
         }
     }
 
@@ -156,8 +300,169 @@ impl Parser {
         Ok(Stmt::Return { keyword, value })
     }
 
+    fn yield_statement(&mut self) -> Result<Stmt> {
+        let keyword = self.previous();
+        let value = self.expression()?;
+        self.consume(SEMICOLON, "Expect ';' after yielded value.")?;
+
+        Ok(Stmt::Yield { keyword, value })
+    }
+
+    fn throw_statement(&mut self) -> Result<Stmt> {
+        let keyword = self.previous();
+        let value = self.expression()?;
+        self.consume(SEMICOLON, "Expect ';' after thrown value.")?;
+
+        Ok(Stmt::Throw { keyword, value })
+    }
+
+    // try → "try" block ( "catch" "(" IDENTIFIER ")" block )? ( "finally" block )? ;
+    fn try_statement(&mut self) -> Result<Stmt> {
+        self.consume(LEFT_BRACE, "Expect '{' after 'try'.")?;
+        let body = self.block()?;
+
+        let mut catch_clause = None;
+        if self.matches(vec![CATCH]) {
+            self.consume(LEFT_PAREN, "Expect '(' after 'catch'.")?;
+            let name = self.consume(IDENTIFIER, "Expect name of the caught value.")?;
+            self.consume(RIGHT_PAREN, "Expect ')' after caught value.")?;
+            self.consume(LEFT_BRACE, "Expect '{' before catch body.")?;
+            catch_clause = Some((name, self.block()?));
+        }
+
+        let mut finally_body = None;
+        if self.matches(vec![FINALLY]) {
+            self.consume(LEFT_BRACE, "Expect '{' after 'finally'.")?;
+            finally_body = Some(self.block()?);
+        }
+
+        if catch_clause.is_none() && finally_body.is_none() {
+            self.consume(CATCH, "Expect 'catch' or 'finally' after try block.")?;
+        }
+
+        Ok(Stmt::Try {
+            body,
+            catch_clause,
+            finally_body,
+        })
+    }
+
+    // match → "match" "(" expression ")" "{" arm* "}" ;
+    // arm   → pattern ( "if" expression )? "=>" statement ","? ;
+    fn match_statement(&mut self) -> Result<Stmt> {
+        let keyword = self.previous();
+        self.consume(LEFT_PAREN, "Expect '(' after 'match'.")?;
+        let value = self.expression()?;
+        self.consume(RIGHT_PAREN, "Expect ')' after match value.")?;
+        self.consume(LEFT_BRACE, "Expect '{' before match arms.")?;
+
+        let mut arms = Vec::new();
+        while !self.check(RIGHT_BRACE) && !self.is_at_end() {
+            let start = self.current;
+            let arm = self.match_arm();
+            arms.push(self.node(SyntaxKind::MatchArm, start, arm)?);
+        }
+        self.consume(RIGHT_BRACE, "Expect '}' after match arms.")?;
+
+        Ok(Stmt::Match { keyword, value, arms })
+    }
+
+    fn match_arm(&mut self) -> Result<MatchArm> {
+        let pattern = self.pattern()?;
+        let guard = if self.matches(vec![IF]) {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+        self.consume(FAT_ARROW, "Expect '=>' after pattern.")?;
+        let body = self.statement()?;
+        self.matches(vec![COMMA]);
+
+        Ok(MatchArm { pattern, guard, body })
+    }
+
+    // pattern → single ( "|" single )* ;
+    fn pattern(&mut self) -> Result<Pattern> {
+        let start = self.current;
+        let mut alternatives = vec![self.single_pattern()?];
+        while self.matches(vec![PIPE]) {
+            alternatives.push(self.single_pattern()?);
+        }
+
+        let pattern = if alternatives.len() == 1 {
+            alternatives.remove(0)
+        } else {
+            Pattern::Or { alternatives }
+        };
+        self.node(SyntaxKind::Pattern, start, Ok(pattern))
+    }
+
+    // single → NUMBER | "-" NUMBER | STRING | "true" | "false" | "nil" | "_"
+    //        | IDENTIFIER ( "(" ( pattern ( "," pattern )* )? ")" )?
+    //        | "[" ( pattern ( "," pattern )* )? ( ","? "..." IDENTIFIER )? "]" ;
+    fn single_pattern(&mut self) -> Result<Pattern> {
+        if self.matches(vec![FALSE, TRUE, NIL, STRING, NUMBER]) {
+            let token = self.previous();
+            let value = match token.token_type {
+                FALSE => Literal::Boolean(false),
+                TRUE => Literal::Boolean(true),
+                NIL => Literal::None,
+                _ => token.literal.clone(),
+            };
+            return Ok(Pattern::Literal { token, value });
+        }
+        if self.matches(vec![MINUS]) {
+            let token = self.consume(NUMBER, "Expect number after '-' in pattern.")?;
+            let Literal::Number(number) = token.literal else {
+                return Err(Error::Parse {});
+            };
+            return Ok(Pattern::Literal {
+                token,
+                value: Literal::Number(-number),
+            });
+        }
+        if self.matches(vec![IDENTIFIER]) {
+            let name = self.previous();
+            if name.lexeme == "_" {
+                return Ok(Pattern::Wildcard);
+            }
+            if !self.matches(vec![LEFT_PAREN]) {
+                return Ok(Pattern::Binding { name });
+            }
+
+            let mut fields = Vec::new();
+            if !self.check(RIGHT_PAREN) {
+                loop {
+                    fields.push(self.pattern()?);
+                    if !self.matches(vec![COMMA]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(RIGHT_PAREN, "Expect ')' after field patterns.")?;
+            return Ok(Pattern::Class { name, fields });
+        }
+        if self.matches(vec![LEFT_BRACKET]) {
+            let mut elements = Vec::new();
+            let mut rest = None;
+            while !self.check(RIGHT_BRACKET) {
+                if self.matches(vec![DOT_DOT_DOT]) {
+                    rest = Some(self.consume(IDENTIFIER, "Expect name after '...'.")?);
+                    break;
+                }
+                elements.push(self.pattern()?);
+                if !self.matches(vec![COMMA]) {
+                    break;
+                }
+            }
+            self.consume(RIGHT_BRACKET, "Expect ']' after list pattern.")?;
+            return Ok(Pattern::List { elements, rest });
+        }
+
+        Err(error(self.peek(), "Expect pattern."))
+    }
+
     fn while_statement(&mut self) -> Result<Stmt> {
-        println!("while statement");
         self.consume(LEFT_PAREN, "Expect '(' after 'while'.")?;
         let condition = self.expression()?;
         self.consume(RIGHT_PAREN, "Expect ')' after condition.")?;
@@ -206,7 +511,7 @@ impl Parser {
             });
         }
 
-        if let None = condition {
+        if condition.is_none() {
             condition = Some(Expr::Literal {
                 value: Literal::Boolean(true),
             });
@@ -230,6 +535,32 @@ impl Parser {
         body
     }
 
+    // `(var name in`, `in` is only a keyword here
+    fn for_in_ahead(&mut self) -> bool {
+        let ahead = |offset: usize| self.tokens.get(self.current + offset);
+        ahead(0).is_some_and(|token| token.token_type == LEFT_PAREN)
+            && ahead(1).is_some_and(|token| token.token_type == VAR)
+            && ahead(2).is_some_and(|token| token.token_type == IDENTIFIER)
+            && ahead(3).is_some_and(|token| token.token_type == IDENTIFIER && token.lexeme == "in")
+    }
+
+    // forIn → "for" "(" "var" IDENTIFIER "in" expression ")" statement ;
+    fn for_in_statement(&mut self) -> Result<Stmt> {
+        self.consume(LEFT_PAREN, "Expect a '(' after 'for.'")?;
+        self.consume(VAR, "Expect 'var' in for-in loop.")?;
+        let name = self.consume(IDENTIFIER, "Expect variable name.")?;
+        self.consume(IDENTIFIER, "Expect 'in' after variable name.")?;
+        let iterable = self.expression()?;
+        self.consume(RIGHT_PAREN, "Expect ')' after for-in clause.")?;
+        let body = self.statement()?;
+
+        Ok(Stmt::ForIn {
+            name,
+            iterable,
+            body: Box::new(body),
+        })
+    }
+
     fn if_statement(&mut self) -> Result<Stmt> {
         self.consume(LEFT_PAREN, "Exepect '(' after 'if'.")?;
 
@@ -263,49 +594,103 @@ impl Parser {
     }
 
     fn print_statement(&mut self) -> Result<Stmt> {
-        let expr = self.expression();
+        let expr = self.expression()?;
         self.consume(SEMICOLON, "Expect ';' after value")?;
-        expr.map(Stmt::Print)
+        Ok(Stmt::Print(expr))
     }
 
     fn expr_statement(&mut self) -> Result<Stmt> {
-        let expr = self.expression();
+        let expr = self.expression()?;
         self.consume(SEMICOLON, "Expect ';' after expression")?;
-        expr.map(Stmt::Expression)
+        Ok(Stmt::Expression(expr))
     }
     fn expression(&mut self) -> Result<Expr> {
         self.assignment()
     }
 
     fn assignment(&mut self) -> Result<Expr> {
-        // assignment → IDENTIFIER "=" assignment | equality ;
+        // assignment → ( call "." )? IDENTIFIER "=" assignment | conditional ;
 
-        let expr = self.or();
+        let start = self.current;
+        let expr = self.conditional();
 
         if (self.matches(vec![EQUAL])) {
             let equals = self.previous();
             let value = self.assignment()?;
+            self.nodes.push((Assign, start, self.current));
             if let Ok(Expr::Variable { name }) = expr {
                 Ok(Expr::Assign {
                     name,
                     value: Box::new(value),
                 })
+            } else if let Ok(Expr::Get { object, name }) = expr {
+                Ok(Expr::Set {
+                    object,
+                    name,
+                    value: Box::new(value),
+                })
+            } else if let Ok(Expr::Index { object, bracket, index }) = expr {
+                Ok(Expr::SetIndex {
+                    object,
+                    bracket,
+                    index,
+                    value: Box::new(value),
+                })
             } else {
-                Err(Error::Runtime(format!("Invalid assignment target.")))
+                Err(Error::Runtime("Invalid assignment target.".to_string()))
+            }
+        } else if self.matches(vec![PLUS_EQUAL, MINUS_EQUAL, STAR_EQUAL, SLASH_EQUAL]) {
+            let operator = self.previous();
+            let value = self.assignment()?;
+            match expr {
+                Ok(target) if is_assignable(&target) => {
+                    let expr = Ok(Expr::Update {
+                        target: Box::new(target),
+                        operator,
+                        value: Box::new(value),
+                        postfix: false,
+                    });
+                    self.node(Update, start, expr)
+                }
+                _ => Err(Error::Runtime("Invalid assignment target.".to_string())),
             }
         } else {
             expr
         }
     }
 
+    fn conditional(&mut self) -> Result<Expr> {
+        // conditional → logic_or ( "?" expression ":" conditional )? ;
+
+        let start = self.current;
+        let condition = self.or()?;
+
+        if !self.matches(vec![QUESTION]) {
+            return Ok(condition);
+        }
+
+        let then_branch = self.expression()?;
+        self.consume(COLON, "Expect ':' after then branch of conditional expression.")?;
+        let else_branch = self.conditional()?;
+
+        let expr = Ok(Expr::Conditional {
+            condition: Box::new(condition),
+            then_branch: Box::new(then_branch),
+            else_branch: Box::new(else_branch),
+        });
+        self.node(Conditional, start, expr)
+    }
+
     fn or(&mut self) -> Result<Expr> {
+        let start = self.current;
         let mut expr = self.and()?;
         while self.matches(vec![OR]) {
             let operator = self.previous();
             let right = self.and()?;
+            self.nodes.push((Logical, start, self.current));
             expr = Expr::Logical {
                 left: Box::new(expr),
-                operator: operator,
+                operator,
                 right: Box::new(right),
             };
         }
@@ -314,14 +699,16 @@ impl Parser {
     }
 
     fn and(&mut self) -> Result<Expr> {
+        let start = self.current;
         let mut expr = self.equality()?;
 
         while self.matches(vec![AND]) {
             let operator = self.previous();
             let right = self.equality()?;
+            self.nodes.push((Logical, start, self.current));
             expr = Expr::Logical {
                 left: Box::new(expr),
-                operator: operator,
+                operator,
                 right: Box::new(right),
             };
         }
@@ -331,14 +718,16 @@ impl Parser {
 
     fn equality(&mut self) -> Result<Expr> {
         // equality → comparison ( ( "!=" | "==" ) comparison )* ;
+        let start = self.current;
         let mut expr = self.comparison()?;
 
         while self.matches(vec![BANG_EQUAL, EQUAL_EQUAL]) {
             let operator = self.previous();
             let right = self.comparison()?;
+            self.nodes.push((Binary, start, self.current));
             expr = Expr::Binary {
                 left: Box::new(expr),
-                operator: operator,
+                operator,
                 right: Box::new(right),
             }
         }
@@ -353,11 +742,13 @@ impl Parser {
     fn comparison(&mut self) -> Result<Expr> {
         // comparison     → term ( ( ">" | ">=" | "<" | "<=" ) term )* ;
 
-        let mut expr = self.term()?;
+        let start = self.current;
+        let mut expr = self.range()?;
 
         while self.matches(vec![GREATER, GREATER_EQUAL, LESS, LESS_EQUAL]) {
             let operator = self.previous();
-            let right = self.term()?;
+            let right = self.range()?;
+            self.nodes.push((Binary, start, self.current));
             expr = Expr::Binary {
                 left: Box::new(expr),
                 operator,
@@ -368,19 +759,40 @@ impl Parser {
         Ok(expr)
     }
 
+    fn range(&mut self) -> Result<Expr> {
+        // range → term ( ".." term )? ;
+
+        let start = self.current;
+        let expr = self.term()?;
+
+        if !self.matches(vec![DOT_DOT]) {
+            return Ok(expr);
+        }
+        let operator = self.previous();
+        let right = self.term()?;
+        self.nodes.push((Binary, start, self.current));
+        Ok(Expr::Binary {
+            left: Box::new(expr),
+            operator,
+            right: Box::new(right),
+        })
+    }
+
     // can we rewrite these using, say, a macro?
     // or better just a function pointer
     fn term(&mut self) -> Result<Expr> {
         // term   → factor ( ( "-" | "+" ) factor )* ;
 
+        let start = self.current;
         let mut expr = self.factor()?;
 
         while self.matches(vec![TokenType::MINUS, TokenType::PLUS]) {
             let operator = self.previous();
             let right = self.factor()?;
+            self.nodes.push((Binary, start, self.current));
             expr = Expr::Binary {
                 left: Box::new(expr),
-                operator: operator,
+                operator,
                 right: Box::new(right),
             };
         }
@@ -389,14 +801,16 @@ impl Parser {
     }
 
     fn factor(&mut self) -> Result<Expr> {
+        let start = self.current;
         let mut expr = self.unary()?;
 
         while self.matches(vec![SLASH, STAR]) {
             let operator = self.previous();
             let right = self.unary()?;
+            self.nodes.push((Binary, start, self.current));
             expr = Expr::Binary {
                 left: Box::new(expr),
-                operator: operator,
+                operator,
                 right: Box::new(right),
             };
         }
@@ -405,29 +819,75 @@ impl Parser {
     }
 
     fn unary(&mut self) -> Result<Expr> {
+        // unary → ( "!" | "-" | "++" | "--" ) unary | call ( "++" | "--" )? ;
+
+        let start = self.current;
+        if self.matches(vec![PLUS_PLUS, MINUS_MINUS]) {
+            let operator = self.previous();
+            let target = self.unary()?;
+
+            if is_assignable(&target) {
+                let expr = Ok(increment(target, operator, false));
+                return self.node(Update, start, expr);
+            }
+
+            // `--(3)` has always been a double negation
+            if operator.token_type == MINUS_MINUS {
+                let negate = Token::new(MINUS, "-".to_string(), Literal::None, operator.line, operator.unique_count);
+                let inner = Expr::Unary { operator: negate.clone(), right: Box::new(target) };
+                return Ok(Expr::Unary { operator: negate, right: Box::new(inner) });
+            }
+
+            return Err(Error::Runtime("Invalid assignment target.".to_string()));
+        }
         if self.matches(vec![BANG, MINUS]) {
             let operator = self.previous();
             let right = self.unary()?;
+            self.nodes.push((Unary, start, self.current));
             Ok(Expr::Unary {
-                operator: operator,
+                operator,
                 right: Box::new(right),
             })
         } else {
-            self.call()
+            let expr = self.call()?;
+            if !self.matches(vec![PLUS_PLUS, MINUS_MINUS]) {
+                return Ok(expr);
+            }
+
+            let operator = self.previous();
+            if !is_assignable(&expr) {
+                return Err(Error::Runtime("Invalid assignment target.".to_string()));
+            }
+            let expr = Ok(increment(expr, operator, true));
+            self.node(Update, start, expr)
         }
     }
 
     fn call(&mut self) -> Result<Expr> {
+        let start = self.current;
         let mut expr = self.primary();
 
         loop {
             if self.matches(vec![LEFT_PAREN]) {
-                expr = self.finish_call(expr?);
+                let call = self.finish_call(expr?);
+                expr = self.node(Call, start, call);
             } else if self.matches(vec![DOT]) {
                 let name = self.consume(IDENTIFIER, "Expect property name after '.'.")?;
+                self.nodes.push((Get, start, self.current));
                 expr = Ok(Expr::Get {
                     object: Box::new(expr?),
-                    name: name,
+                    name,
+                });
+            } else if self.matches(vec![LEFT_BRACKET]) {
+                let bracket = self.previous();
+                let object = expr?;
+                let index = self.expression()?;
+                self.consume(RIGHT_BRACKET, "Expect ']' after index.")?;
+                self.nodes.push((Index, start, self.current));
+                expr = Ok(Expr::Index {
+                    object: Box::new(object),
+                    bracket,
+                    index: Box::new(index),
                 });
             } else {
                 break;
@@ -437,19 +897,30 @@ impl Parser {
         expr
     }
 
+    // arguments → ( expression | IDENTIFIER ":" expression ) ( "," ... )* ;
     fn finish_call(&mut self, callee: Expr) -> Result<Expr> {
         let mut arguments: Vec<Expr> = Vec::new();
+        let mut named = Vec::new();
 
         if !self.check(RIGHT_PAREN) {
             loop {
-                if arguments.len() >= 255 {
+                if arguments.len() + named.len() >= 255 {
                     return Err(Error::Runtime(format!(
                         "{:?} Can't have more than 255 arguments.",
                         self.peek()
                     )));
                 }
 
-                arguments.push(self.expression()?);
+                if self.check(IDENTIFIER) && self.check_next(COLON) {
+                    let name = self.advance();
+                    self.advance();
+                    named.push((name, self.expression()?));
+                } else if !named.is_empty() {
+                    return Err(error(self.peek(), "Positional arguments can't follow named ones."));
+                } else {
+                    arguments.push(self.expression()?);
+                }
+
                 if !self.matches(vec![COMMA]) {
                     break;
                 }
@@ -462,43 +933,54 @@ impl Parser {
             callee: Box::new(callee),
             paren,
             arguments,
+            named,
         })
     }
 
     fn primary(&mut self) -> Result<Expr> {
         // primary        → NUMBER | STRING | "true" | "false" | "nil" | "(" expression ")" ;
 
-        if self.matches(vec![FALSE]) {
-            return Ok(Expr::Literal {
-                value: Literal::Boolean(false),
-            });
-        }
-        if self.matches(vec![TRUE]) {
-            return Ok(Expr::Literal {
-                value: Literal::Boolean(true),
-            });
-        }
-        if self.matches(vec![NIL]) {
-            return Ok(Expr::Literal {
-                value: Literal::None,
-            });
+        let start = self.current;
+        if self.matches(vec![FALSE, TRUE, NIL, STRING, NUMBER]) {
+            let value = match self.previous().token_type {
+                FALSE => Literal::Boolean(false),
+                TRUE => Literal::Boolean(true),
+                NIL => Literal::None,
+                _ => self.previous().literal,
+            };
+            return self.node(SyntaxKind::Literal, start, Ok(Expr::Literal { value }));
         }
-        if self.matches(vec![STRING, NUMBER]) {
-            return Ok(Expr::Literal {
-                value: self.previous().literal,
-            });
+        if self.matches(vec![INTERPOLATION]) {
+            let expr = self.interpolation();
+            return self.node(Interpolation, start, expr);
         }
         if self.matches(vec![LEFT_PAREN]) {
             let expr = self.expression()?;
             self.consume(RIGHT_PAREN, "Expect ')' after expression.");
-            return Ok(Expr::Grouping {
+            let expr = Ok(Expr::Grouping {
                 expression: Box::new(expr),
             });
+            return self.node(Grouping, start, expr);
+        }
+        if self.matches(vec![THIS]) {
+            let expr = Ok(Expr::This {
+                keyword: self.previous(),
+            });
+            return self.node(This, start, expr);
         }
         if self.matches(vec![IDENTIFIER]) {
-            return Ok(Expr::Variable {
+            let expr = Ok(Expr::Variable {
                 name: self.previous(),
             });
+            return self.node(Variable, start, expr);
+        }
+        if self.matches(vec![LEFT_BRACKET]) {
+            let expr = self.list();
+            return self.node(List, start, expr);
+        }
+        if self.matches(vec![LEFT_BRACE]) {
+            let expr = self.map();
+            return self.node(Map, start, expr);
         }
 
         // println!("current token: {}", self.peek());
@@ -506,17 +988,78 @@ impl Parser {
         Err(Error::Parse {})
     }
 
+    // list → "[" ( expression ( "," expression )* )? "]" ;
+    fn list(&mut self) -> Result<Expr> {
+        let bracket = self.previous();
+        let mut elements = Vec::new();
+
+        if !self.check(RIGHT_BRACKET) {
+            loop {
+                elements.push(self.expression()?);
+                if !self.matches(vec![COMMA]) {
+                    break;
+                }
+            }
+        }
+        self.consume(RIGHT_BRACKET, "Expect ']' after list elements.")?;
+
+        Ok(Expr::List { bracket, elements })
+    }
+
+    // map → "{" ( expression ":" expression ( "," expression ":" expression )* )? "}" ;
+    fn map(&mut self) -> Result<Expr> {
+        let brace = self.previous();
+        let mut entries = Vec::new();
+
+        if !self.check(RIGHT_BRACE) {
+            loop {
+                let key = self.expression()?;
+                self.consume(COLON, "Expect ':' after map key.")?;
+                entries.push((key, self.expression()?));
+                if !self.matches(vec![COMMA]) {
+                    break;
+                }
+            }
+        }
+        self.consume(RIGHT_BRACE, "Expect '}' after map entries.")?;
+
+        Ok(Expr::Map { brace, entries })
+    }
+
+    // the INTERPOLATION token has been matched, the string ends with a STRING token
+    fn interpolation(&mut self) -> Result<Expr> {
+        let mut parts = Vec::new();
+
+        loop {
+            let text = self.previous().literal;
+            if text != Literal::String(String::new()) {
+                parts.push(Expr::Literal { value: text });
+            }
+
+            parts.push(self.expression()?);
+
+            if !self.matches(vec![INTERPOLATION]) {
+                break;
+            }
+        }
+
+        let end = self.consume(STRING, "Expect '}' after interpolated expression.")?;
+        if end.literal != Literal::String(String::new()) {
+            parts.push(Expr::Literal { value: end.literal });
+        }
+
+        Ok(Expr::Interpolation { parts })
+    }
+
     fn consume(&mut self, _type: TokenType, error: &str) -> Result<Token> {
         if self.check(_type) {
             Ok(self.advance())
         } else {
-            let token = self.peek();
-            // todo: refactor this into a separate function or module
-            eprintln!("{} at {} {}", token.line, token.token_type, error);
-            Err(Error::Parse {})
+            Err(self::error(self.peek(), error))
         }
     }
 
+
     fn matches(&mut self, types: Vec<TokenType>) -> bool {
         for _type in types {
             if self.check(_type) {
@@ -543,6 +1086,21 @@ impl Parser {
         }
     }
 
+    // `import`, `from`, `as` and `export` are only keywords in front of these tokens, so
+    // they still work as names everywhere else
+    fn contextual(&mut self, word: &str, followed_by: &[TokenType]) -> bool {
+        self.check(IDENTIFIER)
+            && self.peek().lexeme == word
+            && self
+                .tokens
+                .get(self.current + 1)
+                .is_some_and(|token| followed_by.contains(&token.token_type))
+    }
+
+    fn check_next(&mut self, token_type: TokenType) -> bool {
+        self.tokens.get(self.current + 1).is_some_and(|token| token.token_type == token_type)
+    }
+
     fn advance(&mut self) -> Token {
         if !self.is_at_end() {
             self.current += 1;
@@ -562,7 +1120,7 @@ impl Parser {
 
             // we assume this is the start of a statement
             match self.peek().token_type {
-                CLASS | FUN | VAR | FOR | IF | WHILE | PRINT | RETURN => {
+                CLASS | FUN | VAR | FOR | IF | MATCH | WHILE | PRINT | RETURN | BREAK | YIELD | THROW | TRY => {
                     return;
                 }
                 _ => {}
@@ -572,3 +1130,25 @@ impl Parser {
         }
     }
 }
+
+fn is_assignable(expr: &Expr) -> bool {
+    matches!(expr, Expr::Variable { .. } | Expr::Get { .. } | Expr::Index { .. })
+}
+
+// `++a` adds one, `a++` too but gives back the old value
+fn increment(target: Expr, operator: Token, postfix: bool) -> Expr {
+    Expr::Update {
+        target: Box::new(target),
+        operator,
+        value: Box::new(Expr::Literal {
+            value: Literal::Number(1.0),
+        }),
+        postfix,
+    }
+}
+
+// reports a syntax error at the token, the caller decides whether to go on
+fn error(token: &Token, message: &str) -> Error {
+    eprintln!("{} at {} {}", token.line, token.token_type, message);
+    Error::Parse {}
+}
@@ -0,0 +1,176 @@
+use crate::line_editor::{LineEditor, ReadLine};
+use crate::lox::Lox;
+use std::io;
+
+const HELP: &str = "\
+Enter Lox statements or expressions, the value of an expression is printed.
+Input continues on the next line until all parentheses and braces are closed.
+
+  :help         show this message
+  :reset        forget all definitions
+  :load <file>  run a file in this session
+  :env          list the global variables
+  :quit         leave the REPL (or press Ctrl-D)";
+
+pub enum Step {
+    Continue,
+    Quit,
+}
+
+pub struct Repl<'a> {
+    lox: &'a mut Lox,
+    buffer: String,
+}
+
+// whether every parenthesis, brace and bracket is closed, ignoring strings and comments
+pub fn is_complete(source: &str) -> bool {
+    let mut depth = 0;
+    let mut chars = source.chars().peekable();
+    // the last character, and whether it was an `r` starting a word
+    let mut previous = ' ';
+    let mut raw = false;
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '(' | '{' | '[' => depth += 1,
+            ')' | '}' | ']' => depth -= 1,
+            // r"..." has no escapes, it ends at the next quote
+            '"' if raw => loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(_) => {}
+                    None => return false,
+                }
+            },
+            '"' => loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => {
+                        chars.next();
+                    }
+                    Some(_) => {}
+                    None => return false,
+                }
+            },
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.peek().is_some_and(|&ch| ch != '\n') {
+                    chars.next();
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut comments = 1;
+                while comments > 0 {
+                    match (chars.next(), chars.peek()) {
+                        (Some('/'), Some('*')) => {
+                            chars.next();
+                            comments += 1;
+                        }
+                        (Some('*'), Some('/')) => {
+                            chars.next();
+                            comments -= 1;
+                        }
+                        (Some(_), _) => {}
+                        (None, _) => return false,
+                    }
+                }
+            }
+            _ => {}
+        }
+        raw = ch == 'r' && !(previous.is_alphanumeric() || previous == '_');
+        previous = ch;
+    }
+
+    depth <= 0
+}
+
+impl<'a> Repl<'a> {
+    pub fn new(lox: &'a mut Lox) -> Self {
+        Repl {
+            lox,
+            buffer: String::new(),
+        }
+    }
+
+    pub fn prompt(&self) -> &str {
+        if self.buffer.is_empty() {
+            "> "
+        } else {
+            "... "
+        }
+    }
+
+    pub fn run(&mut self) -> io::Result<()> {
+        let mut editor = LineEditor::new(LineEditor::default_history_path());
+
+        loop {
+            match editor.read_line(self.prompt())? {
+                ReadLine::Line(line) => {
+                    editor.add_history(&line);
+                    if let Step::Quit = self.feed(&line) {
+                        break;
+                    }
+                }
+                // drops whatever was typed so far
+                ReadLine::Interrupted => self.buffer.clear(),
+                ReadLine::Eof => break,
+            }
+        }
+
+        Ok(())
+    }
+
+    // handles one line of input, running it once the entry is complete
+    pub fn feed(&mut self, line: &str) -> Step {
+        if self.buffer.is_empty() && line.trim_start().starts_with(':') {
+            return self.command(line.trim());
+        }
+
+        self.buffer.push_str(line);
+        self.buffer.push('\n');
+
+        if !is_complete(&self.buffer) {
+            return Step::Continue;
+        }
+
+        let mut source = std::mem::take(&mut self.buffer);
+        let trimmed = source.trim_end();
+        if trimmed.is_empty() {
+            return Step::Continue;
+        }
+
+        // a bare expression does not need its semicolon
+        if !trimmed.ends_with(';') && !trimmed.ends_with('}') {
+            source = format!("{};", trimmed);
+        }
+
+        self.lox.run_interactive(&source);
+        Step::Continue
+    }
+
+    fn command(&mut self, line: &str) -> Step {
+        let (command, argument) = match line.split_once(char::is_whitespace) {
+            Some((command, argument)) => (command, argument.trim()),
+            None => (line, ""),
+        };
+
+        match command {
+            ":help" => self.lox.output().print(&HELP),
+            ":reset" => self.lox.reset(),
+            ":load" if !argument.is_empty() => {
+                if let Err(err) = self.lox.run_file(argument) {
+                    eprintln!("Could not load '{}': {}", argument, err);
+                }
+            }
+            ":env" => {
+                for (name, value) in self.lox.globals() {
+                    self.lox.output().print(&format!("{} = {}", name, value));
+                }
+            }
+            ":quit" | ":q" => return Step::Quit,
+            _ => eprintln!("Unknown command '{}', try :help", line),
+        }
+
+        Step::Continue
+    }
+}
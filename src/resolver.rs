@@ -1,38 +1,128 @@
 #![allow(dead_code, unused)]
 use crate::ast::{Expr, Visitor as ExprVisitor};
-use crate::errors::{Error, Result};
+use crate::errors::{Error, ResolveError, Result};
 use crate::function::Function;
 use crate::interpreter::Interpreter;
-use crate::statement::{self, Stmt, Visitor as StmtVisitor};
-use crate::tokens::Token;
+use crate::lint::{Lints, Rule, Warning};
+use crate::statement::{self, Pattern, Stmt, Visitor as StmtVisitor};
+use crate::tokens::{Literal, Token, TokenType};
 use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 pub enum FunctionType {
     None,
     Function,
+    Initializer,
+    Method,
+    Generator,
+}
+
+#[derive(Debug, Clone)]
+pub enum ClassType {
+    None,
+    Class,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LocalKind {
+    Variable,
+    Function,
+    Class,
+    Parameter,
+    This,
+}
+
+// what the resolver knows about a local variable, the lints use the rest
+#[derive(Debug, Clone)]
+struct Local {
+    name: Token,
+    kind: LocalKind,
+    defined: bool,
+    read: bool,
+    // the first assignment after the declaration
+    assigned: Option<Token>,
 }
 
 pub struct Resolver<'a> {
     interpreter: &'a mut Interpreter,
-    scopes: Vec<HashMap<String, bool>>,
+    scopes: Vec<HashMap<String, Local>>,
     current_function: FunctionType,
+    current_class: ClassType,
+    // how many loops the current function is nested in, for `break`
+    loops: usize,
+    // and how many try statements, a generator can't be suspended inside one
+    tries: usize,
+    errors: Vec<ResolveError>,
+    lints: Lints,
+    warnings: Vec<Warning>,
 }
 
 impl<'a> Resolver<'a> {
     pub fn new(interpreter: &'a mut Interpreter) -> Self {
+        Self::with_lints(interpreter, Lints::default())
+    }
+
+    pub fn with_lints(interpreter: &'a mut Interpreter, lints: Lints) -> Self {
         Resolver {
             interpreter,
             scopes: Vec::default(),
             current_function: FunctionType::None,
+            current_class: ClassType::None,
+            loops: 0,
+            tries: 0,
+            errors: Vec::new(),
+            lints,
+            warnings: Vec::new(),
         }
     }
 
+    // static errors do not stop the resolver, they are collected here
+    pub fn errors(&self) -> &[ResolveError] {
+        &self.errors
+    }
+
+    // the warnings of the enabled lints, in the order they were found
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    fn warn(&mut self, rule: Rule, token: &Token, message: String) {
+        if self.lints.is_enabled(rule) {
+            self.warnings.push(Warning {
+                rule,
+                line: token.line,
+                lexeme: token.lexeme.clone(),
+                message,
+            });
+        }
+    }
+
+    fn error(&mut self, token: &Token, message: &str) {
+        self.errors.push(ResolveError {
+            line: token.line,
+            lexeme: token.lexeme.clone(),
+            message: message.to_string(),
+        });
+    }
+
     fn begin_scope(&mut self) {
         self.scopes.push(HashMap::default());
     }
-    pub fn resolve_statements(&mut self, statements: &Vec<Stmt>) -> Result<()> {
+    pub fn resolve_statements(&mut self, statements: &[Stmt]) -> Result<()> {
+        let mut returned: Option<&Token> = None;
         for statement in statements.iter() {
+            // only the first statement after a return, throw or break is reported
+            if let Some(keyword) = returned.take() {
+                self.warn(
+                    Rule::UnreachableCode,
+                    keyword,
+                    format!("Code after '{}' is never executed.", keyword.lexeme),
+                );
+            }
+            if let Stmt::Return { keyword, .. } | Stmt::Throw { keyword, .. } | Stmt::Break { keyword } = statement {
+                returned = Some(keyword);
+            }
+
             self.resolve_statement(statement)?;
         }
         Ok(())
@@ -48,45 +138,172 @@ impl<'a> Resolver<'a> {
     }
 
     fn resolve_function(&mut self, function: &Stmt, function_type: FunctionType) -> Result<()> {
-        if let Stmt::Function { name, params, body } = function {
+        if let Stmt::Function { name, params, defaults, rest, body, .. } = function {
             let enclosing_function = self.current_function.clone();
             self.current_function = function_type;
+            // a break can't leave the function it is in
+            let enclosing_loops = std::mem::take(&mut self.loops);
+            let enclosing_tries = std::mem::take(&mut self.tries);
 
             self.begin_scope();
 
-            // parameters
-            for param in params.iter() {
-                self.declare(param);
+            // parameters, a default can use the ones before it
+            for (param, default) in params.iter().zip(defaults) {
+                if let Some(default) = default {
+                    self.resolve_expression(default)?;
+                }
+                self.declare_as(param, LocalKind::Parameter);
                 self.define(param);
             }
+            if let Some(rest) = rest {
+                self.declare_as(rest, LocalKind::Parameter);
+                self.define(rest);
+            }
             self.resolve_statements(body)?;
             self.end_scope();
             self.current_function = enclosing_function;
+            self.loops = enclosing_loops;
+            self.tries = enclosing_tries;
 
             Ok(())
         } else {
-            Err(Error::Runtime(format!(
-                "resolve_function:: should never happend"
-            )))
+            Err(Error::Runtime("resolve_function:: should never happend".to_string()))
         }
     }
 
+    fn resolve_try(
+        &mut self,
+        body: &[Stmt],
+        catch_clause: &Option<(Token, Vec<Stmt>)>,
+        finally_body: &Option<Vec<Stmt>>,
+    ) -> Result<()> {
+        self.visit_block_statement(body)?;
+
+        // the caught value lives in a scope around the catch body
+        if let Some((name, catch_body)) = catch_clause {
+            self.begin_scope();
+            self.declare(name);
+            self.define(name);
+            self.resolve_statements(catch_body)?;
+            self.end_scope();
+        }
+
+        if let Some(finally_body) = finally_body {
+            self.visit_block_statement(finally_body)?;
+        }
+        Ok(())
+    }
+
+    // declares the names a pattern binds in the current scope
+    fn resolve_pattern(&mut self, pattern: &Pattern) -> Result<()> {
+        match pattern {
+            Pattern::Literal { .. } | Pattern::Wildcard => {}
+            Pattern::Binding { name } => {
+                self.declare(name);
+                self.define(name);
+            }
+            Pattern::Or { alternatives } => {
+                for alternative in alternatives {
+                    if let Some(name) = alternative.bindings().first() {
+                        self.error(name, "Can't bind names in alternative patterns.");
+                    }
+                    self.resolve_pattern(alternative)?;
+                }
+            }
+            Pattern::Class { name, fields } => {
+                self.visit_variable_expr(name)?;
+                for field in fields {
+                    self.resolve_pattern(field)?;
+                }
+            }
+            Pattern::List { elements, rest } => {
+                for element in elements {
+                    self.resolve_pattern(element)?;
+                }
+                if let Some(rest) = rest {
+                    self.declare(rest);
+                    self.define(rest);
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn end_scope(&mut self) {
-        self.scopes.pop();
+        let Some(scope) = self.scopes.pop() else {
+            return;
+        };
+
+        let mut locals: Vec<Local> = scope.into_values().collect();
+        locals.sort_by_key(|local| local.name.unique_count);
+        for local in locals {
+            self.unused(local);
+        }
+    }
+
+    // warns about a local that went out of scope without ever being read
+    fn unused(&mut self, local: Local) {
+        if local.read || local.name.lexeme.starts_with('_') {
+            return;
+        }
+
+        let name = &local.name.lexeme;
+        if let Some(assignment) = &local.assigned {
+            let message = format!("Value assigned to '{}' is never read.", name);
+            self.warn(Rule::UnreadAssignment, assignment, message);
+            return;
+        }
+
+        let (rule, message) = match local.kind {
+            LocalKind::Variable => (Rule::UnusedVariable, format!("Local variable '{}' is never used.", name)),
+            LocalKind::Function => (Rule::UnusedVariable, format!("Local function '{}' is never used.", name)),
+            LocalKind::Class => (Rule::UnusedVariable, format!("Local class '{}' is never used.", name)),
+            LocalKind::Parameter => (Rule::UnusedParameter, format!("Parameter '{}' is never used.", name)),
+            LocalKind::This => return,
+        };
+        self.warn(rule, &local.name, message);
     }
 
     fn declare(&mut self, name: &Token) {
-        if let Some(scope) = self.scopes.last_mut() {
-            if scope.contains_key(&name.lexeme) {
-                // println!("Variable with name {} already declared in this scope.", name.lexeme);
-                panic!("aborting");
-            }
+        self.declare_as(name, LocalKind::Variable);
+    }
+
+    fn declare_as(&mut self, name: &Token, kind: LocalKind) {
+        let Some((scope, enclosing)) = self.scopes.split_last_mut() else {
+            return;
+        };
 
-            // println!("DECLARING variable {:?}", name);
-            scope.insert(name.lexeme.clone(), false);
+        if scope.contains_key(&name.lexeme) {
+            self.error(name, "Already a variable with this name in this scope.");
+            return;
+        }
+
+        scope.insert(
+            name.lexeme.clone(),
+            Local {
+                name: name.clone(),
+                kind,
+                defined: false,
+                read: false,
+                assigned: None,
+            },
+        );
+
+        // globals are not locals, shadowing them is fine
+        if let Some(outer) = enclosing.iter().rev().find_map(|scope| scope.get(&name.lexeme)) {
+            let message = format!(
+                "'{}' shadows the local declared on line {}.",
+                name.lexeme, outer.name.line
+            );
+            self.warn(Rule::Shadowing, name, message);
         }
     }
 
+    // the innermost local with this name
+    fn local_mut(&mut self, name: &Token) -> Option<&mut Local> {
+        self.scopes.iter_mut().rev().find_map(|scope| scope.get_mut(&name.lexeme))
+    }
+
     fn resolve_local(&mut self, name: &Token) {
         // println!("resolve_local -- {:?}", name);
         for (i, scope) in self.scopes.iter().rev().enumerate() {
@@ -102,12 +319,37 @@ impl<'a> Resolver<'a> {
 
     fn define(&mut self, name: &Token) {
         // println!("\tDEFINING {:?}", name);
-        if let Some(scope) = self.scopes.last_mut() {
-            scope.insert(name.lexeme.clone(), true);
+        if let Some(local) = self.scopes.last_mut().and_then(|scope| scope.get_mut(&name.lexeme)) {
+            local.defined = true;
         }
     }
 }
 
+fn is_comparison(operator: &Token) -> bool {
+    matches!(
+        operator.token_type,
+        TokenType::EQUAL_EQUAL
+            | TokenType::BANG_EQUAL
+            | TokenType::LESS
+            | TokenType::LESS_EQUAL
+            | TokenType::GREATER
+            | TokenType::GREATER_EQUAL
+    )
+}
+
+fn ungroup(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Grouping { expression } => ungroup(expression),
+        _ => expr,
+    }
+}
+
+// both sides are literals so the result is always the same, `x == x` isn't
+// flagged since it's false when x is NaN
+fn is_constant_comparison(left: &Expr, right: &Expr) -> bool {
+    matches!((ungroup(left), ungroup(right)), (Expr::Literal { .. }, Expr::Literal { .. }))
+}
+
 impl<'a> ExprVisitor<()> for Resolver<'a> {
     fn visit_binary_expr(
         &mut self,
@@ -115,6 +357,14 @@ impl<'a> ExprVisitor<()> for Resolver<'a> {
         operator: &crate::tokens::Token,
         right: &Expr,
     ) -> crate::errors::Result<()> {
+        if is_comparison(operator) && is_constant_comparison(left, right) {
+            self.warn(
+                Rule::ConstantComparison,
+                operator,
+                "This comparison always has the same result.".to_string(),
+            );
+        }
+
         self.resolve_expression(left)?;
         self.resolve_expression(right)
     }
@@ -124,18 +374,17 @@ impl<'a> ExprVisitor<()> for Resolver<'a> {
             callee,
             paren,
             arguments,
+            named,
         } = expr
         {
-            self.resolve_expression(&callee)?;
-            for arg in arguments.iter() {
+            self.resolve_expression(callee)?;
+            for arg in arguments.iter().chain(named.iter().map(|(_, arg)| arg)) {
                 self.resolve_expression(arg)?;
             }
 
             Ok(())
         } else {
-            Err(Error::Runtime(format!(
-                "resolver::visit_call_expr - should never happend"
-            )))
+            Err(Error::Runtime("resolver::visit_call_expr - should never happend".to_string()))
         }
     }
 
@@ -158,7 +407,39 @@ impl<'a> ExprVisitor<()> for Resolver<'a> {
             self.resolve_expression(left)?;
             self.resolve_expression(right)
         } else {
-            Err(Error::Runtime(format!("should never happend")))
+            Err(Error::Runtime("should never happend".to_string()))
+        }
+    }
+
+    fn visit_conditional_expr(&mut self, expr: &Expr) -> crate::errors::Result<()> {
+        if let Expr::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        } = expr
+        {
+            self.resolve_expression(condition)?;
+            self.resolve_expression(then_branch)?;
+            self.resolve_expression(else_branch)
+        } else {
+            Err(Error::Runtime("should never happend".to_string()))
+        }
+    }
+
+    fn visit_update_expr(&mut self, expr: &Expr) -> crate::errors::Result<()> {
+        if let Expr::Update { target, value, .. } = expr {
+            self.resolve_expression(value)?;
+            // `a += 1` writes to a local like `a = a + 1` but doesn't count as reading it
+            if let Expr::Variable { name } = target.as_ref() {
+                self.resolve_local(name);
+                if let Some(local) = self.local_mut(name) {
+                    local.assigned.get_or_insert_with(|| name.clone());
+                }
+                return Ok(());
+            }
+            self.resolve_expression(target)
+        } else {
+            Err(Error::Runtime("should never happend".to_string()))
         }
     }
 
@@ -175,24 +456,30 @@ impl<'a> ExprVisitor<()> for Resolver<'a> {
             self.resolve_expression(value)?;
             self.resolve_local(name);
 
+            if let Some(local) = self.local_mut(name) {
+                local.assigned.get_or_insert_with(|| name.clone());
+            }
+
             Ok(())
         } else {
-            Err(Error::Runtime(format!("should never happen")))
+            Err(Error::Runtime("should never happen".to_string()))
         }
     }
 
     fn visit_variable_expr(&mut self, name: &crate::tokens::Token) -> crate::errors::Result<()> {
         // println!("visit_varibale_expr:: name = {:?}", name);
-        if let Some(scope) = self.scopes.last_mut() {
-            if let Some(false) = scope.get(&name.lexeme) {
-                return Err(Error::Runtime(format!(
-                    "Can't read local variable in its own initializer."
-                )));
+        if let Some(scope) = self.scopes.last() {
+            if scope.get(&name.lexeme).is_some_and(|local| !local.defined) {
+                self.error(name, "Can't read local variable in its own initializer.");
             }
         }
 
         self.resolve_local(name);
 
+        if let Some(local) = self.local_mut(name) {
+            local.read = true;
+        }
+
         Ok(())
     }
 
@@ -201,13 +488,81 @@ impl<'a> ExprVisitor<()> for Resolver<'a> {
         if let Expr::Get { object, name } = expr {
             self.resolve_expression(object)
         } else {
-            Err(Error::Runtime(format!("should never happen")))
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_set_expr(&mut self, expr: &Expr) -> Result<()> {
+        if let Expr::Set { object, name, value } = expr {
+            self.resolve_expression(value)?;
+            self.resolve_expression(object)
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_interpolation_expr(&mut self, parts: &[Expr]) -> Result<()> {
+        for part in parts {
+            self.resolve_expression(part)?;
+        }
+        Ok(())
+    }
+
+    fn visit_list_expr(&mut self, expr: &Expr) -> Result<()> {
+        if let Expr::List { elements, .. } = expr {
+            for element in elements {
+                self.resolve_expression(element)?;
+            }
+            Ok(())
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_map_expr(&mut self, expr: &Expr) -> Result<()> {
+        if let Expr::Map { entries, .. } = expr {
+            for (key, value) in entries {
+                self.resolve_expression(key)?;
+                self.resolve_expression(value)?;
+            }
+            Ok(())
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_index_expr(&mut self, expr: &Expr) -> Result<()> {
+        if let Expr::Index { object, index, .. } = expr {
+            self.resolve_expression(object)?;
+            self.resolve_expression(index)
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
         }
     }
+
+    fn visit_set_index_expr(&mut self, expr: &Expr) -> Result<()> {
+        if let Expr::SetIndex { object, index, value, .. } = expr {
+            self.resolve_expression(object)?;
+            self.resolve_expression(index)?;
+            self.resolve_expression(value)
+        } else {
+            Err(Error::Runtime("should never happen".to_string()))
+        }
+    }
+
+    fn visit_this_expr(&mut self, keyword: &Token) -> Result<()> {
+        if let ClassType::None = self.current_class {
+            self.error(keyword, "Can't use 'this' outside of a class.");
+            return Ok(());
+        }
+
+        self.resolve_local(keyword);
+        Ok(())
+    }
 }
 
 impl<'a> StmtVisitor<()> for Resolver<'a> {
-    fn visit_block_statement(&mut self, statements: &Vec<Stmt>) -> crate::errors::Result<()> {
+    fn visit_block_statement(&mut self, statements: &[Stmt]) -> crate::errors::Result<()> {
         self.begin_scope();
         self.resolve_statements(statements)?;
         self.end_scope();
@@ -219,17 +574,22 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
         if let Stmt::Expression(expr) = statement {
             self.resolve_expression(expr)
         } else {
-            Err(Error::Runtime(format!("should never happen!")))
+            Err(Error::Runtime("should never happen!".to_string()))
         }
     }
 
     fn visit_function_statement(&mut self, statement: &Stmt) -> crate::errors::Result<()> {
-        if let Stmt::Function { name, params, body } = statement {
-            self.declare(name);
+        if let Stmt::Function { name, generator, .. } = statement {
+            self.declare_as(name, LocalKind::Function);
             self.define(name);
-            self.resolve_function(statement, FunctionType::Function)
+            let function_type = if *generator {
+                FunctionType::Generator
+            } else {
+                FunctionType::Function
+            };
+            self.resolve_function(statement, function_type)
         } else {
-            Err(Error::Runtime(format!("should never happen!")))
+            Err(Error::Runtime("should never happen!".to_string()))
         }
     }
 
@@ -250,7 +610,7 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
                 Ok(())
             }
         } else {
-            Err(Error::Runtime(format!("should never happen!")))
+            Err(Error::Runtime("should never happen!".to_string()))
         }
     }
 
@@ -258,62 +618,197 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
         if let Stmt::Print(expr) = statement {
             self.resolve_expression(expr)
         } else {
-            Err(Error::Runtime(format!("should never happen!")))
+            Err(Error::Runtime("should never happen!".to_string()))
         }
     }
 
     fn visit_return_statement(&mut self, statement: &Stmt) -> crate::errors::Result<()> {
-        if let FunctionType::None = self.current_function {
-            return Err(Error::Runtime(format!("Returning outside of function.")));
-        }
-
         if let Stmt::Return { keyword, value } = statement {
+            if let FunctionType::None = self.current_function {
+                self.error(keyword, "Can't return from top-level code.");
+            }
+
             if let Some(expr) = value {
+                match self.current_function {
+                    FunctionType::Initializer => self.error(keyword, "Can't return a value from an initializer."),
+                    FunctionType::Generator => self.error(keyword, "Can't return a value from a generator."),
+                    _ => {}
+                }
                 self.resolve_expression(expr)
             } else {
                 Ok(())
             }
         } else {
-            Err(Error::Runtime(format!("should never happen!")))
+            Err(Error::Runtime("should never happen!".to_string()))
         }
     }
 
     fn visit_variable_statement(&mut self, statement: &Stmt) -> crate::errors::Result<()> {
         // println!("visit_variable_statement {:?}", statement);
-        if let Stmt::Variable { name, initializer } = statement {
+        if let Stmt::Variable { name, initializer, .. } = statement {
             self.declare(name);
 
             // println!("init = {:?}", initializer);
 
             if let Some(initializer) = initializer {
-                self.resolve_expression(initializer);
+                self.resolve_expression(initializer)?;
             }
 
             self.define(name);
 
             Ok(())
         } else {
-            Err(Error::Runtime(format!("This should never happen!")))
+            Err(Error::Runtime("This should never happen!".to_string()))
         }
     }
 
     fn visit_while_statement(&mut self, statement: &Stmt) -> crate::errors::Result<()> {
         if let Stmt::While { condition, body } = statement {
             self.resolve_expression(condition)?;
-            self.resolve_statement(body)
+            self.loops += 1;
+            let resolved = self.resolve_statement(body);
+            self.loops -= 1;
+            resolved
         } else {
-            Err(Error::Runtime(format!("This should never happen!")))
+            Err(Error::Runtime("This should never happen!".to_string()))
         }
     }
 
-    fn visit_class_statement(&mut self, statement: &Stmt) -> Result<()> {
-        if let Stmt::Class { name, methods } = statement {
+    fn visit_for_in_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::ForIn { name, iterable, body } = statement {
+            self.resolve_expression(iterable)?;
+
+            // the loop variable is in a scope of its own, around the body
+            self.begin_scope();
             self.declare(name);
             self.define(name);
+            self.loops += 1;
+            let resolved = self.resolve_statement(body);
+            self.loops -= 1;
+            self.end_scope();
+            resolved
+        } else {
+            Err(Error::Runtime("This should never happen!".to_string()))
+        }
+    }
 
+    fn visit_break_statement(&mut self, keyword: &Token) -> Result<()> {
+        if self.loops == 0 {
+            self.error(keyword, "Can't use 'break' outside of a loop.");
+        }
+        Ok(())
+    }
+
+    fn visit_yield_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::Yield { keyword, value } = statement {
+            if !matches!(self.current_function, FunctionType::Generator) {
+                self.error(keyword, "Can't use 'yield' outside of a generator.");
+            } else if self.tries > 0 {
+                self.error(keyword, "Can't use 'yield' inside a try statement.");
+            }
+            self.resolve_expression(value)
+        } else {
+            Err(Error::Runtime("This should never happen!".to_string()))
+        }
+    }
+
+    fn visit_throw_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::Throw { value, .. } = statement {
+            self.resolve_expression(value)
+        } else {
+            Err(Error::Runtime("This should never happen!".to_string()))
+        }
+    }
+
+    fn visit_match_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::Match { value, arms, .. } = statement {
+            self.resolve_expression(value)?;
+
+            // every arm has a scope of its own for the names its pattern binds
+            for arm in arms {
+                self.begin_scope();
+                self.resolve_pattern(&arm.pattern)?;
+                if let Some(guard) = &arm.guard {
+                    self.resolve_expression(guard)?;
+                }
+                self.resolve_statement(&arm.body)?;
+                self.end_scope();
+            }
             Ok(())
         } else {
-            Err(Error::Runtime(format!("This should never happen!")))
+            Err(Error::Runtime("This should never happen!".to_string()))
+        }
+    }
+
+    fn visit_try_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::Try { body, catch_clause, finally_body } = statement {
+            self.tries += 1;
+            let resolved = self.resolve_try(body, catch_clause, finally_body);
+            self.tries -= 1;
+            resolved
+        } else {
+            Err(Error::Runtime("This should never happen!".to_string()))
+        }
+    }
+
+    fn visit_class_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::Class { name, methods, .. } = statement {
+            let enclosing_class = self.current_class.clone();
+            self.current_class = ClassType::Class;
+
+            self.declare_as(name, LocalKind::Class);
+            self.define(name);
+
+            self.begin_scope();
+            if let Some(scope) = self.scopes.last_mut() {
+                let this = Token::new(TokenType::THIS, "this".to_string(), Literal::None, name.line, 0);
+                scope.insert(
+                    "this".to_string(),
+                    Local {
+                        name: this,
+                        kind: LocalKind::This,
+                        defined: true,
+                        read: true,
+                        assigned: None,
+                    },
+                );
+            }
+
+            for method in methods.iter() {
+                let function_type = match method.as_ref() {
+                    Stmt::Function { name, .. } if name.lexeme == "init" => FunctionType::Initializer,
+                    _ => FunctionType::Method,
+                };
+                self.resolve_function(method, function_type)?;
+            }
+
+            self.end_scope();
+            self.current_class = enclosing_class;
+
+            Ok(())
+        } else {
+            Err(Error::Runtime("This should never happen!".to_string()))
+        }
+    }
+
+    fn visit_import_statement(&mut self, statement: &Stmt) -> Result<()> {
+        if let Stmt::Import { alias, names, .. } = statement {
+            for name in alias.iter().chain(names) {
+                self.declare(name);
+                self.define(name);
+            }
+            Ok(())
+        } else {
+            Err(Error::Runtime("This should never happen!".to_string()))
+        }
+    }
+
+    fn visit_export_statement(&mut self, declaration: &Stmt) -> Result<()> {
+        if !self.scopes.is_empty() {
+            if let Stmt::Variable { name, .. } | Stmt::Function { name, .. } | Stmt::Class { name, .. } = declaration {
+                self.error(name, "Can only export top-level declarations.");
+            }
         }
+        self.resolve_statement(declaration)
     }
 }
@@ -1,16 +1,58 @@
 use std::collections::HashMap;
 
+use crate::errors::ScanError;
 use crate::tokens::TokenType::{self, *};
 use crate::tokens::{Literal, Token};
 
+// a `/** ... */` comment, kept so that a documentation generator can attach it to
+// the declaration that follows
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocComment {
+    pub text: String,
+    pub line: usize,
+    // index of the first token after the comment
+    pub token: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TriviaKind {
+    Whitespace,
+    LineComment,
+    BlockComment,
+    // characters that produced a scanner error
+    Skipped,
+}
+
+// source text between tokens, only kept when scanning with `Scanner::with_trivia`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+}
+
 #[derive(Debug, Default)]
 pub struct Scanner<'a> {
     source: Vec<char>,
     tokens: Vec<Token>,
+    errors: Vec<ScanError>,
+    doc_comments: Vec<DocComment>,
+    // open braces inside each `${ ... }` being scanned, innermost last
+    interpolations: Vec<usize>,
+
+    keep_trivia: bool,
+    // trivia since the last token
+    pending: Vec<Trivia>,
+    // the trivia in front of each token
+    leading: Vec<Vec<Trivia>>,
 
     start: usize,
     current: usize,
     line: usize,
+    // index of the first character on the current line, for columns
+    line_start: usize,
+    // where the token being scanned began
+    start_line: usize,
+    start_column: usize,
 
     keywords: HashMap<&'a str, TokenType>,
 }
@@ -19,40 +61,69 @@ impl<'a> Scanner<'a> {
     pub fn new(source: String) -> Self {
         let keywords = HashMap::from([
             ("and", AND),
+            ("break", BREAK),
+            ("catch", CATCH),
             ("class", CLASS),
             ("else", ELSE),
             ("false", FALSE),
+            ("finally", FINALLY),
             ("for", FOR),
             ("fun", FUN),
             ("if", IF),
+            ("match", MATCH),
             ("nil", NIL),
             ("or", OR),
             ("print", PRINT),
             ("return", RETURN),
             ("super", SUPER),
             ("this", THIS),
+            ("throw", THROW),
             ("true", TRUE),
+            ("try", TRY),
             ("var", VAR),
             ("while", WHILE),
+            ("yield", YIELD),
         ]);
 
         Scanner {
             source: source.chars().collect(),
             tokens: Vec::new(),
+            errors: Vec::new(),
+            doc_comments: Vec::new(),
+            interpolations: Vec::new(),
+            keep_trivia: false,
+            pending: Vec::new(),
+            leading: Vec::new(),
             start: 0,
             current: 0,
             line: 1,
+            line_start: 0,
+            start_line: 1,
+            start_column: 1,
             keywords,
         }
     }
 
+    // also keeps whitespace, comments and bad characters, see `trivia`
+    pub fn with_trivia(source: String) -> Self {
+        Scanner {
+            keep_trivia: true,
+            ..Scanner::new(source)
+        }
+    }
+
+    // the trivia in front of every token, the file's trailing trivia belongs to EOF
+    pub fn trivia(&self) -> &[Vec<Trivia>] {
+        &self.leading
+    }
+
     fn is_at_end(&self) -> bool {
         self.current >= self.source.len()
     }
 
     fn advance(&mut self) -> char {
         let ch = self.source[self.current];
-        self.current = self.current + 1;
+        self.current += 1;
         ch
     }
 
@@ -66,6 +137,29 @@ impl<'a> Scanner<'a> {
             .collect::<String>();
         let token = Token::new(token_type, text, literal, self.line, self.tokens.len());
         self.tokens.push(token);
+
+        if self.keep_trivia {
+            let trivia = std::mem::take(&mut self.pending);
+            self.leading.push(trivia);
+        }
+    }
+
+    // whatever the last call to `scan_token` skipped over
+    fn add_trivia(&mut self) {
+        let text = self.substring(self.start, self.current);
+        let kind = match (self.source[self.start], self.source.get(self.start + 1)) {
+            ('/', Some('/')) => TriviaKind::LineComment,
+            ('/', Some('*')) => TriviaKind::BlockComment,
+            (ch, _) if ch.is_whitespace() => TriviaKind::Whitespace,
+            _ => TriviaKind::Skipped,
+        };
+
+        match self.pending.last_mut() {
+            Some(last) if last.kind == kind && kind == TriviaKind::Whitespace => {
+                last.text.push_str(&text)
+            }
+            _ => self.pending.push(Trivia { kind, text }),
+        }
     }
 
     fn is_match(&mut self, expected: char) -> bool {
@@ -76,7 +170,7 @@ impl<'a> Scanner<'a> {
             return false;
         }
 
-        self.current = self.current + 1;
+        self.current += 1;
         true
     }
 
@@ -86,14 +180,65 @@ impl<'a> Scanner<'a> {
         match c {
             '(' => self.add_token(LEFT_PAREN),
             ')' => self.add_token(RIGHT_PAREN),
-            '{' => self.add_token(LEFT_BRACE),
-            '}' => self.add_token(RIGHT_BRACE),
+            '{' => {
+                if let Some(braces) = self.interpolations.last_mut() {
+                    *braces += 1;
+                }
+                self.add_token(LEFT_BRACE);
+            }
+            '}' => match self.interpolations.last_mut() {
+                // closes the `${`, the string carries on
+                Some(0) => {
+                    self.interpolations.pop();
+                    self.string();
+                }
+                Some(braces) => {
+                    *braces -= 1;
+                    self.add_token(RIGHT_BRACE);
+                }
+                None => self.add_token(RIGHT_BRACE),
+            },
             ',' => self.add_token(COMMA),
-            '.' => self.add_token(DOT),
-            '-' => self.add_token(MINUS),
-            '+' => self.add_token(PLUS),
+            '[' => self.add_token(LEFT_BRACKET),
+            ']' => self.add_token(RIGHT_BRACKET),
+            '.' => {
+                let token = if !self.is_match('.') {
+                    DOT
+                } else if self.is_match('.') {
+                    DOT_DOT_DOT
+                } else {
+                    DOT_DOT
+                };
+                self.add_token(token);
+            }
+            '-' => {
+                let token = if self.is_match('-') {
+                    MINUS_MINUS
+                } else if self.is_match('=') {
+                    MINUS_EQUAL
+                } else {
+                    MINUS
+                };
+                self.add_token(token);
+            }
+            '+' => {
+                let token = if self.is_match('+') {
+                    PLUS_PLUS
+                } else if self.is_match('=') {
+                    PLUS_EQUAL
+                } else {
+                    PLUS
+                };
+                self.add_token(token);
+            }
             ';' => self.add_token(SEMICOLON),
-            '*' => self.add_token(STAR),
+            '*' => {
+                let token = if self.is_match('=') { STAR_EQUAL } else { STAR };
+                self.add_token(token);
+            }
+            '?' => self.add_token(QUESTION),
+            ':' => self.add_token(COLON),
+            '|' => self.add_token(PIPE),
 
             // Operators
             '!' => {
@@ -104,6 +249,8 @@ impl<'a> Scanner<'a> {
             '=' => {
                 let token = if self.is_match('=') {
                     EQUAL_EQUAL
+                } else if self.is_match('>') {
+                    FAT_ARROW
                 } else {
                     EQUAL
                 };
@@ -130,6 +277,10 @@ impl<'a> Scanner<'a> {
                     while self.peek() != '\n' && !self.is_at_end() {
                         self.advance();
                     }
+                } else if self.is_match('*') {
+                    self.block_comment();
+                } else if self.is_match('=') {
+                    self.add_token(SLASH_EQUAL);
                 } else {
                     self.add_token(SLASH);
                 }
@@ -137,30 +288,87 @@ impl<'a> Scanner<'a> {
 
             // whitespace characters
             '\r' | '\t' | ' ' => {}
-            '\n' => self.line = self.line + 1,
+            '\n' => self.newline(),
 
             // string literals
             '"' => {
                 self.string();
             }
+            'r' if self.peek() == '"' => self.raw_string(),
 
             _ => {
-                if c.is_digit(10) {
+                if c.is_ascii_digit() {
                     self.number();
                 } else if c.is_alphabetic() || c == '_' {
                     self.identifier();
                 } else {
-                    eprintln!("Unexpected character: {} at line: {}", c, self.line);
+                    self.error(format!("Unexpected character '{}'.", c));
                 }
             }
         };
     }
+    fn newline(&mut self) {
+        self.line += 1;
+        self.line_start = self.current;
+    }
+
+    // errors point at the start of the offending token
+    fn error(&mut self, message: String) {
+        self.errors.push(ScanError {
+            line: self.start_line,
+            column: self.start_column,
+            message,
+        });
+    }
+
+    // block comments nest, so `/* a /* b */ c */` is a single comment
+    fn block_comment(&mut self) {
+        let mut depth = 1;
+
+        while depth > 0 {
+            if self.is_at_end() {
+                self.error("Unterminated block comment.".to_string());
+                return;
+            }
+
+            match self.advance() {
+                '/' if self.is_match('*') => depth += 1,
+                '*' if self.is_match('/') => depth -= 1,
+                '\n' => self.newline(),
+                _ => {}
+            }
+        }
+
+        // `/**/` is just an empty comment
+        let text = self.substring(self.start, self.current);
+        if text.starts_with("/**") && text.len() > 4 {
+            self.doc_comments.push(DocComment {
+                text: doc_text(&text[3..text.len() - 2]),
+                line: self.start_line,
+                token: self.tokens.len(),
+            });
+        }
+    }
+
+    pub fn doc_comments(&self) -> &[DocComment] {
+        &self.doc_comments
+    }
+
+    // for errors inside a token, like a bad escape in a string
+    fn error_at(&mut self, column: usize, message: String) {
+        self.errors.push(ScanError {
+            line: self.line,
+            column,
+            message,
+        });
+    }
+
     fn substring(&self, start: usize, stop: usize) -> String {
         self.source[start..stop].iter().collect::<String>()
     }
 
     fn identifier(&mut self) {
-        while self.source[self.current].is_alphanumeric() || self.source[self.current] == '_' {
+        while self.peek().is_alphanumeric() || self.peek() == '_' {
             self.advance();
         }
 
@@ -175,50 +383,229 @@ impl<'a> Scanner<'a> {
         }
     }
 
+    // "a ${b} c" is scanned as INTERPOLATION("a "), the tokens of `b`, then STRING(" c")
+    // once the closing brace is reached
     fn string(&mut self) {
+        let mut value = String::new();
+
         while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.line = self.line + 1
-            };
-            self.advance();
+            if self.peek() == '$' && self.peek_next() == '{' {
+                self.current += 2;
+                self.interpolations.push(0);
+                self.add_token_literal(INTERPOLATION, Literal::String(value));
+                return;
+            }
+
+            match self.advance() {
+                '\\' => {
+                    if let Some(ch) = self.escape() {
+                        value.push(ch);
+                    }
+                }
+                '\n' => {
+                    self.newline();
+                    value.push('\n');
+                }
+                ch => value.push(ch),
+            }
         }
 
         if self.is_at_end() {
-            eprintln!("Unterminated string!");
+            self.error("Unterminated string.".to_string());
             return;
         }
 
         self.advance();
+        self.add_token_literal(STRING, Literal::String(value));
+    }
 
-        let value = self.source[self.start + 1..self.current - 1]
-            .iter()
-            .collect::<String>();
+    // r"..." keeps every character as written, backslashes included
+    fn raw_string(&mut self) {
+        // the opening quote
+        self.advance();
+
+        while self.peek() != '"' && !self.is_at_end() {
+            if self.advance() == '\n' {
+                self.newline();
+            }
+        }
+
+        if self.is_at_end() {
+            self.error("Unterminated string.".to_string());
+            return;
+        }
+
+        self.advance();
+
+        let value = self.substring(self.start + 2, self.current - 1);
         self.add_token_literal(STRING, Literal::String(value));
     }
 
+    // the character after a backslash, None when the escape is malformed (after reporting it)
+    fn escape(&mut self) -> Option<char> {
+        // points at the backslash
+        let column = self.current - self.line_start;
+
+        if self.is_at_end() {
+            return None;
+        }
+
+        let ch = match self.advance() {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            '\\' => '\\',
+            '"' => '"',
+            '$' => '$',
+            'x' => {
+                let digits = self.hex_digits(2);
+                let value = match digits.len() {
+                    2 => u8::from_str_radix(&digits, 16).ok().map(char::from),
+                    _ => None,
+                };
+                if value.is_none() {
+                    self.error_at(column, "Invalid escape '\\x', expected two hex digits.".to_string());
+                }
+                return value;
+            }
+            'u' => {
+                if !self.is_match('{') {
+                    self.error_at(column, "Invalid escape '\\u', expected '{'.".to_string());
+                    return None;
+                }
+                let digits = self.hex_digits(6);
+                if !self.is_match('}') || digits.is_empty() {
+                    self.error_at(column, "Invalid escape '\\u', expected 1 to 6 hex digits and '}'.".to_string());
+                    return None;
+                }
+                let value = u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32);
+                if value.is_none() {
+                    self.error_at(column, format!("Invalid unicode code point '{}'.", digits));
+                }
+                return value;
+            }
+            '\n' => {
+                self.error_at(column, "Invalid escape at end of line.".to_string());
+                self.newline();
+                return None;
+            }
+            other => {
+                self.error_at(column, format!("Invalid escape '\\{}'.", other));
+                return None;
+            }
+        };
+
+        Some(ch)
+    }
+
+    fn hex_digits(&mut self, max: usize) -> String {
+        let mut digits = String::new();
+        while digits.len() < max && self.peek().is_ascii_hexdigit() {
+            digits.push(self.advance());
+        }
+        digits
+    }
+
+    // 42, 1.5, 6.02E23, 1e-9, 0xFF, 0b1010, 0o17 and 1_000_000, underscores only
+    // between digits
     pub fn number(&mut self) {
-        while self.peek().is_digit(10) {
+        let radix = match (self.source[self.start], self.peek()) {
+            ('0', 'x' | 'X') => 16,
+            ('0', 'b' | 'B') => 2,
+            ('0', 'o' | 'O') => 8,
+            _ => 10,
+        };
+
+        let value = if radix == 10 {
+            self.decimal()
+        } else {
             self.advance();
+            let digits = self.digits(radix);
+            let text = self.substring(self.start, self.current);
+            if digits.is_empty() {
+                Err(format!("Expected digits after '{}'.", text))
+            } else if digits.contains('_') {
+                Err(format!("Misplaced digit separator in '{}'.", text))
+            } else {
+                Ok(digits
+                    .chars()
+                    .filter_map(|ch| ch.to_digit(radix))
+                    .fold(0.0, |value, digit| value * radix as f64 + digit as f64))
+            }
+        };
+
+        // 0b102 or 12abc, swallow the rest so that it is reported once
+        let value = value.and_then(|value| {
+            if self.peek().is_alphanumeric() {
+                while self.peek().is_alphanumeric() || self.peek() == '_' {
+                    self.advance();
+                }
+                let text = self.substring(self.start, self.current);
+                Err(format!("Invalid number '{}'.", text))
+            } else {
+                Ok(value)
+            }
+        });
+
+        match value {
+            Ok(value) => self.add_token_literal(NUMBER, Literal::Number(value)),
+            Err(message) => self.error(message),
         }
+    }
+
+    fn decimal(&mut self) -> std::result::Result<f64, String> {
+        // the first digit has been consumed already
+        let mut text = self.source[self.start].to_string();
+        text.push_str(&self.digits(10));
 
         if self.peek() == '.' && self.is_digit(self.peek_next()) {
             self.advance();
+            text.push('.');
+            text.push_str(&self.digits(10));
+        }
 
-            while self.is_digit(self.peek()) {
-                self.advance();
+        if matches!(self.peek(), 'e' | 'E') {
+            self.advance();
+            text.push('e');
+            if matches!(self.peek(), '+' | '-') {
+                text.push(self.advance());
             }
+            let exponent = self.digits(10);
+            if exponent.is_empty() {
+                let text = self.substring(self.start, self.current);
+                return Err(format!("Expected digits in the exponent of '{}'.", text));
+            }
+            text.push_str(&exponent);
         }
 
-        let value = self.source[self.start..self.current]
-            .iter()
-            .collect::<String>();
-        let number = value.parse::<f64>().unwrap();
+        if text.contains('_') {
+            let text = self.substring(self.start, self.current);
+            return Err(format!("Misplaced digit separator in '{}'.", text));
+        }
+
+        text.parse::<f64>().map_err(|err| format!("Invalid number '{}': {}.", text, err))
+    }
+
+    // digits of the radix with their separators removed, a separator that is not
+    // between two digits is left in so that the caller reports it
+    fn digits(&mut self, radix: u32) -> String {
+        let mut digits = String::new();
+
+        while self.peek().is_digit(radix) || self.peek() == '_' {
+            let ch = self.advance();
+            let previous = self.source[self.current - 2];
+            let separates = previous.is_digit(radix) && self.peek().is_digit(radix);
+            if ch != '_' || !separates {
+                digits.push(ch);
+            }
+        }
 
-        self.add_token_literal(NUMBER, Literal::Number(number))
+        digits
     }
 
     pub fn is_digit(&self, ch: char) -> bool {
-        ch.is_digit(10)
+        ch.is_ascii_digit()
     }
 
     pub fn peek(&self) -> char {
@@ -237,20 +624,39 @@ impl<'a> Scanner<'a> {
         }
     }
 
-    pub fn scan_tokens(&mut self) -> Vec<Token> {
+    // scans the whole source, an error does not stop the scanner so that all of them are reported
+    pub fn scan_tokens(&mut self) -> (Vec<Token>, Vec<ScanError>) {
         while !self.is_at_end() {
             self.start = self.current;
+            self.start_line = self.line;
+            self.start_column = self.current - self.line_start + 1;
+
+            let count = self.tokens.len();
             self.scan_token();
+            if self.keep_trivia && self.tokens.len() == count {
+                self.add_trivia();
+            }
         }
 
-        let token = Token::new(
-            EOF,
-            "".to_string(),
-            Literal::None,
-            self.line,
-            self.tokens.len(),
-        );
-        self.tokens.push(token);
-        self.tokens.clone()
+        if !self.interpolations.is_empty() {
+            self.error("Unterminated string interpolation.".to_string());
+        }
+
+        self.start = self.current;
+        self.add_token(EOF);
+        (self.tokens.clone(), self.errors.clone())
     }
 }
+
+// drops the leading `*` that usually lines up the body of a doc comment
+fn doc_text(body: &str) -> String {
+    body.lines()
+        .map(|line| {
+            let line = line.trim();
+            line.strip_prefix('*').map_or(line, str::trim_start)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
@@ -1,7 +1,6 @@
 use crate::ast::Expr;
 use crate::errors::Result;
-use crate::tokens::Token;
-use crate::object::Object;
+use crate::tokens::{Literal, Token};
 
 #[derive(Clone, Debug)]
 pub enum Stmt {
@@ -9,6 +8,8 @@ pub enum Stmt {
     Expression(Expr),
     Variable {
         name: Token,
+        // `var x: Number`, only the type checker looks at annotations
+        annotation: Option<Token>,
         initializer: Option<Expr>,
     },
     Block {
@@ -23,18 +24,126 @@ pub enum Stmt {
         condition: Expr,
         body: Box<Stmt>
     },
+    // `for (var name in iterable) body`, every iteration gets its own `name`
+    ForIn {
+        name: Token,
+        iterable: Expr,
+        body: Box<Stmt>
+    },
     Function {
         name: Token,
         params: Vec<Token>,
-        body: Vec<Stmt>
+        // one per parameter
+        param_types: Vec<Option<Token>>,
+        // one per parameter, `b = 2` is evaluated in the function's scope at every
+        // call that leaves `b` out
+        defaults: Vec<Option<Expr>>,
+        // `...rest`, a list of the positional arguments left over
+        rest: Option<Token>,
+        return_type: Option<Token>,
+        body: Vec<Stmt>,
+        // `fun*`, calling it gives back an iterator over what the body yields
+        generator: bool
     },
     Return {
         keyword: Token,
         value: Option<Expr>
     },
+    Break {
+        keyword: Token
+    },
+    Yield {
+        keyword: Token,
+        value: Expr
+    },
+    Throw {
+        keyword: Token,
+        value: Expr
+    },
+    // `match (value) { pattern if guard => body ... }`, the first arm whose pattern
+    // matches and whose guard holds runs
+    Match {
+        keyword: Token,
+        value: Expr,
+        arms: Vec<MatchArm>
+    },
+    // at least one of the catch clause, the caught value's name and its body, and
+    // the finally block is there
+    Try {
+        body: Vec<Stmt>,
+        catch_clause: Option<(Token, Vec<Stmt>)>,
+        finally_body: Option<Vec<Stmt>>
+    },
     Class {
         name: Token,
+        // `x: Number;` declarations, the name and its type
+        fields: Vec<(Token, Token)>,
         methods: Vec<Box<Stmt>>
+    },
+    // `import "path.lox" as name;` or `from "path.lox" import a, b;`, `keyword` is
+    // the `import` or `from` that starts it
+    Import {
+        keyword: Token,
+        path: Token,
+        alias: Option<Token>,
+        names: Vec<Token>
+    },
+    // `export` in front of a top-level var, fun or class
+    Export {
+        declaration: Box<Stmt>
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Expr>,
+    pub body: Stmt,
+}
+
+#[derive(Clone, Debug)]
+pub enum Pattern {
+    // a number, string, true, false or nil, compared with `==`
+    Literal {
+        token: Token,
+        value: Literal
+    },
+    // `_`
+    Wildcard,
+    Binding {
+        name: Token
+    },
+    // `1 | 2`, the alternatives can't bind names
+    Or {
+        alternatives: Vec<Pattern>
+    },
+    // `Point(x, y)`, an instance of the class whose fields named after the
+    // parameters of `init` match the patterns in order
+    Class {
+        name: Token,
+        fields: Vec<Pattern>
+    },
+    // `[first, second, ...rest]`, without a rest the lengths have to be the same
+    List {
+        elements: Vec<Pattern>,
+        rest: Option<Token>
+    },
+}
+
+impl Pattern {
+    // the names the pattern binds, in order
+    pub fn bindings(&self) -> Vec<&Token> {
+        match self {
+            Pattern::Literal { .. } | Pattern::Wildcard => Vec::new(),
+            Pattern::Binding { name } => vec![name],
+            Pattern::Or { alternatives } => alternatives.iter().flat_map(Pattern::bindings).collect(),
+            Pattern::Class { fields, .. } => fields.iter().flat_map(Pattern::bindings).collect(),
+            Pattern::List { elements, rest } => elements
+                .iter()
+                .flat_map(Pattern::bindings)
+                .chain(rest.iter())
+                .collect(),
+        }
     }
 }
 
@@ -44,12 +153,20 @@ impl Stmt {
             Self::Print(_) => visitor.visit_print_statement(self),
             Self::Expression(_) => visitor.visit_expression_statement(self),
             Self::Variable { .. } => visitor.visit_variable_statement(self),
-            Self::Block { statements } => visitor.visit_block_statement(&statements),
+            Self::Block { statements } => visitor.visit_block_statement(statements),
             Self::If{..} => visitor.visit_if_statement(self),
             Self::While {..} => visitor.visit_while_statement(self),
+            Self::ForIn { .. } => visitor.visit_for_in_statement(self),
             Self::Function { .. } => visitor.visit_function_statement(self),
             Self::Return { .. } => visitor.visit_return_statement(self),
-            Self::Class { ..} => visitor.visit_class_statement(self)
+            Self::Break { keyword } => visitor.visit_break_statement(keyword),
+            Self::Yield { .. } => visitor.visit_yield_statement(self),
+            Self::Throw { .. } => visitor.visit_throw_statement(self),
+            Self::Match { .. } => visitor.visit_match_statement(self),
+            Self::Try { .. } => visitor.visit_try_statement(self),
+            Self::Class { ..} => visitor.visit_class_statement(self),
+            Self::Import { .. } => visitor.visit_import_statement(self),
+            Self::Export { declaration } => visitor.visit_export_statement(declaration),
 
         }
     }
@@ -59,10 +176,18 @@ pub trait Visitor<T> {
     fn visit_print_statement(&mut self, statement: &Stmt) -> Result<T>;
     fn visit_expression_statement(&mut self, statement: &Stmt) -> Result<T>;
     fn visit_variable_statement(&mut self, statement: &Stmt) -> Result<T>;
-    fn visit_block_statement(&mut self, statement: &Vec<Stmt>) -> Result<T>;
+    fn visit_block_statement(&mut self, statement: &[Stmt]) -> Result<T>;
     fn visit_if_statement(&mut self, statement: &Stmt) -> Result<T>;
     fn visit_while_statement(&mut self, statement: &Stmt) -> Result<T>;
+    fn visit_for_in_statement(&mut self, statement: &Stmt) -> Result<T>;
     fn visit_function_statement(&mut self, statement: &Stmt) -> Result<T>;
     fn visit_return_statement(&mut self, statement: &Stmt) -> Result<T>;
+    fn visit_break_statement(&mut self, keyword: &Token) -> Result<T>;
+    fn visit_yield_statement(&mut self, statement: &Stmt) -> Result<T>;
+    fn visit_throw_statement(&mut self, statement: &Stmt) -> Result<T>;
+    fn visit_match_statement(&mut self, statement: &Stmt) -> Result<T>;
+    fn visit_try_statement(&mut self, statement: &Stmt) -> Result<T>;
     fn visit_class_statement(&mut self, statement: &Stmt) -> Result<T>;
+    fn visit_import_statement(&mut self, statement: &Stmt) -> Result<T>;
+    fn visit_export_statement(&mut self, declaration: &Stmt) -> Result<T>;
 }
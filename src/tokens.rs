@@ -62,11 +62,18 @@ impl Hash for Token {
         self.line.hash(state);
     }
 }
+// the reference implementation's format, `NUMBER 123 123.0`
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{} ", self.token_type);
         write!(f, "{} ", self.lexeme);
-        write!(f, "{}", self.literal)
+        match self.literal {
+            // like Java's Double.toString for everyday numbers
+            Literal::Number(value) if value.fract() == 0.0 && value.abs() < 1e16 => {
+                write!(f, "{:.1}", value)
+            }
+            _ => write!(f, "{}", self.literal),
+        }
     }
 }
 
@@ -84,6 +91,8 @@ pub enum TokenType {
     RIGHT_PAREN,
     LEFT_BRACE,
     RIGHT_BRACE,
+    LEFT_BRACKET,
+    RIGHT_BRACKET,
     COMMA,
     DOT,
     MINUS,
@@ -91,6 +100,9 @@ pub enum TokenType {
     SEMICOLON,
     SLASH,
     STAR,
+    QUESTION,
+    COLON,
+    PIPE,
 
     // One or two character tokens.
     BANG,
@@ -101,29 +113,47 @@ pub enum TokenType {
     GREATER_EQUAL,
     LESS,
     LESS_EQUAL,
+    PLUS_EQUAL,
+    MINUS_EQUAL,
+    STAR_EQUAL,
+    SLASH_EQUAL,
+    PLUS_PLUS,
+    MINUS_MINUS,
+    DOT_DOT,
+    DOT_DOT_DOT,
+    FAT_ARROW,
 
     // Literals.
     IDENTIFIER,
     STRING,
+    // the part of a string before `${`
+    INTERPOLATION,
     NUMBER,
 
     // Keywords.
     AND,
+    BREAK,
+    CATCH,
     CLASS,
     ELSE,
     FALSE,
+    FINALLY,
     FUN,
     FOR,
     IF,
+    MATCH,
     NIL,
     OR,
     PRINT,
     RETURN,
     SUPER,
     THIS,
+    THROW,
     TRUE,
+    TRY,
     VAR,
     WHILE,
+    YIELD,
 
     EOF,
 }
@@ -19,7 +19,7 @@ fn main() {
     let mut lox = Lox::new();
     let tokens = lox.run_debug_file(&file);
 
-    let contents = fs::read_to_string(file).unwrap_or(String::new());
+    let contents = fs::read_to_string(file).unwrap_or_default();
     let expected_tokens = contents
         .split('\n')
         .filter(|x| x.starts_with("//"))
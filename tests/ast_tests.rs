@@ -0,0 +1,85 @@
+use rlox::ast::{AstPrinter, JsonPrinter};
+use rlox::parser::Parser;
+use rlox::scanner::Scanner;
+use rlox::statement::Stmt;
+use std::process::Command;
+
+fn parse(source: &str) -> Vec<Stmt> {
+    let (tokens, _) = Scanner::new(source.to_string()).scan_tokens();
+    Parser::new(tokens).parse().unwrap()
+}
+
+fn sexp(source: &str) -> String {
+    AstPrinter.print_statements(&parse(source)).unwrap()
+}
+
+#[test]
+fn prints_expressions() {
+    assert_eq!(sexp("-123 * (45.67);"), "(; (* (- 123) (group 45.67)))");
+    assert_eq!(sexp("a = b.c = !d and e or nil;"), "(; (= a (.= c b (or (and (! d) e) nil))))");
+    assert_eq!(sexp("print f(1, \"two\")(this);"), "(print (call (call f 1 \"two\") this))");
+}
+
+#[test]
+fn prints_desugared_for_loops() {
+    assert_eq!(
+        sexp("for (var i = 0; i < 3; i = i + 1) print i;"),
+        "(block (var i 0) (while (< i 3) (block (print i) (; (= i (+ i 1))))))"
+    );
+    assert_eq!(sexp("for (;;) {}"), "(while true (block))");
+}
+
+#[test]
+fn prints_declarations() {
+    assert_eq!(
+        sexp("var a; class A { m(x, y) { return; } } fun f() {} if (a) print 1; else {}"),
+        "(var a)\n(class A (fun m (x y) (return)))\n(fun f ())\n(if a (print 1) (block))"
+    );
+}
+
+#[test]
+fn prints_type_annotations() {
+    assert_eq!(
+        sexp("var a: Number = 1; class P { x: Number; } fun f(a: String, b): Bool { return true; }"),
+        "(var (: a Number) 1)\n(class P (: x Number))\n(fun (: f Bool) ((: a String) b) (return true))"
+    );
+}
+
+#[test]
+fn prints_json_with_tokens_and_lines() {
+    let json = JsonPrinter.print_statements(&parse("var a = \"q\\\"\";\nprint -a;")).unwrap();
+    assert_eq!(
+        json,
+        concat!(
+            r#"[{"kind":"Var","name":{"type":"IDENTIFIER","lexeme":"a","line":1},"annotation":null,"#,
+            r#""initializer":{"kind":"Literal","value":"q\""}},"#,
+            r#"{"kind":"Print","expression":{"kind":"Unary","#,
+            r#""operator":{"type":"MINUS","lexeme":"-","line":2},"#,
+            r#""right":{"kind":"Variable","name":{"type":"IDENTIFIER","lexeme":"a","line":2}}}}]"#
+        )
+    );
+}
+
+#[test]
+fn ast_binary_prints_files() {
+    let ast = env!("CARGO_BIN_EXE_ast");
+    let file = "./tests/test_cases/closure/nested_closure.lox";
+
+    let output = Command::new(ast).arg(file).output().unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("(var f)\n(fun f1 () (var a \"a\")"));
+
+    let output = Command::new(ast).args(["--json", file]).output().unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with(r#"[{"kind":"Var""#));
+
+    let output = Command::new(ast).arg("./tests/test_cases/unexpected_character.lox").output().unwrap();
+    assert_eq!(output.status.code(), Some(65));
+}
+
+#[test]
+fn prints_defaults_and_named_arguments() {
+    assert_eq!(
+        sexp("fun f(a, b = 2, ...rest) {} f(1, b: 3);"),
+        "(fun f (a (= b 2) ...rest))\n(; (call f 1 (: b 3)))"
+    );
+}
@@ -0,0 +1,32 @@
+// Helpers shared by the integration tests, every test file uses only some of them.
+#![allow(dead_code)]
+
+use rlox::interpreter::Output;
+use rlox::lox::{Lox, Status};
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+// runs the source, gives back the status and what it printed
+pub fn run(source: &str) -> (Status, String) {
+    let buffer = Rc::new(RefCell::new(Vec::new()));
+    let mut lox = Lox::new();
+    lox.set_output(Output::Buffer(Rc::clone(&buffer)));
+
+    let status = lox.run(source);
+    let output = String::from_utf8(buffer.borrow().clone()).unwrap();
+    (status, output)
+}
+
+// collects the paths of the .lox files under the directory
+pub fn visit(directory: &Path, files: &mut Vec<String>) {
+    for entry in fs::read_dir(directory).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            visit(&path, files);
+        } else if path.extension().is_some_and(|extension| extension == "lox") {
+            files.push(path.to_str().unwrap().to_string());
+        }
+    }
+}
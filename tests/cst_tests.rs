@@ -0,0 +1,84 @@
+mod common;
+
+use common::visit;
+use rlox::cst::{self, SyntaxElement, SyntaxKind, SyntaxNode};
+use rlox::parser::Parser;
+use rlox::scanner::{Scanner, TriviaKind};
+use std::fs;
+use std::path::Path;
+
+fn kinds(node: &SyntaxNode) -> Vec<SyntaxKind> {
+    node.nodes().map(|node| node.kind).collect()
+}
+
+#[test]
+fn keeps_trivia_in_front_of_tokens() {
+    let mut scanner = Scanner::with_trivia("  // hello\nprint /* a */ 1 @;\n".to_string());
+    let (tokens, _) = scanner.scan_tokens();
+    let trivia = scanner.trivia();
+
+    assert_eq!(trivia.len(), tokens.len());
+    let first: Vec<_> = trivia[0].iter().map(|trivia| (trivia.kind, trivia.text.as_str())).collect();
+    assert_eq!(
+        first,
+        vec![
+            (TriviaKind::Whitespace, "  "),
+            (TriviaKind::LineComment, "// hello"),
+            (TriviaKind::Whitespace, "\n"),
+        ]
+    );
+    assert_eq!(trivia[1][1].kind, TriviaKind::BlockComment);
+    assert_eq!(trivia[2][1].kind, TriviaKind::Skipped);
+    // the trailing newline belongs to EOF
+    assert_eq!(trivia[3][0].text, "\n");
+}
+
+#[test]
+fn builds_nested_nodes() {
+    let (root, _) = cst::parse("var a = 1 + 2 * b;\nfun f(x) { return x; }\nclass A { m() {} }");
+
+    assert_eq!(kinds(&root), vec![SyntaxKind::VarDecl, SyntaxKind::FunDecl, SyntaxKind::ClassDecl]);
+
+    let var = root.nodes().next().unwrap();
+    let sum = var.nodes().next().unwrap();
+    assert_eq!(sum.kind, SyntaxKind::Binary);
+    assert_eq!(sum.text(), " 1 + 2 * b");
+    assert_eq!(kinds(sum), vec![SyntaxKind::Literal, SyntaxKind::Binary]);
+
+    let class = root.nodes().nth(2).unwrap();
+    assert_eq!(kinds(class), vec![SyntaxKind::Method]);
+    assert!(matches!(root.children.last(), Some(SyntaxElement::Token(_))));
+}
+
+#[test]
+fn round_trips_every_test_case() {
+    let mut files = Vec::new();
+    visit(Path::new("./tests/test_cases"), &mut files);
+    assert!(files.len() > 100);
+
+    for file in files {
+        let source = fs::read_to_string(&file).unwrap();
+        let (root, _) = cst::parse(&source);
+        assert_eq!(root.text(), source, "{} does not round-trip", file);
+    }
+}
+
+#[test]
+fn round_trips_broken_source() {
+    for source in ["print \"abc", "var = ;\n@ #", "/* open", "fun (", "print \"${1 +\";"] {
+        let (root, _) = cst::parse(source);
+        assert_eq!(root.text(), source);
+    }
+}
+
+#[test]
+fn lowers_to_the_same_ast() {
+    let source = fs::read_to_string("./tests/test_cases/closure/nested_closure.lox").unwrap();
+    let (tokens, _) = Scanner::new(source.clone()).scan_tokens();
+    let expected = Parser::new(tokens).parse().unwrap();
+
+    let (root, _) = cst::parse(&source);
+    let lowered = root.lower().unwrap();
+
+    assert_eq!(format!("{:?}", lowered), format!("{:?}", expected));
+}
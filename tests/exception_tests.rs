@@ -0,0 +1,82 @@
+mod common;
+
+use common::run;
+use rlox::lox::Status;
+
+#[test]
+fn catches_runtime_errors() {
+    let (status, output) = run(
+        "fun inner() {\n  return undefined;\n}\nfun outer() {\n  inner();\n}\ntry {\n  outer();\n} catch (e) {\n  print e.message;\n  print e.line;\n  print e.stack;\n}\n",
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(
+        output,
+        "Undefined variable 'undefined'.\n2\n[line 2] in inner()\n[line 5] in outer()\n[line 8] in script\n"
+    );
+}
+
+#[test]
+fn call_errors_have_plain_messages() {
+    let (status, output) = run(
+        "fun f(a, b) {}\ntry { f(1); } catch (e) { print e.message; print e.line; }\ntry { nil(); } catch (e) { print e.message; }\n",
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "Expected 2 arguments but got 1.\n2\nCan only call functions and classes.\n");
+}
+
+#[test]
+fn throws_any_value() {
+    let (status, output) = run(
+        r#"
+        try { throw "boom"; } catch (e) { print e; }
+        try { throw Error("custom"); } catch (e) { print e.message; print e.line; }
+        try { try { throw 1; } finally { print "inner"; } } catch (e) { print e + 1; }
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "boom\ncustom\n3\ninner\n2\n");
+}
+
+#[test]
+fn finally_always_runs() {
+    let (status, output) = run(
+        r#"
+        fun f() {
+          try { return "returned"; } finally { print "after return"; }
+        }
+        print f();
+        while (true) {
+          try { break; } finally { print "after break"; }
+        }
+        try {
+          try { nil(); } finally { print "after error"; }
+        } catch (e) {
+          print "caught";
+        }
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "after return\nreturned\nafter break\nafter error\ncaught\n");
+}
+
+#[test]
+fn uncaught_errors_stop_the_program() {
+    let (status, output) = run("print 1;\nthrow Error(\"oops\");\nprint 2;\n");
+
+    assert_eq!(status, Status::RuntimeError);
+    assert_eq!(output, "1\n");
+}
+
+#[test]
+fn break_only_inside_loops() {
+    assert_eq!(run("break;").0, Status::StaticError);
+    assert_eq!(run("while (true) { fun f() { break; } }").0, Status::StaticError);
+
+    let (status, output) = run("for (var i = 0; i < 10; i = i + 1) { if (i == 2) break; print i; }");
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "0\n1\n");
+}
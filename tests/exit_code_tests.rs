@@ -0,0 +1,39 @@
+use rlox::lox::{Lox, Status};
+use std::process::Command;
+
+fn exit_code(args: &[&str]) -> i32 {
+    Command::new(env!("CARGO_BIN_EXE_lox"))
+        .args(args)
+        .output()
+        .unwrap()
+        .status
+        .code()
+        .unwrap()
+}
+
+#[test]
+fn reports_status_from_run() {
+    let mut lox = Lox::new();
+
+    assert_eq!(lox.run("var a = 1;"), Status::Success);
+    assert_eq!(lox.run("var = 1;"), Status::StaticError);
+    assert_eq!(lox.run("return 1;"), Status::StaticError);
+    assert_eq!(lox.run("print -\"a\";"), Status::RuntimeError);
+    // an error does not stick to the session
+    assert_eq!(lox.run("print a;"), Status::Success);
+}
+
+#[test]
+fn exits_with_reference_codes() {
+    assert_eq!(exit_code(&["./tests/test_cases/closure/nested_closure.lox"]), 0);
+    assert_eq!(exit_code(&["./tests/test_cases/assignment/infix_operator.lox"]), 65);
+    assert_eq!(exit_code(&["./tests/test_cases/return/at_top_level.lox"]), 65);
+    assert_eq!(exit_code(&["./tests/test_cases/operator/add_bool_string.lox"]), 70);
+    assert_eq!(exit_code(&["./tests/test_cases/field/get_on_nil.lox"]), 70);
+}
+
+#[test]
+fn exits_with_usage_error() {
+    assert_eq!(exit_code(&["a.lox", "b.lox"]), 64);
+    assert_eq!(exit_code(&["--unknown-flag"]), 64);
+}
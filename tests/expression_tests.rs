@@ -0,0 +1,89 @@
+mod common;
+
+use common::run;
+use rlox::ast::AstPrinter;
+use rlox::lox::Status;
+use rlox::parser::Parser;
+use rlox::scanner::Scanner;
+
+fn sexp(source: &str) -> String {
+    let (tokens, _) = Scanner::new(source.to_string()).scan_tokens();
+    AstPrinter.print_statements(&Parser::new(tokens).parse().unwrap()).unwrap()
+}
+
+#[test]
+fn conditional_is_right_associative_below_assignment() {
+    assert_eq!(sexp("a ? b : c ? d : e;"), "(; (?: a b (?: c d e)))");
+    assert_eq!(sexp("x = a or b ? 1 : 2;"), "(; (= x (?: (or a b) 1 2)))");
+    assert_eq!(sexp("a ? b = 1 : c;"), "(; (?: a (= b 1) c))");
+}
+
+#[test]
+fn conditional_evaluates_only_the_taken_branch() {
+    let (status, output) = run(
+        r#"
+        fun say(value) { print value; return value; }
+        print true ? say("then") : say("else");
+        print nil ? say("then") : say("else");
+        var n = 5;
+        print n < 0 ? "negative" : n == 0 ? "zero" : "positive";
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "then\nthen\nelse\nelse\npositive\n");
+}
+
+#[test]
+fn conditional_needs_a_colon() {
+    assert_eq!(run("print true ? 1;").0, Status::StaticError);
+    assert_eq!(run("var a; true ? a : a = 1;").0, Status::StaticError);
+}
+
+#[test]
+fn compound_assignment_and_increments() {
+    let (status, output) = run(
+        r#"
+        var a = 1;
+        a += 2; print a;
+        a *= 10; a /= 4; a -= 0.5; print a;
+        print a++; print a; print ++a; print a--; print --a;
+        var s = "a"; s += "b"; print s;
+        fun counter() { var n = 0; fun next() { return ++n; } return next; }
+        var next = counter(); next(); print next();
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "3\n7\n7\n8\n9\n9\n7\nab\n2\n");
+}
+
+#[test]
+fn field_targets_are_evaluated_once() {
+    let (status, output) = run(
+        r#"
+        class Box { init() { this.value = 0; } }
+        var box = Box();
+        var calls = 0;
+        fun get() { calls += 1; return box; }
+        get().value += 5;
+        print get().value++;
+        print ++get().value;
+        print calls;
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "5\n7\n3\n");
+}
+
+#[test]
+fn rejects_invalid_update_targets() {
+    assert_eq!(sexp("a += b -= 1; --(3);"), "(; (+= a (-= b 1)))\n(; (- (- (group 3))))");
+    assert_eq!(sexp("a.b++;"), "(; (post++ (. b a)))");
+
+    for source in ["var a; (a)++;", "var a; ++(a);", "var a; a + 1 += 2;", "1++;"] {
+        assert_eq!(run(source).0, Status::StaticError, "{}", source);
+    }
+    assert_eq!(run("var s = \"a\"; s -= 1;").0, Status::RuntimeError);
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use common::visit;
+use rlox::formatter::format;
+use rlox::scanner::Scanner;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+fn lexemes(source: &str) -> Vec<String> {
+    let (tokens, _) = Scanner::new(source.to_string()).scan_tokens();
+    tokens.into_iter().map(|token| token.lexeme).collect()
+}
+
+#[test]
+fn formats_statements_and_operators() {
+    let source = "var   a=1+2*-3 ;\nfun add(x,y){return x+y;}\nif(a>1){print \"big ${a+1}\";}else print !true;\nfor(;;) {}\n";
+    assert_eq!(
+        format(source).unwrap(),
+        "var a = 1 + 2 * -3;\nfun add(x, y) {\n  return x + y;\n}\nif (a > 1) {\n  print \"big ${a + 1}\";\n} else print !true;\nfor (;;) {}\n"
+    );
+}
+
+#[test]
+fn keeps_comments_and_one_blank_line() {
+    let source = "// header\nvar a = 1;   // trailing\n\n\n\n{ /* inside */\nprint a; }\n// footer\n";
+    assert_eq!(
+        format(source).unwrap(),
+        "// header\nvar a = 1; // trailing\n\n{ /* inside */\n  print a;\n}\n// footer\n"
+    );
+}
+
+#[test]
+fn wraps_long_argument_lists() {
+    let source = "print describe(\"a fairly long first argument\", \"and a second one\", \"a third argument\");";
+    assert_eq!(
+        format(source).unwrap(),
+        "print describe(\n  \"a fairly long first argument\",\n  \"and a second one\",\n  \"a third argument\"\n);\n"
+    );
+}
+
+#[test]
+fn refuses_broken_source() {
+    assert!(format("print 1 +;").is_err());
+    assert!(format("print @;").is_err());
+}
+
+#[test]
+fn formatting_test_cases_is_stable() {
+    let mut files = Vec::new();
+    visit(Path::new("./tests/test_cases"), &mut files);
+
+    for file in files {
+        let source = fs::read_to_string(&file).unwrap();
+        let formatted = match format(&source) {
+            Ok(formatted) => formatted,
+            Err(_) => continue,
+        };
+
+        assert_eq!(lexemes(&formatted), lexemes(&source), "{} changed more than whitespace", file);
+        assert_eq!(format(&formatted).unwrap(), formatted, "{} is not formatted in one pass", file);
+    }
+}
+
+#[test]
+fn check_mode_reports_unformatted_files() {
+    let directory = std::env::temp_dir().join(format!("lox-fmt-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let file = directory.join("messy.lox");
+    fs::write(&file, "print 1+2;").unwrap();
+
+    let lox = env!("CARGO_BIN_EXE_lox");
+    let check = Command::new(lox).args(["fmt", "--check"]).arg(&file).output().unwrap();
+    assert_eq!(check.status.code(), Some(1));
+    assert_eq!(fs::read_to_string(&file).unwrap(), "print 1+2;");
+
+    let fix = Command::new(lox).arg("fmt").arg(&file).output().unwrap();
+    assert_eq!(fix.status.code(), Some(0));
+    assert_eq!(fs::read_to_string(&file).unwrap(), "print 1 + 2;\n");
+
+    let check = Command::new(lox).args(["fmt", "--check"]).arg(&file).output().unwrap();
+    assert_eq!(check.status.code(), Some(0));
+
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn keeps_lists_and_maps_on_one_line() {
+    let source = "var m={ \"a\" : [1,2], \"b\":{} };\nfor(var i in 0 .. 3){print m [ \"a\" ] [i];}\n";
+    assert_eq!(
+        format(source).unwrap(),
+        "var m = {\"a\": [1, 2], \"b\": {}};\nfor (var i in 0..3) {\n  print m[\"a\"][i];\n}\n"
+    );
+}
+
+#[test]
+fn puts_match_arms_on_their_own_lines() {
+    let source = "match(p){Point(0,y)if y>1=>print y; [x,... rest]=>{print rest;}, _=>{}}\n";
+    assert_eq!(
+        format(source).unwrap(),
+        "match (p) {\n  Point(0, y) if y > 1 => print y;\n  [x, ...rest] => {\n    print rest;\n  },\n  _ => {}\n}\n"
+    );
+}
+
+#[test]
+fn formats_defaults_and_named_arguments() {
+    let source = "fun f(a,b=2,...rest){}\nf(1,b:3);\n";
+    assert_eq!(format(source).unwrap(), "fun f(a, b = 2, ...rest) {}\nf(1, b: 3);\n");
+}
+
+#[test]
+fn keeps_map_entries_in_conditionals_apart() {
+    let source = "var c=x?{\"k\":1}:2;\nvar d=x?f(a:1):{\"k\":y?1:2};\n";
+    assert_eq!(
+        format(source).unwrap(),
+        "var c = x ? {\"k\": 1} : 2;\nvar d = x ? f(a: 1) : {\"k\": y ? 1 : 2};\n"
+    );
+}
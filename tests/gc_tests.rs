@@ -0,0 +1,123 @@
+use rlox::lox::Lox;
+use std::fs;
+
+fn binary_trees(max_depth: usize) -> String {
+    fs::read_to_string("./tests/test_cases/benchmark/binary_trees.lox")
+        .unwrap()
+        .replace("var maxDepth = 14;", &format!("var maxDepth = {};", max_depth))
+}
+
+#[test]
+fn collects_closure_cycles() {
+    let mut lox = Lox::new();
+    lox.run(r#"
+        fun makeCounter() {
+          var count = 0;
+          fun counter() { count = count + 1; return count; }
+          return counter;
+        }
+
+        var i = 0;
+        while (i < 500) { makeCounter(); i = i + 1; }
+    "#);
+
+    let leaked = lox.heap().stats().live();
+    let freed = lox.heap().collect();
+
+    assert!(freed >= 500, "expected the counter cycles to be freed, got {}", freed);
+    assert!(lox.heap().stats().live() < leaked);
+}
+
+#[test]
+fn collects_instance_cycles() {
+    let mut lox = Lox::new();
+    lox.run(r#"
+        class Node {}
+        fun link() {
+          var a = Node();
+          var b = Node();
+          a.other = b;
+          b.other = a;
+        }
+
+        var i = 0;
+        while (i < 200) { link(); i = i + 1; }
+    "#);
+
+    lox.heap().collect();
+    assert_eq!(lox.heap().stats().live_instances, 0);
+}
+
+#[test]
+fn collects_cycles_through_collections() {
+    let mut lox = Lox::new();
+    lox.run(r#"
+        fun list() { var self = []; fun f() { return self; } self = [f]; }
+        fun map() { var self = {}; fun f() { return self; } self["f"] = f; }
+        fun generator() { var self; fun* g() { yield self; } self = g(); }
+        fun next() { var self; fun* g() { yield self; } self = g().next; }
+        fun rest(...self) { fun f() { return self; } self[0] = f; }
+
+        var i = 0;
+        while (i < 2000) { list(); map(); generator(); next(); rest(nil); i = i + 1; }
+    "#);
+
+    lox.heap().collect();
+    let stats = lox.heap().stats();
+
+    assert!(stats.freed >= 8 * 2000, "expected the cycles to be freed, got {}", stats.freed);
+    assert_eq!((stats.live_lists, stats.live_maps, stats.live_generators), (0, 0, 0));
+    // only the globals
+    assert_eq!(stats.live_environments, 1);
+}
+
+#[test]
+fn keeps_reachable_objects_under_stress() {
+    let mut lox = Lox::new();
+    lox.heap().stress = true;
+    lox.run(r#"
+        fun makeCounter() {
+          var count = 0;
+          fun counter() { count = count + 1; return count; }
+          return counter;
+        }
+
+        var counter = makeCounter();
+        counter();
+        gc();
+        var value = counter();
+    "#);
+
+    lox.run("gc(); value = value + counter();");
+    lox.run("print value;");
+
+    let stats = lox.heap().stats();
+    assert!(stats.collections >= stats.allocated);
+    // the globals and the environment captured by `counter`
+    assert_eq!(stats.live_environments, 2);
+}
+
+#[test]
+fn binary_trees_runs_in_bounded_memory() {
+    let mut small = Lox::new();
+    small.run(&binary_trees(4));
+    let small = small.heap().stats();
+
+    let mut large = Lox::new();
+    large.run(&binary_trees(8));
+    let large = large.heap().stats();
+
+    // sixteen times the allocations, but the peak only follows the long lived tree
+    assert!(large.allocated > 16 * small.allocated);
+    assert!(large.peak_live < 4 * 1024, "peak live objects: {}", large.peak_live);
+}
+
+#[test]
+#[ignore = "takes minutes, run with --ignored"]
+fn full_binary_trees_runs_in_bounded_memory() {
+    let mut lox = Lox::new();
+    lox.run_file("./tests/test_cases/benchmark/binary_trees.lox").unwrap();
+
+    let stats = lox.heap().stats();
+    assert!(stats.peak_live < 128 * 1024, "peak live objects: {}", stats.peak_live);
+}
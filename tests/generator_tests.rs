@@ -0,0 +1,105 @@
+mod common;
+
+use common::run;
+use rlox::lox::Status;
+
+#[test]
+fn yields_values_lazily() {
+    let (status, output) = run(
+        r#"
+        fun* count(n) {
+          print "start";
+          for (var i = 0; i < n; i = i + 1) {
+            yield i;
+          }
+        }
+        var numbers = count(2);
+        print "created";
+        print numbers.next();
+        print numbers.next();
+        print numbers.next();
+        print numbers.next();
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "created\nstart\n0\n1\nnull\nnull\n");
+}
+
+#[test]
+fn done_tells_the_end_apart_from_nil() {
+    let (status, output) = run(
+        r#"
+        fun* values() {
+          yield nil;
+          yield 1;
+        }
+        var g = values();
+        print g.done();
+        var value = g.next();
+        while (!g.done()) {
+          print value;
+          value = g.next();
+        }
+        print g.done();
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "false\nnull\n1\ntrue\n");
+}
+
+#[test]
+fn keeps_scopes_between_yields() {
+    let (status, output) = run(
+        r#"
+        fun* pairs() {
+          for (var i = 0; i < 3; i = i + 1) {
+            for (var j = 0; j < 3; j = j + 1) {
+              if (j == 2) break;
+              fun f() { return i * 10 + j; }
+              yield f;
+            }
+            if (i == 1) return;
+          }
+        }
+        var g = pairs();
+        var f = g.next();
+        while (f != nil) {
+          print f();
+          f = g.next();
+        }
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "0\n1\n10\n11\n");
+}
+
+#[test]
+fn errors_finish_the_generator() {
+    let (status, output) = run(
+        r#"
+        fun* broken() {
+          yield 1;
+          nil();
+        }
+        var g = broken();
+        print g.next();
+        try { g.next(); } catch (e) { print e.stack; }
+        print g.next();
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "1\n[line 4] in broken()\n[line 8] in script\nnull\n");
+}
+
+#[test]
+fn yield_only_in_generators() {
+    assert_eq!(run("yield 1;").0, Status::StaticError);
+    assert_eq!(run("fun f() { yield 1; }").0, Status::StaticError);
+    assert_eq!(run("fun* f() { fun g() { yield 1; } }").0, Status::StaticError);
+    assert_eq!(run("fun* f() { try { yield 1; } finally {} }").0, Status::StaticError);
+    assert_eq!(run("fun* f() { return 1; }").0, Status::StaticError);
+}
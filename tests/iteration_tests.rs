@@ -0,0 +1,109 @@
+mod common;
+
+use common::run;
+use rlox::lox::Status;
+
+#[test]
+fn iterates_over_builtin_values() {
+    let (status, output) = run(
+        r#"
+        for (var c in "ab") print c;
+        for (var x in [1, 2]) print x * 10;
+        var ages = {"ann": 30, "bob": 40};
+        for (var name in ages) print "${name} ${ages[name]}";
+        for (var i in 2..4) print i;
+        for (var i in 3..3) print "never";
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "a\nb\n10\n20\nann 30\nbob 40\n2\n3\n");
+}
+
+#[test]
+fn iterates_over_instances_and_generators() {
+    let (status, output) = run(
+        r#"
+        class Countdown {
+          init(n) { this.n = n; }
+          next() {
+            if (this.n == 0) return nil;
+            this.n = this.n - 1;
+            return this.n + 1;
+          }
+        }
+        class Pair {
+          iter() { return ["left", "right"]; }
+        }
+        fun* squares(n) {
+          for (var i in 1..n) yield i * i;
+        }
+        for (var n in Countdown(2)) print n;
+        for (var side in Pair()) print side;
+        for (var square in squares(4)) print square;
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "2\n1\nleft\nright\n1\n4\n9\n");
+}
+
+#[test]
+fn binds_a_fresh_variable_each_iteration() {
+    let (status, output) = run(
+        r#"
+        var closures = [nil, nil, nil];
+        for (var i in 0..3) {
+          fun get() { return i; }
+          closures[i] = get;
+          if (i == 1) break;
+        }
+        print closures[0]();
+        print closures[1]();
+        print closures[2];
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "0\n1\nnull\n");
+}
+
+#[test]
+fn breaks_out_of_loops_in_generators() {
+    let (status, output) = run(
+        r#"
+        fun* pairs() {
+          for (var a in [1, 2, 3]) {
+            for (var b in "xy") {
+              if (a == 2) break;
+              yield "${a}${b}";
+            }
+          }
+        }
+        for (var pair in pairs()) print pair;
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "1x\n1y\n3x\n3y\n");
+}
+
+#[test]
+fn reports_values_that_cannot_be_iterated() {
+    let (status, output) = run(
+        r#"
+        try {
+          for (var x in 42) print x;
+        } catch (e) {
+          print e.message;
+        }
+        for (var x in nil) print x;
+        "#,
+    );
+
+    assert_eq!(status, Status::RuntimeError);
+    assert_eq!(
+        output,
+        "Can only iterate over strings, lists, maps, ranges, generators and instances with 'iter' or 'next'.\n"
+    );
+}
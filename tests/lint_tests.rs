@@ -0,0 +1,126 @@
+use rlox::interpreter::Interpreter;
+use rlox::lint::{Lints, Rule};
+use rlox::parser::Parser;
+use rlox::resolver::Resolver;
+use rlox::scanner::Scanner;
+use std::fs;
+use std::process::Command;
+
+// the warnings the resolver gives with the lints, one per line
+fn lint(source: &str, lints: Lints) -> Vec<String> {
+    let (tokens, _) = Scanner::new(source.to_string()).scan_tokens();
+    let statements = Parser::new(tokens).parse().unwrap();
+
+    let mut interpreter = Interpreter::new();
+    let mut resolver = Resolver::with_lints(&mut interpreter, lints);
+    resolver.resolve_statements(&statements).unwrap();
+    resolver.warnings().iter().map(|warning| warning.to_string()).collect()
+}
+
+fn only(rule: Rule) -> Lints {
+    let mut lints = Lints::default();
+    lints.enable(rule);
+    lints
+}
+
+#[test]
+fn unused_locals_and_parameters() {
+    let source = "fun f(a, b, _c) {\n  var d = 1;\n  var _e = 2;\n  fun g() {}\n  return a;\n}\n";
+
+    assert_eq!(
+        lint(source, Lints::all()),
+        [
+            "[line 1] Warning at 'b': Parameter 'b' is never used. [unused-parameter]",
+            "[line 2] Warning at 'd': Local variable 'd' is never used. [unused-variable]",
+            "[line 4] Warning at 'g': Local function 'g' is never used. [unused-variable]",
+        ]
+    );
+    assert_eq!(lint(source, only(Rule::UnusedParameter)).len(), 1);
+}
+
+#[test]
+fn globals_are_not_linted() {
+    assert!(lint("var a = 1;\nfun f(x) { return x; }\nclass A { m() { return this; } }", Lints::all()).is_empty());
+}
+
+#[test]
+fn assignments_that_are_never_read() {
+    let source = "{\n  var a;\n  a = 1;\n  a = 2;\n  var b;\n  b = 1;\n  print b;\n}\n";
+
+    assert_eq!(
+        lint(source, Lints::all()),
+        ["[line 3] Warning at 'a': Value assigned to 'a' is never read. [unread-assignment]"]
+    );
+}
+
+#[test]
+fn compound_assignments_are_writes() {
+    let source = "{\n  var a = 0;\n  a += 1;\n  a++;\n  var b = 0;\n  b += 1;\n  print b;\n}\n";
+
+    assert_eq!(
+        lint(source, Lints::all()),
+        ["[line 3] Warning at 'a': Value assigned to 'a' is never read. [unread-assignment]"]
+    );
+}
+
+#[test]
+fn closures_count_as_reads() {
+    let source = "fun counter() {\n  var i = 0;\n  fun next() { i = i + 1; return i; }\n  return next;\n}\n";
+    assert!(lint(source, Lints::all()).is_empty());
+}
+
+#[test]
+fn code_after_return() {
+    let source = "fun f() {\n  return 1;\n  print 2;\n  print 3;\n}\n";
+
+    assert_eq!(
+        lint(source, Lints::all()),
+        ["[line 2] Warning at 'return': Code after 'return' is never executed. [unreachable-code]"]
+    );
+}
+
+#[test]
+fn shadowing_outer_locals() {
+    let source = "var a = 1;\n{\n  var a = 2;\n  fun f(a) { return a; }\n  print f(a);\n}\n";
+
+    assert_eq!(
+        lint(source, Lints::all()),
+        ["[line 4] Warning at 'a': 'a' shadows the local declared on line 3. [shadowing]"]
+    );
+}
+
+#[test]
+fn constant_comparisons() {
+    let source = "var a = 1;\nprint 1 < 2;\nprint a == a;\nprint (\"a\") != \"b\";\nprint a <= 2;\nprint a != a;\n";
+
+    assert_eq!(
+        lint(source, only(Rule::ConstantComparison)),
+        [
+            "[line 2] Warning at '<': This comparison always has the same result. [constant-comparison]",
+            "[line 4] Warning at '!=': This comparison always has the same result. [constant-comparison]",
+        ]
+    );
+}
+
+#[test]
+fn warnings_do_not_stop_the_program() {
+    let path = std::env::temp_dir().join(format!("lox-lint-{}.lox", std::process::id()));
+    fs::write(&path, "{\n  var a = 1;\n  var b = 2;\n  print b;\n}\n").unwrap();
+
+    let lox = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_lox")).args(args).arg(&path).output().unwrap()
+    };
+    let linted = lox(&["--lint"]);
+    let allowed = lox(&["--lint", "--allow=unused-variable"]);
+    let plain = lox(&[]);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(linted.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&linted.stdout), "2\n");
+    assert_eq!(
+        String::from_utf8_lossy(&linted.stderr),
+        "[line 2] Warning at 'a': Local variable 'a' is never used. [unused-variable]\n"
+    );
+    assert!(allowed.stderr.is_empty());
+    assert!(plain.stderr.is_empty());
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use common::run;
+use rlox::lox::Status;
+
+#[test]
+fn matches_literals_alternatives_and_wildcards() {
+    let (status, output) = run(
+        r#"
+        fun name(value) {
+          match (value) {
+            1 => return "one";
+            -1 => return "minus one";
+            "a" | "b" => return "letter";
+            nil | false => return "nothing";
+            _ => return "other";
+          }
+        }
+        print name(1);
+        print name(-1);
+        print name("b");
+        print name(false);
+        print name(2);
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "one\nminus one\nletter\nnothing\nother\n");
+}
+
+#[test]
+fn destructures_instances_and_lists() {
+    let (status, output) = run(
+        r#"
+        class Point {
+          init(x, y) {
+            this.x = x;
+            this.y = y;
+          }
+        }
+        fun describe(value) {
+          match (value) {
+            Point(0, 0) => print "origin";
+            Point(x, y) if x == y => print "diagonal ${x}";
+            Point(x, _) => print "x is ${x}";
+            [] => print "empty";
+            [only] => print "just ${only}";
+            [first, ...rest] => print "${first} then ${rest}";
+            other => print "not a point or list: ${other}";
+          }
+        }
+        describe(Point(0, 0));
+        describe(Point(2, 2));
+        describe(Point(3, 4));
+        describe([]);
+        describe([1]);
+        describe([1, 2, 3]);
+        describe("text");
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(
+        output,
+        "origin\ndiagonal 2\nx is 3\nempty\njust 1\n1 then [2, 3]\nnot a point or list: text\n"
+    );
+}
+
+#[test]
+fn binds_names_per_arm() {
+    let (status, output) = run(
+        r#"
+        var x = "outer";
+        var closures = [];
+        match ([1, 2]) {
+          [x, y] if x > y => print "never";
+          [y, x] => {
+            fun get() { return x; }
+            closures = [get];
+          }
+        }
+        print x;
+        print closures[0]();
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "outer\n2\n");
+}
+
+#[test]
+fn matches_inside_generators() {
+    let (status, output) = run(
+        r#"
+        fun* flatten(values) {
+          for (var value in values) {
+            match (value) {
+              [first, ...rest] => {
+                yield first;
+                for (var other in rest) yield other;
+              }
+              _ => yield value;
+            }
+          }
+        }
+        for (var value in flatten([1, [2, 3], 4])) print value;
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "1\n2\n3\n4\n");
+}
+
+#[test]
+fn reports_values_no_arm_matches() {
+    let (status, output) = run(
+        r#"
+        try {
+          match (3) {
+            1 | 2 => print "small";
+          }
+        } catch (e) {
+          print e.message;
+          print e.line;
+        }
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "Non-exhaustive match, no arm matches 3.\n3\n");
+}
+
+#[test]
+fn rejects_bindings_in_alternatives() {
+    assert_eq!(run("match (1) { [a] | a => print a; }").0, Status::StaticError);
+    assert_eq!(run("match (1) { [a, a] => print a; }").0, Status::StaticError);
+}
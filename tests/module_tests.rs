@@ -0,0 +1,150 @@
+use rlox::interpreter::Output;
+use rlox::lox::{Lox, Status};
+use std::cell::RefCell;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::rc::Rc;
+
+// a directory of module files for one test
+fn modules(test: &str, files: &[(&str, &str)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("lox-modules-{}-{}", test, std::process::id()));
+    for (name, source) in files {
+        let path = root.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, source).unwrap();
+    }
+    root
+}
+
+fn run(lox: &mut Lox, file: PathBuf) -> (Status, String) {
+    let buffer = Rc::new(RefCell::new(Vec::new()));
+    lox.set_output(Output::Buffer(Rc::clone(&buffer)));
+    let status = lox.run_file(file).unwrap();
+    let output = String::from_utf8(buffer.borrow().clone()).unwrap();
+    (status, output)
+}
+
+#[test]
+fn imports_exported_names() {
+    let root = modules(
+        "exports",
+        &[
+            (
+                "main.lox",
+                "import \"shapes/point.lox\" as point;\nfrom \"shapes/point.lox\" import Point, origin;\nvar p = Point(1, 2);\nprint p.sum();\nprint point.origin().sum();\nprint origin().sum();\nprint point;\n",
+            ),
+            (
+                "shapes/point.lox",
+                "print \"loading\";\nvar offset = 10;\nexport class Point {\n  init(x, y) { this.x = x; this.y = y; }\n  sum() { return this.x + this.y + offset; }\n}\nexport fun origin() { return Point(0, 0); }\n",
+            ),
+        ],
+    );
+
+    let (status, output) = run(&mut Lox::new(), root.join("main.lox"));
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(status, Status::Success);
+    // runs once, and its functions see its own globals
+    assert_eq!(output, "loading\n13\n10\n10\n<module point>\n");
+}
+
+#[test]
+fn modules_have_their_own_globals() {
+    let root = modules(
+        "globals",
+        &[
+            (
+                "main.lox",
+                "var count = 100;\nimport \"counter.lox\" as counter;\ncounter.next();\nprint counter.next();\nprint counter.count;\nprint count;\nprint counter.secret;\n",
+            ),
+            ("counter.lox", "export var count = 0;\nvar secret = 1;\nexport fun next() { count = count + 1; return count; }\n"),
+        ],
+    );
+
+    let (status, output) = run(&mut Lox::new(), root.join("main.lox"));
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(status, Status::RuntimeError);
+    assert_eq!(output, "2\n2\n100\n");
+}
+
+#[test]
+fn uses_the_search_path() {
+    let root = modules(
+        "search",
+        &[
+            ("app/main.lox", "from \"strings.lox\" import shout;\nprint shout(\"hi\");\n"),
+            ("lib/strings.lox", "export fun shout(s) { return s + \"!\"; }\n"),
+        ],
+    );
+
+    let (missing, _) = run(&mut Lox::new(), root.join("app/main.lox"));
+
+    let mut lox = Lox::new();
+    lox.search_path().push(root.join("lib"));
+    let (status, output) = run(&mut lox, root.join("app/main.lox"));
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(missing, Status::RuntimeError);
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "hi!\n");
+}
+
+#[test]
+fn reports_circular_imports() {
+    let root = modules(
+        "cycle",
+        &[
+            ("a.lox", "import \"b.lox\";\n"),
+            ("b.lox", "import \"c.lox\";\n"),
+            ("c.lox", "import \"a.lox\";\n"),
+        ],
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_lox")).arg(root.join("a.lox")).output().unwrap();
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(output.status.code(), Some(70));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "Circular import: a.lox -> b.lox -> c.lox -> a.lox.\n"
+    );
+}
+
+#[test]
+fn compile_errors_in_modules_are_static_errors() {
+    let root = modules(
+        "broken",
+        &[
+            ("main.lox", "try { import \"scan.lox\"; } catch (e) { print \"caught\"; }\n"),
+            ("scan.lox", "var a = 1 @;\n"),
+            ("parse.lox", "import \"bad.lox\";\n"),
+            ("bad.lox", "var = 1;\n"),
+        ],
+    );
+
+    let scan = Command::new(env!("CARGO_BIN_EXE_lox")).arg(root.join("main.lox")).output().unwrap();
+    let parse = Command::new(env!("CARGO_BIN_EXE_lox")).arg(root.join("parse.lox")).output().unwrap();
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(scan.status.code(), Some(65));
+    assert!(scan.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8_lossy(&scan.stderr),
+        "[line 1:11] Error: Unexpected character '@'.\nCould not compile module 'scan.lox'.\n"
+    );
+    assert_eq!(parse.status.code(), Some(65));
+    assert_eq!(
+        String::from_utf8_lossy(&parse.stderr),
+        "1 at EQUAL Expect variable name.\nCould not compile module 'bad.lox'.\n"
+    );
+}
+
+#[test]
+fn exports_must_be_top_level() {
+    let mut lox = Lox::new();
+    lox.set_output(Output::Discard);
+    assert_eq!(lox.run("{ export var a = 1; }"), Status::StaticError);
+    assert_eq!(lox.run("var import = 1; var from = import; print from;"), Status::Success);
+}
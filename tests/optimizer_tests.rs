@@ -0,0 +1,117 @@
+use rlox::ast::Expr;
+use rlox::optimizer::Optimizer;
+use rlox::parser::Parser;
+use rlox::scanner::Scanner;
+use rlox::statement::Stmt;
+use rlox::tokens::Literal;
+use std::fs;
+use std::process::{Command, Output};
+
+fn optimize(source: &str, level: u8) -> Vec<Stmt> {
+    let (tokens, _) = Scanner::new(source.to_string()).scan_tokens();
+    let statements = Parser::new(tokens).parse().unwrap();
+    Optimizer::new(level).optimize(&statements).unwrap()
+}
+
+fn run(path: &str, level: u8) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_lox"))
+        .arg(format!("-O{}", level))
+        .arg(path)
+        .output()
+        .unwrap()
+}
+
+fn printed(statement: &Stmt) -> &Expr {
+    match statement {
+        Stmt::Print(expr) => expr,
+        other => panic!("expected a print statement, got {:?}", other),
+    }
+}
+
+#[test]
+fn folds_constant_expressions() {
+    let statements = optimize(r#"print 1 + 2 * -3; print "a" + "b"; print !(1 < 2);"#, 1);
+
+    assert!(matches!(printed(&statements[0]), Expr::Literal { value: Literal::Number(n) } if *n == -5.0));
+    assert!(matches!(printed(&statements[1]), Expr::Literal { value: Literal::String(s) } if s == "ab"));
+    assert!(matches!(printed(&statements[2]), Expr::Literal { value: Literal::Boolean(false) }));
+}
+
+#[test]
+fn keeps_expressions_that_fail_at_runtime() {
+    let statements = optimize(r#"print "a" - 1; print -"b";"#, 2);
+
+    assert!(matches!(printed(&statements[0]), Expr::Binary { .. }));
+    assert!(matches!(printed(&statements[1]), Expr::Unary { .. }));
+}
+
+#[test]
+fn simplifies_logical_expressions() {
+    let statements = optimize("print nil or x; print 1 or x; print false and x; print true and x;", 1);
+
+    assert!(matches!(printed(&statements[0]), Expr::Variable { .. }));
+    assert!(matches!(printed(&statements[1]), Expr::Literal { value: Literal::Number(_) }));
+    assert!(matches!(printed(&statements[2]), Expr::Literal { value: Literal::Boolean(false) }));
+    assert!(matches!(printed(&statements[3]), Expr::Variable { .. }));
+}
+
+#[test]
+fn removes_dead_code() {
+    let statements = optimize(
+        r#"
+        if (false) print 1;
+        if (1 > 2) print 2; else print 3;
+        while (false) print 4;
+        fun f() { return 5; print 6; }
+        "#,
+        2,
+    );
+
+    assert_eq!(statements.len(), 2);
+    assert!(matches!(printed(&statements[0]), Expr::Literal { value: Literal::Number(n) } if *n == 3.0));
+    match &statements[1] {
+        Stmt::Function { body, .. } => assert_eq!(body.len(), 1),
+        other => panic!("expected a function, got {:?}", other),
+    }
+}
+
+#[test]
+fn level_one_keeps_statements() {
+    let statements = optimize("if (false) print 1; while (false) print 2;", 1);
+    assert_eq!(statements.len(), 2);
+}
+
+#[test]
+fn preserves_semantics_of_test_cases() {
+    let directories = [
+        "assignment", "block", "bool", "closure", "for", "if", "logical_operator",
+        "nil", "operator", "return", "string", "variable", "while",
+    ];
+    // `{}` is an empty map, these never stop
+    let endless = ["statement_condition.lox", "statement_increment.lox"];
+
+    for directory in directories.iter() {
+        let mut cases: Vec<_> = fs::read_dir(format!("./tests/test_cases/{}", directory))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        cases.sort();
+
+        for case in cases {
+            if endless.iter().any(|name| case.ends_with(name)) {
+                continue;
+            }
+            let case = case.to_str().unwrap();
+            let unoptimized = run(case, 0);
+            let optimized = run(case, 2);
+
+            assert_eq!(
+                String::from_utf8_lossy(&unoptimized.stdout),
+                String::from_utf8_lossy(&optimized.stdout),
+                "{} prints something else when optimized",
+                case
+            );
+            assert_eq!(unoptimized.status, optimized.status, "{}", case);
+        }
+    }
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use common::run;
+use rlox::lox::Status;
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn evaluates_defaults_at_each_call() {
+    let (status, output) = run(
+        r#"
+        var calls = 0;
+        fun count() { calls = calls + 1; return calls; }
+        fun f(a, b = a * 10 + count()) { print b; }
+        f(1);
+        f(2);
+        f(3, 0);
+        print calls;
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "11\n22\n0\n2\n");
+}
+
+#[test]
+fn passes_arguments_by_name() {
+    let (status, output) = run(
+        r#"
+        fun f(a, b = 2, c = 3) { print "${a} ${b} ${c}"; }
+        f(1, c: 4);
+        f(c: 5, a: 6);
+        class Point {
+          init(x = 0, y = 0) { this.x = x; this.y = y; }
+        }
+        var p = Point(y: 7);
+        print p.x + p.y;
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "1 2 4\n6 2 5\n7\n");
+}
+
+#[test]
+fn collects_extra_arguments() {
+    let (status, output) = run(
+        r#"
+        fun f(a, ...rest) { print rest; }
+        f(1);
+        f(1, 2, 3);
+        fun g(...all) { return len(all); }
+        print g();
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "[]\n[2, 3]\n0\n");
+}
+
+#[test]
+fn reports_bad_calls() {
+    let (status, output) = run(
+        r#"
+        fun f(a, b = 2) {}
+        fun g(a, ...rest) {}
+        fun h(a) {}
+        try { f(); } catch (e) { print e.message; }
+        try { f(1, 2, 3); } catch (e) { print e.message; }
+        try { g(); } catch (e) { print e.message; }
+        try { f(b: 1); } catch (e) { print e.message; }
+        try { f(1, c: 2); } catch (e) { print e.message; }
+        try { h(1, a: 2); } catch (e) { print e.message; }
+        try { len(x: 1); } catch (e) { print e.message; }
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(
+        output,
+        "Expected 1 to 2 arguments but got 0.\n\
+         Expected 1 to 2 arguments but got 3.\n\
+         Expected at least 1 argument but got 0.\n\
+         Expected 1 to 2 arguments but got 1.\n\
+         No parameter named 'c'.\n\
+         Parameter 'a' got more than one argument.\n\
+         <native fn> doesn't take named arguments.\n"
+    );
+}
+
+#[test]
+fn rejects_bad_parameter_lists() {
+    assert_eq!(run("fun f(a = 1, b) {}").0, Status::StaticError);
+    assert_eq!(run("fun f(...a, b) {}").0, Status::StaticError);
+    assert_eq!(run("fun f(a) {} f(a: 1, 2);").0, Status::StaticError);
+}
+
+#[test]
+fn reports_parse_errors_at_the_token() {
+    let path = std::env::temp_dir().join(format!("lox-parameters-{}.lox", std::process::id()));
+    fs::write(&path, "f(a: 1, 2);\nfun g(a = 1, b) {}\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_lox")).arg(&path).output().unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(output.status.code(), Some(65));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "1 at NUMBER Positional arguments can't follow named ones.\n\
+         2 at IDENTIFIER A parameter without a default can't follow one with a default.\n"
+    );
+}
@@ -0,0 +1,70 @@
+use rlox::interpreter::Output;
+use rlox::lox::Lox;
+use rlox::repl::{is_complete, Repl, Step};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+fn session(lines: &[&str]) -> String {
+    let buffer = Rc::new(RefCell::new(Vec::new()));
+    let mut lox = Lox::new();
+    lox.set_output(Output::Buffer(Rc::clone(&buffer)));
+
+    let mut repl = Repl::new(&mut lox);
+    for line in lines {
+        if let Step::Quit = repl.feed(line) {
+            break;
+        }
+    }
+
+    let output = String::from_utf8(buffer.borrow().clone()).unwrap();
+    output
+}
+
+#[test]
+fn detects_unbalanced_input() {
+    assert!(is_complete("print 1;"));
+    assert!(is_complete("fun f() { return (1); }"));
+    assert!(!is_complete("fun f() {"));
+    assert!(!is_complete("print (1 +"));
+    assert!(is_complete("print \"{\";"));
+    assert!(is_complete(r#"print "\"{";"#));
+    assert!(!is_complete("print \"unterminated"));
+    assert!(is_complete(r#"print r"\";"#));
+    assert!(is_complete(r#"print r"\" + "{";"#));
+    assert!(!is_complete(r#"print r"\"#));
+    assert!(!is_complete(r#"var"\";"#));
+    assert!(is_complete("print 1; // {"));
+    assert!(!is_complete("print 1; /* {"));
+    assert!(is_complete("print /* ( /* { */ */ 1;"));
+}
+
+#[test]
+fn echoes_expressions() {
+    assert_eq!(session(&["1 + 2", "var a = 3;", "a * 2;", "print a;"]), "3\n6\n3\n");
+}
+
+#[test]
+fn buffers_multi_line_input() {
+    let output = session(&["fun add(a, b) {", "  return a + b;", "}", "add(1,", "2)"]);
+    assert_eq!(output, "3\n");
+}
+
+#[test]
+fn lists_and_resets_globals() {
+    let output = session(&["var answer = 42;", ":env", ":reset", ":env"]);
+    assert_eq!(
+        output,
+        "Error = Error\nanswer = 42\nclock = <native fn>\ngc = <native fn>\nlen = <native fn>\n\
+         Error = Error\nclock = <native fn>\ngc = <native fn>\nlen = <native fn>\n"
+    );
+}
+
+#[test]
+fn loads_files_and_quits() {
+    let output = session(&[
+        ":load ./tests/test_cases/closure/nested_closure.lox",
+        ":quit",
+        "print 1;",
+    ]);
+    assert_eq!(output, "a\nb\nc\n");
+}
@@ -0,0 +1,66 @@
+use rlox::lox::{Lox, Status};
+use std::fs;
+use std::process::Command;
+
+// `// Error at 'a': ...` comments become the diagnostics the resolver should print
+fn expected_errors(source: &str) -> String {
+    source
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let (_, error) = line.split_once("// Error at ")?;
+            Some(format!("[line {}] Error at {}\n", index + 1, error))
+        })
+        .collect()
+}
+
+#[test]
+fn reports_static_errors_at_their_token() {
+    let cases = [
+        "variable/duplicate_local.lox",
+        "variable/duplicate_parameter.lox",
+        "variable/use_local_in_initializer.lox",
+        "return/at_top_level.lox",
+        "this/this_at_top_level.lox",
+        "this/this_in_top_level_function.lox",
+        "constructor/return_value.lox",
+    ];
+
+    for case in cases {
+        let path = format!("./tests/test_cases/{}", case);
+        let output = Command::new(env!("CARGO_BIN_EXE_lox")).arg(&path).output().unwrap();
+
+        assert_eq!(output.status.code(), Some(65), "{}", case);
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            expected_errors(&fs::read_to_string(&path).unwrap()),
+            "{}",
+            case
+        );
+    }
+}
+
+#[test]
+fn collects_every_error() {
+    let path = std::env::temp_dir().join(format!("lox-resolver-{}.lox", std::process::id()));
+    fs::write(&path, "fun f(a, a) {\n  var b;\n  var b;\n}\nreturn 1;\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_lox")).arg(&path).output().unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(output.status.code(), Some(65));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "[line 1] Error at 'a': Already a variable with this name in this scope.\n\
+         [line 3] Error at 'b': Already a variable with this name in this scope.\n\
+         [line 5] Error at 'return': Can't return from top-level code.\n"
+    );
+}
+
+#[test]
+fn survives_duplicate_declarations() {
+    // used to panic and take the REPL down with it
+    let mut lox = Lox::new();
+    assert_eq!(lox.run("{ var a = 1; var a = 2; }"), Status::StaticError);
+    assert_eq!(lox.run("{ var a = 1; print a; }"), Status::Success);
+}
@@ -1,6 +1,270 @@
+use rlox::errors::ScanError;
+use rlox::lox::{Lox, Status};
 use rlox::scanner::Scanner;
+use rlox::tokens::TokenType;
+
+fn scan(source: &str) -> (Vec<TokenType>, Vec<ScanError>) {
+    let (tokens, errors) = Scanner::new(source.to_string()).scan_tokens();
+    (tokens.into_iter().map(|token| token.token_type).collect(), errors)
+}
+
+fn error(line: usize, column: usize, message: &str) -> ScanError {
+    ScanError {
+        line,
+        column,
+        message: message.to_string(),
+    }
+}
 
 #[test]
 fn it_adds_two() {
     assert_eq!(4, 4);
 }
+
+#[test]
+fn reports_every_unexpected_character() {
+    let (tokens, errors) = scan("var a = 1;\nprint a ~ 2;\n  @ #");
+
+    assert_eq!(
+        errors,
+        vec![
+            error(2, 9, "Unexpected character '~'."),
+            error(3, 3, "Unexpected character '@'."),
+            error(3, 5, "Unexpected character '#'."),
+        ]
+    );
+    // scanning carries on after an error
+    assert_eq!(
+        &tokens[5..],
+        &[TokenType::PRINT, TokenType::IDENTIFIER, TokenType::NUMBER, TokenType::SEMICOLON, TokenType::EOF]
+    );
+}
+
+#[test]
+fn scans_the_pipe_of_match_patterns() {
+    // the reference implementation has no `|`, it reports an unexpected character
+    let (tokens, errors) = scan("print a | 2;");
+
+    assert!(errors.is_empty());
+    assert_eq!(
+        tokens,
+        [TokenType::PRINT, TokenType::IDENTIFIER, TokenType::PIPE, TokenType::NUMBER, TokenType::SEMICOLON, TokenType::EOF]
+    );
+}
+
+#[test]
+fn reports_unterminated_string_at_its_start() {
+    let (tokens, errors) = scan("print 1;\n  \"abc\n\ndef");
+
+    assert_eq!(errors, vec![error(2, 3, "Unterminated string.")]);
+    assert_eq!(tokens.last(), Some(&TokenType::EOF));
+}
+
+#[test]
+fn scans_identifier_at_end_of_input() {
+    let (tokens, errors) = scan("abc");
+
+    assert!(errors.is_empty());
+    assert_eq!(tokens, vec![TokenType::IDENTIFIER, TokenType::EOF]);
+}
+
+#[test]
+fn scan_errors_stop_execution() {
+    let mut lox = Lox::new();
+    assert_eq!(lox.run("var a = 1; @"), Status::StaticError);
+    // nothing ran, so `a` was never defined
+    assert_eq!(lox.run("print a;"), Status::RuntimeError);
+}
+
+#[test]
+fn skips_nested_block_comments() {
+    let (tokens, errors) = scan("print /* outer /* inner */\n still outer */ 1;\nprint 2 / 3;");
+
+    assert!(errors.is_empty());
+    assert_eq!(
+        tokens,
+        vec![
+            TokenType::PRINT,
+            TokenType::NUMBER,
+            TokenType::SEMICOLON,
+            TokenType::PRINT,
+            TokenType::NUMBER,
+            TokenType::SLASH,
+            TokenType::NUMBER,
+            TokenType::SEMICOLON,
+            TokenType::EOF,
+        ]
+    );
+
+    // the newline inside the comment still counts
+    let (tokens, _) = Scanner::new("/*\n\n*/ x".to_string()).scan_tokens();
+    assert_eq!(tokens[0].line, 3);
+}
+
+#[test]
+fn reports_unterminated_block_comment() {
+    let (_, errors) = scan("print 1;\n /* one /* two */\n");
+    assert_eq!(errors, vec![error(2, 2, "Unterminated block comment.")]);
+}
+
+#[test]
+fn captures_doc_comments() {
+    let source = "/**\n * Adds two numbers.\n * Returns the sum.\n */\nfun add(a, b) { return a + b; }\n/**/ /* plain */ class A {}";
+    let mut scanner = Scanner::new(source.to_string());
+    let (tokens, _) = scanner.scan_tokens();
+
+    let docs = scanner.doc_comments();
+    assert_eq!(docs.len(), 1);
+    assert_eq!(docs[0].text, "Adds two numbers.\nReturns the sum.");
+    assert_eq!(docs[0].line, 1);
+    assert_eq!(tokens[docs[0].token].token_type, TokenType::FUN);
+}
+
+fn string_value(source: &str) -> String {
+    let (tokens, errors) = Scanner::new(source.to_string()).scan_tokens();
+    assert!(errors.is_empty(), "{:?}", errors);
+    tokens[0].literal.to_string()
+}
+
+#[test]
+fn translates_escape_sequences() {
+    assert_eq!(string_value(r#""a\tb\nc\r\\\"\0""#), "a\tb\nc\r\\\"\0");
+    assert_eq!(string_value(r#""\x41\x7e""#), "A~");
+    assert_eq!(string_value(r#""smile \u{1F600} \u{e9}""#), "smile \u{1F600} \u{e9}");
+}
+
+#[test]
+fn reports_malformed_escapes_at_their_column() {
+    let (_, errors) = scan("print \"ok\";\nprint \"a\\qb \\x4 \\u{110000} \\u41\";");
+
+    assert_eq!(
+        errors,
+        vec![
+            error(2, 9, "Invalid escape '\\q'."),
+            error(2, 13, "Invalid escape '\\x', expected two hex digits."),
+            error(2, 17, "Invalid unicode code point '110000'."),
+            error(2, 28, "Invalid escape '\\u', expected '{'."),
+        ]
+    );
+}
+
+#[test]
+fn keeps_raw_strings_as_written() {
+    assert_eq!(string_value(r#"r"\d+\.\d*\n""#), r"\d+\.\d*\n");
+
+    // an `r` that is not followed by a quote is still an identifier
+    let (tokens, _) = scan("r + rx");
+    assert_eq!(tokens[0], TokenType::IDENTIFIER);
+    assert_eq!(tokens[2], TokenType::IDENTIFIER);
+}
+
+#[test]
+fn splits_interpolated_strings() {
+    let (tokens, errors) = Scanner::new(r#""a ${b + "${c}"} d""#.to_string()).scan_tokens();
+
+    assert!(errors.is_empty());
+    let tokens: Vec<String> = tokens.iter().map(|token| token.to_string()).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            "INTERPOLATION \"a ${ a ",
+            "IDENTIFIER b null",
+            "PLUS + null",
+            "INTERPOLATION \"${ ",
+            "IDENTIFIER c null",
+            "STRING }\" ",
+            "STRING } d\"  d",
+            "EOF  null",
+        ]
+    );
+}
+
+fn number(source: &str) -> f64 {
+    let (tokens, errors) = Scanner::new(source.to_string()).scan_tokens();
+    assert!(errors.is_empty(), "{}: {:?}", source, errors);
+    match tokens[0].literal {
+        rlox::tokens::Literal::Number(value) => value,
+        ref other => panic!("{} is not a number: {:?}", source, other),
+    }
+}
+
+#[test]
+fn scans_numeric_literals() {
+    assert_eq!(number("0xFF"), 255.0);
+    assert_eq!(number("0Xff"), 255.0);
+    assert_eq!(number("0b1010"), 10.0);
+    assert_eq!(number("0o17"), 15.0);
+    assert_eq!(number("1e-9"), 1e-9);
+    assert_eq!(number("6.02E23"), 6.02e23);
+    assert_eq!(number("2.5e+3"), 2500.0);
+    assert_eq!(number("1_000_000"), 1_000_000.0);
+    assert_eq!(number("0xdead_beef"), 3735928559.0);
+    assert_eq!(number("0.5"), 0.5);
+}
+
+#[test]
+fn reports_malformed_numbers() {
+    let (_, errors) = scan("0x; 1e; 0b102; 1__0; 2_; 0o_7; 3.0e+;");
+    let messages: Vec<&str> = errors.iter().map(|error| error.message.as_str()).collect();
+
+    assert_eq!(
+        messages,
+        vec![
+            "Expected digits after '0x'.",
+            "Expected digits in the exponent of '1e'.",
+            "Invalid number '0b102'.",
+            "Misplaced digit separator in '1__0'.",
+            "Misplaced digit separator in '2_'.",
+            "Misplaced digit separator in '0o_7'.",
+            "Expected digits in the exponent of '3.0e+'.",
+        ]
+    );
+    assert_eq!(errors[2].column, 9);
+}
+
+fn expectations(source: &str) -> Vec<&str> {
+    source
+        .lines()
+        .filter_map(|line| line.strip_prefix("// expect: "))
+        .collect()
+}
+
+#[test]
+fn matches_the_scanning_test_cases() {
+    let mut cases: Vec<_> = std::fs::read_dir("./tests/test_cases/scanning")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    cases.sort();
+    assert_eq!(cases.len(), 6);
+
+    for case in cases {
+        let source = std::fs::read_to_string(&case).unwrap();
+        let (tokens, errors) = Scanner::new(source.clone()).scan_tokens();
+        let tokens: Vec<String> = tokens.iter().map(|token| token.to_string()).collect();
+
+        assert!(errors.is_empty(), "{:?}", case);
+        assert_eq!(tokens, expectations(&source), "{:?}", case);
+    }
+}
+
+#[test]
+fn dumps_tokens_from_the_command_line() {
+    let lox = env!("CARGO_BIN_EXE_lox");
+    let file = "./tests/test_cases/scanning/keywords.lox";
+
+    let output = std::process::Command::new(lox).args(["--tokens", file]).output().unwrap();
+    let expected: String = expectations(&std::fs::read_to_string(file).unwrap())
+        .iter()
+        .map(|line| format!("{}\n", line))
+        .collect();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+
+    let output = std::process::Command::new(lox)
+        .args(["--tokens", "./tests/test_cases/unexpected_tilde.lox"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(65));
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use common::run;
+use rlox::lox::Status;
+
+#[test]
+fn interpolates_expressions() {
+    let (status, output) = run(
+        r#"
+        var name = "Andy";
+        fun greet(age) { return "Hello ${name}, you are ${age + 1}"; }
+        print greet(41);
+        class Point {}
+        print "${Point()} and ${"nested ${1 < 2}"}";
+        "#,
+    );
+
+    assert_eq!(status, Status::Success);
+    assert_eq!(output, "Hello Andy, you are 42\nPoint instance and nested true\n");
+}
+
+#[test]
+fn interpolated_variables_are_resolved() {
+    let (_, output) = run(
+        r#"
+        var a = "global";
+        {
+            var a = "local";
+            fun show() { print "${a}"; }
+            show();
+        }
+        "#,
+    );
+    assert_eq!(output, "local\n");
+}
+
+#[test]
+fn rejects_broken_interpolation() {
+    assert_eq!(run(r#"print "${}";"#).0, Status::StaticError);
+    assert_eq!(run(r#"print "a ${1"#).0, Status::StaticError);
+    assert_eq!(run(r#"print "${undefined}";"#).0, Status::RuntimeError);
+}
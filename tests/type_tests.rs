@@ -0,0 +1,112 @@
+use rlox::checker::Checker;
+use rlox::formatter;
+use rlox::lox::{Lox, Status};
+use rlox::interpreter::Output;
+use rlox::parser::Parser;
+use rlox::scanner::Scanner;
+use std::fs;
+use std::process::Command;
+
+// the errors the type checker finds, one per line
+fn check(source: &str) -> Vec<String> {
+    let (tokens, _) = Scanner::new(source.to_string()).scan_tokens();
+    let statements = Parser::new(tokens).parse().unwrap();
+
+    let mut checker = Checker::new();
+    checker.check(&statements).unwrap();
+    checker.errors().iter().map(|error| error.to_string()).collect()
+}
+
+#[test]
+fn annotated_code_checks_and_runs() {
+    let source = "class Point {\n  x: Number;\n  y: Number;\n  init(x: Number, y: Number) {\n    this.x = x;\n    this.y = y;\n  }\n  sum(): Number { return this.x + this.y; }\n}\nfun twice(f: Function, a: Number): Number { return f(f(a)); }\nfun inc(n: Number): Number { return n + 1; }\nvar p: Point = Point(1, 2);\nvar total: Number = p.sum();\nprint twice(inc, total);\n";
+    assert!(check(source).is_empty());
+
+    let mut lox = Lox::new();
+    lox.type_check = true;
+    lox.set_output(Output::Buffer(Default::default()));
+    assert_eq!(lox.run(source), Status::Success);
+}
+
+#[test]
+fn reports_mismatches() {
+    let source = "var a: Number = \"one\";\nfun f(x: String): Number { return x; }\nf(1);\nf(\"a\", 2);\nclass C { n: Number; }\nC().n = true;\nvar b: Thing;\n";
+
+    assert_eq!(
+        check(source),
+        [
+            "[line 1] Error at 'a': Expected Number but got String.",
+            "[line 2] Error at 'return': Expected Number but got String.",
+            "[line 3] Error at ')': Expected String for argument 1 but got Number.",
+            "[line 4] Error at ')': Expected 1 argument but got 2.",
+            "[line 6] Error at 'n': Expected Number but got Bool.",
+            "[line 7] Error at 'Thing': Unknown type 'Thing'.",
+        ]
+    );
+}
+
+#[test]
+fn infers_types_of_untyped_code() {
+    let source = "print \"a\" - 1;\nvar n = 1;\nn();\nfun name() { return \"lox\"; }\nprint -name();\nprint true.field;\n";
+
+    assert_eq!(
+        check(source),
+        [
+            "[line 1] Error at '-': Operands must be numbers.",
+            "[line 3] Error at ')': Can only call functions and classes.",
+            "[line 5] Error at '-': Operand must be a number.",
+            "[line 6] Error at 'field': Only instances have properties.",
+        ]
+    );
+}
+
+#[test]
+fn untyped_code_is_left_alone() {
+    // reassigned variables and mixed returns could be anything
+    let source = "var a = \"s\";\nwhile (a != 1) { print a - 0; a = 1; }\nfun f(x) { if (x) return 1; return \"one\"; }\nprint f(true) - 1;\nfun g(x) { if (x) return 1; }\nprint g(1) + 1;\nvar h = 1;\nvar h = \"two\";\nprint h + \"!\";\n";
+    assert!(check(source).is_empty());
+}
+
+#[test]
+fn checking_is_optional() {
+    let path = std::env::temp_dir().join(format!("lox-types-{}.lox", std::process::id()));
+    fs::write(&path, "print 1;\nvar a: Number = \"a\";\nprint a;\n").unwrap();
+
+    let run = |args: &[&str]| Command::new(env!("CARGO_BIN_EXE_lox")).args(args).arg(&path).output().unwrap();
+    let checked = run(&["--typecheck"]);
+    let unchecked = run(&[]);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(checked.status.code(), Some(65));
+    assert!(checked.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8_lossy(&checked.stderr),
+        "[line 2] Error at 'a': Expected Number but got String.\n"
+    );
+    assert_eq!(unchecked.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&unchecked.stdout), "1\na\n");
+}
+
+#[test]
+fn formats_annotations() {
+    assert_eq!(
+        formatter::format("fun f(a : Number,b:String) :Bool { return a>0?true:false; }\nvar x:Number=1;\n").unwrap(),
+        "fun f(a: Number, b: String): Bool {\n  return a > 0 ? true : false;\n}\nvar x: Number = 1;\n"
+    );
+}
+
+#[test]
+fn checks_defaults_and_named_arguments() {
+    let source = "fun f(a: Number, b: String = 1, ...rest) {}\nf(1, \"a\", 2, 3);\nf();\nf(1, b: 2);\nf(1, c: 2);\nf(1, a: 2);\n";
+
+    assert_eq!(
+        check(source),
+        [
+            "[line 1] Error at 'b': Expected String but got Number.",
+            "[line 3] Error at ')': Expected at least 1 argument but got 0.",
+            "[line 4] Error at 'b': Expected String but got Number.",
+            "[line 5] Error at ')': No parameter named 'c'.",
+            "[line 6] Error at ')': Parameter 'a' got more than one argument.",
+        ]
+    );
+}